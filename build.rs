@@ -4,11 +4,58 @@ use std::path::PathBuf;
 use std::process::Command;
 
 fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=MAGICER_SYSTEM_LIBMAGIC");
+    println!("cargo:rerun-if-env-changed=MAGICER_BUILD_JOBS");
+
+    // Dev shortcut: link the system libmagic instead of building it.
+    if env::var("MAGICER_SYSTEM_LIBMAGIC").as_deref() == Ok("1") {
+        match link_system_libmagic() {
+            Ok(version) => {
+                println!("cargo:warning=MAGICER_SYSTEM_LIBMAGIC=1: linking system libmagic {} via pkg-config (not a reproducible build)", version);
+                return;
+            }
+            Err(e) => {
+                println!("cargo:warning=MAGICER_SYSTEM_LIBMAGIC=1 but pkg-config could not find libmagic ({}); falling back to the from-source build", e);
+            }
+        }
+    }
+
+    build_from_source();
+}
+
+/// Link against the system `libmagic` (and `zlib`, if pkg-config knows it)
+/// as shared libraries. Returns the libmagic version.
+fn link_system_libmagic() -> Result<String, pkg_config::Error> {
+    let magic = pkg_config::Config::new().probe("libmagic")?;
+    if let Err(e) = pkg_config::Config::new().probe("zlib") {
+        // A shared libmagic pulls in its own zlib; only static links need it.
+        println!("cargo:warning=pkg-config could not find zlib ({}); relying on libmagic's own dependency", e);
+    }
+    Ok(magic.version)
+}
+
+/// Parallelism for `make`: `MAGICER_BUILD_JOBS`, or one job per CPU.
+fn build_jobs() -> String {
+    env::var("MAGICER_BUILD_JOBS")
+        .ok()
+        .and_then(|jobs| jobs.parse::<usize>().ok())
+        .filter(|&jobs| jobs > 0)
+        .unwrap_or_else(num_cpus::get)
+        .to_string()
+}
+
+/// Build static zlib and libmagic from pinned source tarballs into
+/// `OUT_DIR/install` (once) and link them.
+fn build_from_source() {
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let install_dir = out_dir.join("install");
     let build_dir = out_dir.join("build");
 
     if !install_dir.exists() {
+        println!("cargo:warning=Building zlib and libmagic from source; set MAGICER_SYSTEM_LIBMAGIC=1 to link the system libmagic instead");
+        let jobs = build_jobs();
+
         fs::create_dir_all(&build_dir).unwrap();
         fs::create_dir_all(&install_dir).unwrap();
 
@@ -34,7 +81,7 @@ fn main() {
         }
 
         Command::new("./configure").arg(format!("--prefix={}", install_dir.display())).arg("--static").current_dir(&zlib_src).status().unwrap();
-        Command::new("make").arg("-j").arg(num_cpus::get().to_string()).current_dir(&zlib_src).status().unwrap();
+        Command::new("make").arg("-j").arg(&jobs).current_dir(&zlib_src).status().unwrap();
         Command::new("make").arg("install").current_dir(&zlib_src).status().unwrap();
 
         // 2. Build libmagic
@@ -68,12 +115,11 @@ fn main() {
             .status()
             .unwrap();
             
-        Command::new("make").arg("-j").arg(num_cpus::get().to_string()).current_dir(&magic_src).status().unwrap();
+        Command::new("make").arg("-j").arg(&jobs).current_dir(&magic_src).status().unwrap();
         Command::new("make").arg("install").current_dir(&magic_src).status().unwrap();
     }
 
     println!("cargo:rustc-link-search=native={}/lib", install_dir.display());
     println!("cargo:rustc-link-lib=static=magic");
     println!("cargo:rustc-link-lib=static=z");
-    println!("cargo:rerun-if-changed=build.rs");
 }
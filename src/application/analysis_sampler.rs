@@ -0,0 +1,48 @@
+use crate::domain::entities::magic_result::MagicResult;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Logs every `rate`th successful analysis at info level with its detected
+/// type, size and how the content was read (`logging.sample_rate`), for a
+/// rough picture of the traffic without enabling metrics. A `rate` of 0
+/// disables sampling.
+#[derive(Debug)]
+pub struct AnalysisSampler {
+    rate: u64,
+    seen: AtomicU64,
+}
+
+impl AnalysisSampler {
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate,
+            seen: AtomicU64::new(0),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    /// Count one analysis, returning whether it is one to log.
+    pub fn sample(&self) -> bool {
+        self.rate > 0
+            && self
+                .seen
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.rate)
+    }
+
+    /// Count `result`, logging it when sampled. `mode` is how the content
+    /// was read: `memory`, `head`, `temp_file`, `mmap` or `file`.
+    pub fn record(&self, result: &MagicResult, size: u64, mode: &'static str) {
+        if self.sample() {
+            tracing::info!(
+                request_id = %result.request_id(),
+                mime_type = %result.mime_type(),
+                size,
+                analysis.mode = mode,
+                "Sampled analysis"
+            );
+        }
+    }
+}
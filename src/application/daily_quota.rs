@@ -0,0 +1,69 @@
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Successful analyses per authenticated user per day (`[auth.quota]`).
+///
+/// A day starts at `reset_hour_utc`; counts from an earlier day no longer
+/// apply. Counts are kept in memory, so a restart starts everyone afresh.
+/// The check and the count are separate, so concurrent requests from one
+/// user may overshoot the limit by the number in flight. A `limit` of 0
+/// means unlimited.
+#[derive(Debug)]
+pub struct DailyQuota {
+    limit: u64,
+    reset_hour_utc: u32,
+    used: Mutex<HashMap<String, (NaiveDate, u64)>>,
+}
+
+impl DailyQuota {
+    pub fn new(limit: u64, reset_hour_utc: u32) -> Self {
+        Self {
+            limit,
+            reset_hour_utc: reset_hour_utc.min(23),
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Whether `username` has used up the day's analyses as of `now`.
+    pub fn is_exhausted(&self, username: &str, now: DateTime<Utc>) -> bool {
+        self.is_enabled() && self.used(username, now) >= self.limit
+    }
+
+    /// Analyses `username` has run in the day containing `now`.
+    pub fn used(&self, username: &str, now: DateTime<Utc>) -> u64 {
+        let day = self.day_of(now);
+        match self.used.lock().unwrap().get(username) {
+            Some((counted_day, count)) if *counted_day == day => *count,
+            _ => 0,
+        }
+    }
+
+    /// Count one successful analysis by `username` at `now`.
+    pub fn record(&self, username: &str, now: DateTime<Utc>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let day = self.day_of(now);
+        let mut used = self.used.lock().unwrap();
+        let entry = used.entry(username.to_string()).or_insert((day, 0));
+        if entry.0 != day {
+            *entry = (day, 0);
+        }
+        entry.1 += 1;
+    }
+
+    /// When the day containing `now` ends and counts start over.
+    pub fn resets_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let next_day = self.day_of(now) + Duration::days(1);
+        Utc.from_utc_datetime(&next_day.and_hms_opt(self.reset_hour_utc, 0, 0).unwrap())
+    }
+
+    fn day_of(&self, now: DateTime<Utc>) -> NaiveDate {
+        (now - Duration::hours(i64::from(self.reset_hour_utc))).date_naive()
+    }
+}
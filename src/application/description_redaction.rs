@@ -0,0 +1,29 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Placeholder substituted for redacted metadata.
+pub const REDACTED: &str = "[redacted]";
+
+/// Image dimensions such as `800 x 600` or `1920x1080`.
+static DIMENSIONS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\d+\s?x\s?\d+\b").unwrap());
+
+/// Labelled free text copied from the file: `Author: ...`, `comment: "..."`,
+/// and the `key=value` pairs of libmagic's EXIF summary. The value runs to
+/// the next comma or closing bracket, or to the closing quote when quoted.
+static LABELLED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b(author|creator|producer|comment|title|subject|last saved by|name of creating application|software|manufacturer|model|artist|copyright|datetime|hostcomputer)(\s*[:=]\s*)("[^"]*"|[^,\]]*)"#,
+    )
+    .unwrap()
+});
+
+/// Remove embedded metadata (dimensions, author and software strings) from
+/// a libmagic description, keeping the format information around it
+/// (`analysis.redact_description_metadata`).
+pub fn redact_metadata(description: &str) -> String {
+    let description = DIMENSIONS.replace_all(description, REDACTED);
+    LABELLED
+        .replace_all(&description, format!("${{1}}${{2}}{}", REDACTED))
+        .into_owned()
+}
@@ -0,0 +1,52 @@
+use crate::domain::value_objects::mime_type::MimeType;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Detections retained by the lowercase hex SHA-256 of the analyzed content,
+/// so a client presenting `If-Content-Hash` for content seen before is
+/// answered without running libmagic again.
+///
+/// Holds at most `capacity` entries; the oldest insertion is evicted first.
+pub struct DetectionCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_hash: HashMap<String, (MimeType, String)>,
+    order: VecDeque<String>,
+}
+
+impl DetectionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn get(&self, hash: &str) -> Option<(MimeType, String)> {
+        self.entries.lock().unwrap().by_hash.get(hash).cloned()
+    }
+
+    pub fn insert(&self, hash: String, detection: (MimeType, String)) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.by_hash.insert(hash.clone(), detection).is_some() {
+            return;
+        }
+        entries.order.push_back(hash);
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.by_hash.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.entries.lock().unwrap() = Entries::default();
+    }
+}
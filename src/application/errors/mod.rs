@@ -10,7 +10,28 @@ pub enum ApplicationError {
     UnprocessableEntity(String),
     InsufficientStorage(String),
     InternalError(String),
-    Timeout,
+    ServiceUnavailable(String),
+    /// Text content in a charset outside `analysis.text.allowed_charsets`.
+    DisallowedEncoding(String),
+    /// A path naming something other than a regular file (FIFO, device,
+    /// socket, directory).
+    UnsupportedFileType(String),
+    /// Content shorter than `analysis.min_content_bytes`.
+    ContentTooShort(String),
+    /// Content whose description contains a `magic.reject_descriptions`
+    /// entry; holds the matching entry.
+    RejectedContent(String),
+    /// Detection exceeded `server.timeouts.analysis_timeout_secs` (the
+    /// configured value, in seconds) and was aborted.
+    Timeout(u64),
+    /// The client stopped sending the request body.
+    RequestTimeout,
+    /// A `Range` that selects no byte of the file.
+    RangeNotSatisfiable(String),
+    /// Content beyond a configured size cap.
+    PayloadTooLarge(String),
+    /// libmagic failed to analyze the content; holds its error message.
+    AnalysisFailed(String),
 }
 
 impl ApplicationError {
@@ -20,10 +41,33 @@ impl ApplicationError {
             Self::Unauthorized(_) => axum::http::StatusCode::UNAUTHORIZED,
             Self::Forbidden(_) => axum::http::StatusCode::FORBIDDEN,
             Self::NotFound(_) => axum::http::StatusCode::NOT_FOUND,
-            Self::UnprocessableEntity(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            Self::UnprocessableEntity(_) | Self::AnalysisFailed(_) => {
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR
+            }
             Self::InsufficientStorage(_) => axum::http::StatusCode::INSUFFICIENT_STORAGE,
             Self::InternalError(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            Self::Timeout => axum::http::StatusCode::GATEWAY_TIMEOUT,
+            Self::ServiceUnavailable(_) => axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            Self::DisallowedEncoding(_)
+            | Self::UnsupportedFileType(_)
+            | Self::ContentTooShort(_)
+            | Self::RejectedContent(_) => {
+                axum::http::StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::Timeout(_) => axum::http::StatusCode::GATEWAY_TIMEOUT,
+            Self::RequestTimeout => axum::http::StatusCode::REQUEST_TIMEOUT,
+            Self::RangeNotSatisfiable(_) => axum::http::StatusCode::RANGE_NOT_SATISFIABLE,
+            Self::PayloadTooLarge(_) => axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+impl ApplicationError {
+    /// libmagic's own account of a failed analysis, for clients that want
+    /// more than the summary in the error message.
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Self::AnalysisFailed(msg) => Some(msg),
+            _ => None,
         }
     }
 }
@@ -38,7 +82,24 @@ impl fmt::Display for ApplicationError {
             Self::UnprocessableEntity(msg) => write!(f, "Unprocessable Entity: {}", msg),
             Self::InsufficientStorage(msg) => write!(f, "Insufficient Storage: {}", msg),
             Self::InternalError(msg) => write!(f, "Internal Error: {}", msg),
-            Self::Timeout => write!(f, "Timeout"),
+            Self::ServiceUnavailable(msg) => write!(f, "Service Unavailable: {}", msg),
+            Self::DisallowedEncoding(charset) => write!(f, "Disallowed Encoding: {}", charset),
+            Self::UnsupportedFileType(msg) => write!(f, "Unsupported File Type: {}", msg),
+            Self::ContentTooShort(msg) => write!(f, "Content Too Short: {}", msg),
+            Self::RejectedContent(pattern) => {
+                write!(f, "Rejected Content: description matches \"{}\"", pattern)
+            }
+            Self::Timeout(secs) => write!(
+                f,
+                "Analysis timed out after {}s; detection was aborted",
+                secs
+            ),
+            Self::RequestTimeout => write!(f, "Request body stalled"),
+            Self::RangeNotSatisfiable(msg) => write!(f, "Range Not Satisfiable: {}", msg),
+            Self::PayloadTooLarge(msg) => write!(f, "Payload Too Large: {}", msg),
+            Self::AnalysisFailed(msg) => {
+                write!(f, "Unprocessable Entity: Analysis failed: {}", msg)
+            }
         }
     }
 }
@@ -46,11 +107,15 @@ impl fmt::Display for ApplicationError {
 impl From<DomainError> for ApplicationError {
     fn from(err: DomainError) -> Self {
         match err {
-            DomainError::ValidationError(e) => Self::BadRequest(e.to_string()),
+            DomainError::ValidationError(e) => e.into(),
             DomainError::MagicError(e) => match e {
                 crate::domain::errors::MagicError::FileNotFound(path) => {
                     Self::NotFound(format!("File not found: {}", path))
                 }
+                crate::domain::errors::MagicError::Unavailable(msg) => {
+                    Self::ServiceUnavailable(msg)
+                }
+                crate::domain::errors::MagicError::AnalysisFailed(msg) => Self::AnalysisFailed(msg),
                 _ => Self::UnprocessableEntity(e.to_string()),
             },
             DomainError::StorageError(e) => match e {
@@ -74,6 +139,8 @@ impl From<crate::domain::errors::MagicError> for ApplicationError {
             crate::domain::errors::MagicError::FileNotFound(path) => {
                 Self::NotFound(format!("File not found: {}", path))
             }
+            crate::domain::errors::MagicError::Unavailable(msg) => Self::ServiceUnavailable(msg),
+            crate::domain::errors::MagicError::AnalysisFailed(msg) => Self::AnalysisFailed(msg),
             _ => Self::UnprocessableEntity(err.to_string()),
         }
     }
@@ -81,6 +148,11 @@ impl From<crate::domain::errors::MagicError> for ApplicationError {
 
 impl From<crate::domain::errors::ValidationError> for ApplicationError {
     fn from(err: crate::domain::errors::ValidationError) -> Self {
-        Self::BadRequest(err.to_string())
+        match err {
+            crate::domain::errors::ValidationError::PermissionDenied => {
+                Self::Forbidden(err.to_string())
+            }
+            _ => Self::BadRequest(err.to_string()),
+        }
     }
 }
@@ -0,0 +1,116 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Digests that can be requested with `?hash=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Md5,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub const ALL: &'static [HashAlgorithm] = &[Self::Sha256, Self::Md5, Self::Crc32];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.name() == value)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Md5 => "md5",
+            Self::Crc32 => "crc32",
+        }
+    }
+}
+
+/// Bytes fed to the hashers per update.
+const HASH_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Computes several digests in a single pass over the content.
+#[derive(Default)]
+pub struct MultiHasher {
+    sha256: Option<Sha256>,
+    md5: Option<Md5>,
+    crc32: Option<crc32fast::Hasher>,
+}
+
+impl MultiHasher {
+    pub fn new(algorithms: &[HashAlgorithm]) -> Self {
+        let mut hasher = Self::default();
+        for algorithm in algorithms {
+            match algorithm {
+                HashAlgorithm::Sha256 => hasher.sha256 = Some(Sha256::new()),
+                HashAlgorithm::Md5 => hasher.md5 = Some(Md5::new()),
+                HashAlgorithm::Crc32 => hasher.crc32 = Some(crc32fast::Hasher::new()),
+            }
+        }
+        hasher
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(HASH_CHUNK_BYTES) {
+            if let Some(sha256) = &mut self.sha256 {
+                sha256.update(chunk);
+            }
+            if let Some(md5) = &mut self.md5 {
+                md5.update(chunk);
+            }
+            if let Some(crc32) = &mut self.crc32 {
+                crc32.update(chunk);
+            }
+        }
+    }
+
+    /// Lowercase hex digests keyed by algorithm name.
+    pub fn finalize(self) -> BTreeMap<String, String> {
+        let mut digests = BTreeMap::new();
+        if let Some(sha256) = self.sha256 {
+            digests.insert("sha256".to_string(), format!("{:x}", sha256.finalize()));
+        }
+        if let Some(md5) = self.md5 {
+            digests.insert("md5".to_string(), format!("{:x}", md5.finalize()));
+        }
+        if let Some(crc32) = self.crc32 {
+            digests.insert("crc32".to_string(), format!("{:08x}", crc32.finalize()));
+        }
+        digests
+    }
+}
+
+/// Digests of `data` for every algorithm in `algorithms`.
+pub fn digests_hex(algorithms: &[HashAlgorithm], data: &[u8]) -> BTreeMap<String, String> {
+    let mut hasher = MultiHasher::new(algorithms);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Like [`digests_hex`], streaming `file` from its start.
+pub fn digests_hex_file(
+    algorithms: &[HashAlgorithm],
+    mut file: &File,
+) -> std::io::Result<BTreeMap<String, String>> {
+    file.seek(SeekFrom::Start(0))?;
+    digests_hex_reader(algorithms, file)
+}
+
+/// Like [`digests_hex`], streaming from `reader`.
+pub fn digests_hex_reader(
+    algorithms: &[HashAlgorithm],
+    mut reader: impl Read,
+) -> std::io::Result<BTreeMap<String, String>> {
+    let mut hasher = MultiHasher::new(algorithms);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize())
+}
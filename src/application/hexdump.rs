@@ -0,0 +1,36 @@
+use std::fmt::Write;
+
+/// Bytes shown per line.
+const LINE_BYTES: usize = 16;
+
+/// Format `data` like `hexdump -C`: an 8-digit hex offset, sixteen bytes in
+/// two groups of eight, and the printable ASCII between bars, with a final
+/// line holding the total length. Repeated lines are not collapsed.
+pub fn hexdump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (index, line) in data.chunks(LINE_BYTES).enumerate() {
+        let _ = write!(out, "{:08x} ", index * LINE_BYTES);
+        for column in 0..LINE_BYTES {
+            if column % 8 == 0 {
+                out.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => {
+                    let _ = write!(out, "{:02x} ", byte);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    let _ = writeln!(out, "{:08x}", data.len());
+    out
+}
@@ -1,2 +1,9 @@
+pub mod analysis_sampler;
+pub mod description_redaction;
+pub mod detection_cache;
+pub mod daily_quota;
 pub mod errors;
+pub mod hashing;
+pub mod hexdump;
+pub mod memory_budget;
 pub mod use_cases;
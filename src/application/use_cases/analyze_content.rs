@@ -1,20 +1,279 @@
+use crate::application::analysis_sampler::AnalysisSampler;
+use crate::application::description_redaction::redact_metadata;
+use crate::application::detection_cache::DetectionCache;
 use crate::application::errors::ApplicationError;
+use crate::application::hashing::{HashAlgorithm, digests_hex, digests_hex_file};
+use crate::application::hexdump::hexdump;
+use crate::domain::entities::archive_entry::ArchiveEntry;
 use crate::domain::entities::magic_result::MagicResult;
+use crate::domain::entities::nested_type::NestedType;
+use crate::domain::errors::MagicError;
 use crate::domain::repositories::magic_repository::MagicRepository;
 use crate::domain::services::temp_storage::{TempStorageService, TemporaryFile};
+use crate::domain::services::upload_session_store::UploadSession;
+use crate::domain::value_objects::byte_range::ByteRange;
 use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+use crate::domain::value_objects::mime_type::MimeType;
 use crate::domain::value_objects::request_id::RequestId;
-use crate::infrastructure::config::server_config::ServerConfig;
+use crate::infrastructure::config::server_config::{ServerConfig, TextConfig};
+use crate::infrastructure::errors::InfrastructureError;
 use crate::infrastructure::filesystem::mmap::MmapHandler;
+use crate::infrastructure::magic::archive_peek::{self, ArchivePeek};
 use futures_util::{Stream, StreamExt};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
+/// MIME types treated as compressed containers for nested detection.
+const COMPRESSED_CONTAINER_TYPES: &[&str] = &[
+    "application/gzip",
+    "application/x-gzip",
+    "application/zstd",
+    "application/x-bzip2",
+    "application/x-xz",
+];
+
+/// What libmagic reports when it cannot identify the content.
+const INCONCLUSIVE_TYPE: &str = "application/octet-stream";
+
+/// Types replaced by `analysis.unknown_type_alias`.
+const UNKNOWN_TYPES: &[&str] = &[INCONCLUSIVE_TYPE, "inode/x-empty"];
+
+/// Signatures short enough to identify content that is below
+/// `analysis.min_content_bytes`.
+const SHORT_DETECTABLE_PREFIXES: &[&[u8]] = &[
+    b"%PDF",
+    b"\x89PNG",
+    b"GIF8",
+    b"\xFF\xD8\xFF",
+    b"PK\x03\x04",
+    b"\x1F\x8B",
+    b"\x7FELF",
+    b"#!",
+];
+
+/// Prefix of content read from a file that is examined for its charset;
+/// libmagic's default `encoding_max`.
+pub const ENCODING_PREFIX_BYTES: u64 = 64 * 1024;
+
+/// Type and description libmagic reports for zero-length input.
+pub const EMPTY_CONTENT_TYPE: &str = "application/x-empty";
+pub const EMPTY_CONTENT_DESCRIPTION: &str = "empty";
+
+/// Report `alias` in place of a type libmagic uses when it cannot identify
+/// the content.
+pub fn alias_unknown_type(result: MagicResult, alias: Option<&MimeType>) -> MagicResult {
+    match alias {
+        Some(alias) if UNKNOWN_TYPES.contains(&result.mime_type().as_str().as_str()) => {
+            result.with_alias(alias.clone())
+        }
+        _ => result,
+    }
+}
+
+/// Result for zero-length input, answered without consulting libmagic.
+pub fn empty_content_result(
+    request_id: RequestId,
+    filename: WindowsCompatibleFilename,
+) -> MagicResult {
+    MagicResult::new(
+        request_id,
+        filename,
+        MimeType::new(EMPTY_CONTENT_TYPE).expect("valid MIME type"),
+        EMPTY_CONTENT_DESCRIPTION.to_string(),
+    )
+}
+
+/// Which of `mime_type` and `description` a client asked for with
+/// `?output=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    Mime,
+    Description,
+    Both,
+}
+
+impl OutputMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "mime" => Some(Self::Mime),
+            "description" => Some(Self::Description),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    /// Whether the description cookie has to run.
+    pub fn wants_description(self) -> bool {
+        matches!(self, Self::Description | Self::Both)
+    }
+}
+
+/// Per-request analysis switches chosen by the client.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    /// Also detect the payload type of compressed uploads.
+    pub nested: bool,
+    /// Report every distinct type the content matches, to flag polyglots.
+    pub polyglot: bool,
+    /// Include the SHA-256 of the content in the result.
+    pub hash: bool,
+    /// Every digest requested with `?hash=`, reported together in
+    /// `result.hashes`.
+    pub hashes: Vec<HashAlgorithm>,
+    /// The request's `Content-Type`, used only when
+    /// `analysis.fallback_to_declared_type` is enabled and detection is
+    /// inconclusive.
+    pub declared_type: Option<MimeType>,
+    /// Explicit `?output=` selection. When unset the description is the one
+    /// returned alongside the MIME type, as before `?output=` existed.
+    pub output: Option<OutputMode>,
+    /// Include the repository's unparsed output in the result.
+    pub raw: bool,
+    /// Include libmagic's debug output for the detection.
+    pub trace: bool,
+    /// Include a hex+ASCII dump of the first `analysis.hexdump_bytes` of
+    /// the content.
+    pub hexdump: bool,
+    /// SHA-256 the client presented with `If-Content-Hash`. When it matches
+    /// the content and the detection is cached, libmagic is skipped.
+    pub if_content_hash: Option<String>,
+    /// `Range` of a path analysis, honoured when `if_range` is unset or
+    /// still matches the file's entity tag.
+    pub range: Option<ByteRange>,
+    /// `If-Range` entity tag presented with `range`.
+    pub if_range: Option<String>,
+    /// Declared length of a streamed upload, letting the temp storage
+    /// place small files apart from large ones.
+    pub size_hint: Option<u64>,
+    /// Resolve a path analysis through symlinks even when
+    /// `sandbox.deny_symlinks` refuses them.
+    pub follow_symlinks: bool,
+}
+
+impl AnalysisOptions {
+    /// Algorithms to run over the content: every requested digest, plus
+    /// SHA-256 when `hash` or `sha256` asks for it.
+    pub fn digest_algorithms(&self, sha256: bool) -> Vec<HashAlgorithm> {
+        let mut algorithms = self.hashes.clone();
+        if (self.hash || sha256) && !algorithms.contains(&HashAlgorithm::Sha256) {
+            algorithms.push(HashAlgorithm::Sha256);
+        }
+        algorithms
+    }
+
+    /// Report `digests` as `result.hash` and `result.hashes`, as requested.
+    pub fn apply_digests(
+        &self,
+        result: MagicResult,
+        digests: &BTreeMap<String, String>,
+    ) -> MagicResult {
+        let hash = self
+            .hash
+            .then(|| digests.get(HashAlgorithm::Sha256.name()).cloned())
+            .flatten();
+        let hashes = (!self.hashes.is_empty()).then(|| {
+            self.hashes
+                .iter()
+                .filter_map(|a| Some((a.name().to_string(), digests.get(a.name())?.clone())))
+                .collect()
+        });
+        result.with_hash(hash).with_hashes(hashes)
+    }
+}
+
+/// Lowercase hex SHA-256 of `data`, fed to the hasher in chunks.
+pub fn sha256_hex(data: &[u8]) -> String {
+    digests_hex(&[HashAlgorithm::Sha256], data)
+        .remove(HashAlgorithm::Sha256.name())
+        .unwrap_or_default()
+}
+
+/// Charset of text content when `[analysis.text]` asks for one, rejecting
+/// charsets outside `allowed_charsets`. Content whose charset the repository
+/// cannot tell is let through.
+pub async fn text_encoding(
+    magic_repo: &dyn MagicRepository,
+    text: &TextConfig,
+    mime_type: &MimeType,
+    data: &[u8],
+    filename: &str,
+    timeout_secs: u64,
+) -> Result<Option<String>, ApplicationError> {
+    if !text.detects_encoding() || !mime_type.is_text() {
+        return Ok(None);
+    }
+    let encoding = timeout(
+        Duration::from_secs(timeout_secs),
+        magic_repo.detect_encoding(data, filename),
+    )
+    .await
+    .map_err(|_| ApplicationError::Timeout(timeout_secs))??;
+    match encoding {
+        Some(charset) if !text.allows(&charset) => {
+            Err(ApplicationError::DisallowedEncoding(charset))
+        }
+        encoding => Ok(encoding),
+    }
+}
+
+/// Like [`text_encoding`], for content read through `file`: only its first
+/// [`ENCODING_PREFIX_BYTES`] are read, and only when a charset is wanted.
+pub async fn file_text_encoding(
+    magic_repo: &dyn MagicRepository,
+    text: &TextConfig,
+    mime_type: &MimeType,
+    file: &File,
+    filename: &str,
+    timeout_secs: u64,
+) -> Result<Option<String>, ApplicationError> {
+    if !text.detects_encoding() || !mime_type.is_text() {
+        return Ok(None);
+    }
+    let prefix = read_prefix(file, ENCODING_PREFIX_BYTES)
+        .map_err(|e| ApplicationError::InternalError(format!("Failed to read file: {}", e)))?;
+    text_encoding(magic_repo, text, mime_type, &prefix, filename, timeout_secs).await
+}
+
+/// Up to `max` bytes from the start of `file`, read without moving its
+/// offset.
+pub fn read_prefix(file: &File, max: u64) -> std::io::Result<Vec<u8>> {
+    let mut prefix = vec![0; max as usize];
+    let mut filled = 0;
+    while filled < prefix.len() {
+        match file.read_at(&mut prefix[filled..], filled as u64)? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    prefix.truncate(filled);
+    Ok(prefix)
+}
+
+/// Refuse content whose description contains any of `patterns`, compared
+/// case-insensitively (`magic.reject_descriptions`).
+pub fn reject_description(patterns: &[String], description: &str) -> Result<(), ApplicationError> {
+    let description = description.to_lowercase();
+    match patterns
+        .iter()
+        .find(|pattern| description.contains(&pattern.to_lowercase()))
+    {
+        Some(pattern) => Err(ApplicationError::RejectedContent(pattern.clone())),
+        None => Ok(()),
+    }
+}
+
 pub struct AnalyzeContentUseCase {
     magic_repo: Arc<dyn MagicRepository>,
     temp_storage: Arc<dyn TempStorageService>,
     config: Arc<ServerConfig>,
+    detection_cache: Option<DetectionCache>,
+    sampler: Arc<AnalysisSampler>,
+    unknown_type_alias: Option<MimeType>,
+    map_file: fn(&File) -> Result<MmapHandler, InfrastructureError>,
 }
 
 impl AnalyzeContentUseCase {
@@ -23,13 +282,45 @@ impl AnalyzeContentUseCase {
         temp_storage: Arc<dyn TempStorageService>,
         config: Arc<ServerConfig>,
     ) -> Self {
+        let cache_entries = config.analysis.content_hash_cache_entries;
+        let sampler = Arc::new(AnalysisSampler::new(config.logging.sample_rate));
+        let unknown_type_alias = config
+            .analysis
+            .unknown_type_alias
+            .as_deref()
+            .and_then(|alias| MimeType::try_from(alias).ok());
         Self {
             magic_repo,
             temp_storage,
             config,
+            detection_cache: (cache_entries > 0).then(|| DetectionCache::new(cache_entries)),
+            sampler,
+            unknown_type_alias,
+            map_file: MmapHandler::new,
         }
     }
 
+    /// Forget every cached detection, e.g. once the magic database has been
+    /// reloaded and may type the same content differently.
+    pub fn clear_detection_cache(&self) {
+        if let Some(cache) = &self.detection_cache {
+            cache.clear();
+        }
+    }
+
+    /// Replace the function used to map uploads into memory.
+    pub fn with_mmap(mut self, map_file: fn(&File) -> Result<MmapHandler, InfrastructureError>) -> Self {
+        self.map_file = map_file;
+        self
+    }
+
+    /// Share `sampler` with other use cases, so `logging.sample_rate`
+    /// counts every analysis rather than each kind separately.
+    pub fn with_sampler(mut self, sampler: Arc<AnalysisSampler>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
     #[tracing::instrument(
         name = "use_case.analyze_content_in_memory",
         fields(
@@ -43,18 +334,72 @@ impl AnalyzeContentUseCase {
         request_id: RequestId,
         filename: WindowsCompatibleFilename,
         stream: S,
+        options: AnalysisOptions,
     ) -> Result<MagicResult, ApplicationError>
     where
         S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
         E: std::fmt::Display,
     {
-        let buffer = self.stream_to_buffer(stream).await?;
-        if buffer.is_empty() {
-            return Err(ApplicationError::BadRequest(
-                "Content cannot be empty".to_string(),
-            ));
-        }
-        self.perform_analysis(request_id, filename, &buffer).await
+        self.with_pipeline_deadline(options.nested, async {
+            let read_start = Instant::now();
+            let buffer = self.stream_to_buffer(stream).await?;
+            let read_duration = read_start.elapsed();
+            if buffer.is_empty() {
+                return self.empty_content(request_id, filename, &options);
+            }
+            let result = self
+                .perform_analysis(request_id, filename, &buffer, options)
+                .await?;
+            self.sampler.record(&result, buffer.len() as u64, "memory");
+            Ok(result.with_read_duration(read_duration))
+        })
+        .await
+        .map(|result| result.with_description_limit(self.config.analysis.max_description_length))
+    }
+
+    /// Analyze only the first `analysis.head_bytes` of `stream` in memory,
+    /// draining and discarding the rest so nothing else touches disk. When
+    /// the content ran past the head, the result's analyzed range says how
+    /// much was examined.
+    #[tracing::instrument(
+        name = "use_case.analyze_content_head",
+        fields(
+            request_id = %request_id,
+            analysis.type = "content_head",
+        ),
+        skip(self, filename, stream),
+    )]
+    pub async fn analyze_head<S, E>(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        stream: S,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError>
+    where
+        S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
+        E: std::fmt::Display,
+    {
+        self.with_pipeline_deadline(options.nested, async {
+            let read_start = Instant::now();
+            let (head, total) = self
+                .stream_head(stream, self.config.analysis.head_bytes)
+                .await?;
+            let read_duration = read_start.elapsed();
+            if head.is_empty() {
+                return self.empty_content(request_id, filename, &options);
+            }
+            let result = self
+                .perform_analysis(request_id, filename, &head, options)
+                .await?;
+            self.sampler.record(&result, total, "head");
+            let truncated = total > head.len() as u64;
+            Ok(result
+                .with_analyzed_range(truncated.then(|| (0, head.len() as u64 - 1)))
+                .with_read_duration(read_duration))
+        })
+        .await
+        .map(|result| result.with_description_limit(self.config.analysis.max_description_length))
     }
 
     #[tracing::instrument(
@@ -70,32 +415,240 @@ impl AnalyzeContentUseCase {
         request_id: RequestId,
         filename: WindowsCompatibleFilename,
         stream: S,
+        options: AnalysisOptions,
     ) -> Result<MagicResult, ApplicationError>
     where
         S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
         E: std::fmt::Display,
     {
-        let mut tf = self.stream_to_file(stream).await?;
+        self.with_pipeline_deadline(options.nested, async {
+            let read_start = Instant::now();
+            let mut tf = self.stream_to_file(stream, options.size_hint).await?;
+            tf.sync().await.map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to sync temp file: {}", e))
+            })?;
+
+            let file = tf.read_handle().await.map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to open file for analysis: {}", e))
+            })?;
+
+            self.analyze_file_handle(request_id, filename, &file, read_start, options)
+                .await
+        })
+        .await
+        .map(|result| result.with_description_limit(self.config.analysis.max_description_length))
+    }
+
+    /// Drain `stream` into a temp file kept for later analysis with
+    /// [`Self::analyze_upload`]. Returns the file, which is deleted when
+    /// dropped, and a read handle on its content.
+    pub async fn store_upload<S, E>(
+        &self,
+        stream: S,
+    ) -> Result<(Box<dyn TemporaryFile>, std::fs::File), ApplicationError>
+    where
+        S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
+        E: std::fmt::Display,
+    {
+        let mut tf = self.stream_to_file(stream, None).await?;
         tf.sync().await.map_err(|e| {
             ApplicationError::InternalError(format!("Failed to sync temp file: {}", e))
         })?;
+        let handle = tf.read_handle().await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to open uploaded file: {}", e))
+        })?;
+        Ok((tf, handle))
+    }
 
-        let file = std::fs::File::open(tf.path()).map_err(|e| {
-            ApplicationError::InternalError(format!("Failed to open file for analysis: {}", e))
+    #[tracing::instrument(
+        name = "use_case.analyze_upload",
+        fields(
+            request_id = %request_id,
+            analysis.type = "upload",
+        ),
+        skip(self, filename, file),
+    )]
+    pub async fn analyze_upload(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        file: std::fs::File,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        self.with_pipeline_deadline(options.nested, async {
+            self.analyze_file_handle(request_id, filename, &file, Instant::now(), options)
+                .await
+        })
+        .await
+        .map(|result| result.with_description_limit(self.config.analysis.max_description_length))
+    }
+
+    /// Temp file for a new upload session, subject to the same free-space
+    /// check as a streamed upload.
+    pub async fn start_session(&self) -> Result<Box<dyn TemporaryFile>, ApplicationError> {
+        self.init_temp_file(None).await
+    }
+
+    /// Append `stream` to the session's content. Fails with
+    /// `PayloadTooLarge` before the content would exceed
+    /// `sessions.max_size_mb`; chunks written earlier in the same call are
+    /// kept, so callers should discard the session.
+    pub async fn append_to_session<S, E>(
+        &self,
+        session: &mut UploadSession,
+        mut stream: S,
+    ) -> Result<(), ApplicationError>
+    where
+        S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
+        E: std::fmt::Display,
+    {
+        let max_size_mb = self.config.sessions.max_size_mb;
+        while let Some(chunk_result) = self.next_chunk(&mut stream).await? {
+            let chunk = chunk_result.map_err(|e| ApplicationError::BadRequest(e.to_string()))?;
+            let size = session.size + chunk.len() as u64;
+            if size > max_size_mb * 1024 * 1024 {
+                return Err(ApplicationError::PayloadTooLarge(format!(
+                    "Session content exceeds {}MB",
+                    max_size_mb
+                )));
+            }
+            session.file.write(&chunk).await.map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to write chunk: {}", e))
+            })?;
+            session.size = size;
+        }
+        Ok(())
+    }
+
+    /// Read handle on everything appended to the session so far, for
+    /// [`Self::analyze_upload`]. The session stays open for further appends.
+    pub async fn session_content(
+        &self,
+        session: &mut UploadSession,
+    ) -> Result<std::fs::File, ApplicationError> {
+        session.file.sync().await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to sync temp file: {}", e))
         })?;
+        session.file.read_handle().await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to open session file: {}", e))
+        })
+    }
 
-        let mmap = MmapHandler::new(&file).map_err(|e| {
+    /// Analyze an upload already written to `file`. libmagic reads the open
+    /// descriptor itself; the file is mapped only when an option needs the
+    /// whole content in memory.
+    async fn analyze_file_handle(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        file: &File,
+        read_start: Instant,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        if self.needs_buffer(&options) {
+            return self
+                .analyze_mapped(request_id, filename, file, read_start, options)
+                .await;
+        }
+
+        let size = file
+            .metadata()
+            .map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to stat file for analysis: {}", e))
+            })?
+            .len();
+        let read_duration = read_start.elapsed();
+        if size == 0 {
+            return self.empty_content(request_id, filename, &options);
+        }
+
+        let result = self
+            .perform_file_analysis(request_id, filename, file, size, options)
+            .await?;
+        self.sampler.record(&result, size, "temp_file");
+        Ok(result.with_read_duration(read_duration))
+    }
+
+    /// Whether `options` need the content as a buffer: the description
+    /// cookie, nested, trace and polyglot detection, archive peeking, and
+    /// the content-hash cache all run over memory.
+    fn needs_buffer(&self, options: &AnalysisOptions) -> bool {
+        options.nested
+            || options.polyglot
+            || options.trace
+            || options.output.is_some_and(OutputMode::wants_description)
+            || self.config.analysis.peek_archives
+            || self.detection_cache.is_some()
+    }
+
+    async fn analyze_mapped(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        file: &File,
+        read_start: Instant,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        let mmap = (self.map_file)(file).map_err(|e| {
             ApplicationError::InternalError(format!("Failed to mmap file for analysis: {}", e))
         })?;
 
+        let read_duration = read_start.elapsed();
+
         if mmap.as_slice().is_empty() {
-            return Err(ApplicationError::BadRequest(
+            return self.empty_content(request_id, filename, &options);
+        }
+
+        if options.hash || !options.hashes.is_empty() {
+            mmap.advise_sequential();
+        }
+        let result = self
+            .perform_analysis(request_id, filename, mmap.as_slice(), options)
+            .await?;
+        self.sampler
+            .record(&result, mmap.as_slice().len() as u64, "temp_file");
+        Ok(result.with_read_duration(read_duration))
+    }
+
+    /// Empty uploads are rejected unless `analysis.allow_empty` is set.
+    fn empty_content(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        options: &AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        if self.config.analysis.allow_empty {
+            let digests = digests_hex(&options.digest_algorithms(false), &[]);
+            Ok(options.apply_digests(empty_content_result(request_id, filename), &digests))
+        } else {
+            Err(ApplicationError::BadRequest(
                 "Content cannot be empty".to_string(),
-            ));
+            ))
         }
+    }
 
-        self.perform_analysis(request_id, filename, mmap.as_slice())
+    /// Bound the whole pipeline (body draining + detection) by
+    /// `analysis_timeout_secs`, so a client trickling its body cannot keep the
+    /// request alive past the analysis budget. The detection-only timeout in
+    /// [`Self::perform_analysis`] remains as an inner bound.
+    ///
+    /// Nested analysis runs after detection, so the longest `magic.timeouts`
+    /// override is added to the budget when it was requested.
+    async fn with_pipeline_deadline<F>(
+        &self,
+        nested: bool,
+        pipeline: F,
+    ) -> Result<MagicResult, ApplicationError>
+    where
+        F: Future<Output = Result<MagicResult, ApplicationError>>,
+    {
+        let mut timeout_secs = self.config.server.timeouts.analysis_timeout_secs;
+        if nested {
+            timeout_secs += self.config.magic.max_timeout_override();
+        }
+        timeout(Duration::from_secs(timeout_secs), pipeline)
             .await
+            .map_err(|_| ApplicationError::Timeout(timeout_secs))?
     }
 
     #[tracing::instrument(
@@ -108,47 +661,400 @@ impl AnalyzeContentUseCase {
         request_id: RequestId,
         filename: WindowsCompatibleFilename,
         data: &[u8],
+        options: AnalysisOptions,
     ) -> Result<MagicResult, ApplicationError> {
+        let min_bytes = self.config.analysis.min_content_bytes;
+        if data.len() < min_bytes
+            && !SHORT_DETECTABLE_PREFIXES
+                .iter()
+                .any(|prefix| data.starts_with(prefix))
+        {
+            return Err(ApplicationError::ContentTooShort(format!(
+                "{} bytes is below the minimum of {}",
+                data.len(),
+                min_bytes
+            )));
+        }
+
         let timeout_secs = self.config.server.timeouts.analysis_timeout_secs;
+        let digests = digests_hex(
+            &options.digest_algorithms(self.detection_cache.is_some()),
+            data,
+        );
+        let content_hash = digests.get(HashAlgorithm::Sha256.name()).cloned();
+
+        let detect_start = Instant::now();
+        let cached = match (&self.detection_cache, &options.if_content_hash, &content_hash) {
+            (Some(cache), Some(presented), Some(actual))
+                if presented.eq_ignore_ascii_case(actual) =>
+            {
+                cache.get(actual)
+            }
+            _ => None,
+        };
+        let from_cache = cached.is_some();
+        let (mime_type, description) = match cached {
+            Some(detection) => detection,
+            None => {
+                let detection = match timeout(
+                    Duration::from_secs(timeout_secs),
+                    self.magic_repo.analyze_buffer(data, filename.as_str()),
+                )
+                .await
+                .map_err(|_| ApplicationError::Timeout(timeout_secs))?
+                {
+                    Ok(detection) => detection,
+                    Err(MagicError::Unavailable(msg)) => {
+                        return match self.stale_detection(content_hash.as_deref()) {
+                            Some((mime_type, description)) => self.stale_result(
+                                request_id,
+                                filename,
+                                mime_type,
+                                description,
+                                options,
+                                &digests,
+                            ),
+                            None => Err(ApplicationError::ServiceUnavailable(msg)),
+                        };
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                if let (Some(cache), Some(hash)) = (&self.detection_cache, &content_hash) {
+                    cache.insert(hash.clone(), detection.clone());
+                }
+                detection
+            }
+        };
+        let raw = options.raw.then(|| description.clone());
+        let description = match options.output {
+            Some(output) if output.wants_description() => timeout(
+                Duration::from_secs(timeout_secs),
+                self.magic_repo.describe_buffer(data, filename.as_str()),
+            )
+            .await
+            .map_err(|_| ApplicationError::Timeout(timeout_secs))??,
+            _ => description,
+        };
+        reject_description(&self.config.magic.reject_descriptions, &description)?;
+        let description = if self.config.analysis.redact_description_metadata {
+            redact_metadata(&description)
+        } else {
+            description
+        };
+        let encoding = text_encoding(
+            self.magic_repo.as_ref(),
+            &self.config.analysis.text,
+            &mime_type,
+            data,
+            filename.as_str(),
+            timeout_secs,
+        )
+        .await?;
+        let detect_duration = detect_start.elapsed();
+
+        let archive_first_entry = if self.config.analysis.peek_archives {
+            self.peek_archive(&mime_type.as_str(), data).await
+        } else {
+            None
+        };
+
+        let nested = if options.nested
+            && COMPRESSED_CONTAINER_TYPES.contains(&mime_type.as_str().as_str())
+        {
+            // The container type is only known after detection, so overrides
+            // apply to this second phase only.
+            let nested_secs = self
+                .config
+                .magic
+                .timeout_for(&mime_type)
+                .unwrap_or(timeout_secs);
+            let (inner, _) = timeout(
+                Duration::from_secs(nested_secs),
+                self.magic_repo
+                    .analyze_buffer_decompressed(data, filename.as_str()),
+            )
+            .await
+            .map_err(|_| ApplicationError::Timeout(nested_secs))??;
+            Some(NestedType::new(mime_type.clone(), inner))
+        } else {
+            None
+        };
 
+        let trace = if options.trace {
+            Some(
+                timeout(
+                    Duration::from_secs(timeout_secs),
+                    self.magic_repo.trace_buffer(data, filename.as_str()),
+                )
+                .await
+                .map_err(|_| ApplicationError::Timeout(timeout_secs))??,
+            )
+        } else {
+            None
+        };
+
+        let polyglot_types = if options.polyglot {
+            Some(
+                timeout(
+                    Duration::from_secs(timeout_secs),
+                    self.magic_repo.analyze_all_types(data, filename.as_str()),
+                )
+                .await
+                .map_err(|_| ApplicationError::Timeout(timeout_secs))??,
+            )
+        } else {
+            None
+        };
+
+        let result = MagicResult::new(request_id, filename, mime_type, description)
+            .with_polyglot_types(polyglot_types)
+            .with_archive_first_entry(archive_first_entry)
+            .with_nested(nested)
+            .with_raw(raw)
+            .with_trace(trace)
+            .with_hexdump(options.hexdump.then(|| {
+                hexdump(&data[..data.len().min(self.config.analysis.hexdump_bytes())])
+            }))
+            .with_encoding(encoding)
+            .with_cached(from_cache)
+            .with_database_variant(self.magic_repo.database_variant(data))
+            .with_detect_duration(detect_duration);
+        let result = options.apply_digests(result, &digests);
+        let result = self.fall_back_to_declared(result, options.declared_type);
+        let textual = self.config.analysis.text.is_textual(result.mime_type());
+        Ok(alias_unknown_type(
+            result.with_textual(textual),
+            self.unknown_type_alias.as_ref(),
+        ))
+    }
+
+    /// [`Self::perform_analysis`] for content in `file`, `size` bytes long,
+    /// that libmagic reads through the descriptor. Only a bounded prefix is
+    /// read into memory, for the minimum-length check, the charset and the
+    /// hexdump; digests stream over the file.
+    #[tracing::instrument(
+        name = "repo.analyze_file",
+        fields(request_id = %request_id),
+        skip(self, filename, file),
+    )]
+    async fn perform_file_analysis(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        file: &File,
+        size: u64,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        let read_error =
+            |e: std::io::Error| ApplicationError::InternalError(format!("Failed to read file: {}", e));
+        let min_bytes = self.config.analysis.min_content_bytes;
+        if size < min_bytes as u64 {
+            let data = read_prefix(file, size).map_err(read_error)?;
+            if !SHORT_DETECTABLE_PREFIXES
+                .iter()
+                .any(|prefix| data.starts_with(prefix))
+            {
+                return Err(ApplicationError::ContentTooShort(format!(
+                    "{} bytes is below the minimum of {}",
+                    size, min_bytes
+                )));
+            }
+        }
+
+        let timeout_secs = self.config.server.timeouts.analysis_timeout_secs;
+        let detect_start = Instant::now();
         let (mime_type, description) = timeout(
             Duration::from_secs(timeout_secs),
-            self.magic_repo.analyze_buffer(data, filename.as_str()),
+            self.magic_repo.analyze_file(file, filename.as_str()),
         )
         .await
-        .map_err(|_| ApplicationError::Timeout)??;
+        .map_err(|_| ApplicationError::Timeout(timeout_secs))??;
+        let raw = options.raw.then(|| description.clone());
+        reject_description(&self.config.magic.reject_descriptions, &description)?;
+        let description = if self.config.analysis.redact_description_metadata {
+            redact_metadata(&description)
+        } else {
+            description
+        };
+        let encoding = file_text_encoding(
+            self.magic_repo.as_ref(),
+            &self.config.analysis.text,
+            &mime_type,
+            file,
+            filename.as_str(),
+            timeout_secs,
+        )
+        .await?;
+        let detect_duration = detect_start.elapsed();
 
-        Ok(MagicResult::new(
-            request_id,
-            filename,
-            mime_type,
-            description,
+        let hexdump = if options.hexdump {
+            let dumped = read_prefix(file, self.config.analysis.hexdump_bytes() as u64)
+                .map_err(read_error)?;
+            Some(hexdump(&dumped))
+        } else {
+            None
+        };
+        let algorithms = options.digest_algorithms(false);
+        let digests = if algorithms.is_empty() {
+            BTreeMap::new()
+        } else {
+            digests_hex_file(&algorithms, file).map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to hash file: {}", e))
+            })?
+        };
+
+        let result = MagicResult::new(request_id, filename, mime_type, description)
+            .with_raw(raw)
+            .with_hexdump(hexdump)
+            .with_encoding(encoding)
+            .with_database_variant(self.magic_repo.database_variant_for_file(file))
+            .with_detect_duration(detect_duration);
+        let result = options.apply_digests(result, &digests);
+        let result = self.fall_back_to_declared(result, options.declared_type);
+        let textual = self.config.analysis.text.is_textual(result.mime_type());
+        Ok(alias_unknown_type(
+            result.with_textual(textual),
+            self.unknown_type_alias.as_ref(),
+        ))
+    }
+
+    /// A previous detection of identical content, for when libmagic is
+    /// unavailable (typically the circuit breaker is open).
+    fn stale_detection(&self, content_hash: Option<&str>) -> Option<(MimeType, String)> {
+        self.detection_cache.as_ref()?.get(content_hash?)
+    }
+
+    /// Answer from a cached detection while libmagic is unavailable. Only
+    /// the cached type and description are served: every extra that would
+    /// need libmagic (encoding, nested, trace, polyglot) is left out.
+    fn stale_result(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        mime_type: MimeType,
+        description: String,
+        options: AnalysisOptions,
+        digests: &BTreeMap<String, String>,
+    ) -> Result<MagicResult, ApplicationError> {
+        reject_description(&self.config.magic.reject_descriptions, &description)?;
+        tracing::warn!(
+            request_id = %request_id,
+            mime_type = %mime_type,
+            "libmagic unavailable, serving stale cached detection"
+        );
+        let description = if self.config.analysis.redact_description_metadata {
+            redact_metadata(&description)
+        } else {
+            description
+        };
+        let result = MagicResult::new(request_id, filename, mime_type, description)
+            .with_cached(true)
+            .with_stale(true);
+        let result = options.apply_digests(result, digests);
+        let result = self.fall_back_to_declared(result, options.declared_type);
+        let textual = self.config.analysis.text.is_textual(result.mime_type());
+        Ok(alias_unknown_type(
+            result.with_textual(textual),
+            self.unknown_type_alias.as_ref(),
         ))
     }
 
+    /// Substitute the client's declared type for an inconclusive detection.
+    /// A confident detection is never overridden.
+    fn fall_back_to_declared(&self, result: MagicResult, declared: Option<MimeType>) -> MagicResult {
+        match declared {
+            Some(declared)
+                if self.config.analysis.fallback_to_declared_type
+                    && result.mime_type().as_str() == INCONCLUSIVE_TYPE
+                    && declared.as_str() != INCONCLUSIVE_TYPE =>
+            {
+                result.with_declared_type(declared)
+            }
+            _ => result,
+        }
+    }
+
+    /// Identify the first member of a zip/tar archive from its header and a
+    /// bounded prefix of its content. Failures are not fatal: the archive
+    /// itself has already been identified.
+    async fn peek_archive(&self, mime_type: &str, data: &[u8]) -> Option<ArchiveEntry> {
+        let ArchivePeek { name, prefix } = match mime_type {
+            "application/zip" => archive_peek::peek_zip(data)?,
+            "application/x-tar" => archive_peek::peek_tar(data)?,
+            _ => return None,
+        };
+
+        match self.magic_repo.analyze_buffer(&prefix, &name).await {
+            Ok((entry_mime, _)) => Some(ArchiveEntry::new(name, entry_mime)),
+            Err(e) => {
+                tracing::debug!(error = %e, "Failed to identify first archive entry");
+                None
+            }
+        }
+    }
+
+    /// Await the next body chunk, failing with `RequestTimeout` when the
+    /// client sends nothing for `stream_idle_secs`. Unlike the pipeline
+    /// deadline this catches uploads that trickle a byte every few seconds.
+    async fn next_chunk<S, E>(
+        &self,
+        stream: &mut S,
+    ) -> Result<Option<Result<bytes::Bytes, E>>, ApplicationError>
+    where
+        S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
+    {
+        let idle_secs = self.config.server.timeouts.stream_idle_secs;
+        timeout(Duration::from_secs(idle_secs), stream.next())
+            .await
+            .map_err(|_| ApplicationError::RequestTimeout)
+    }
+
     async fn stream_to_buffer<S, E>(&self, mut stream: S) -> Result<Vec<u8>, ApplicationError>
     where
         S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
         E: std::fmt::Display,
     {
         let mut buffer = Vec::new();
-        while let Some(chunk_result) = stream.next().await {
+        while let Some(chunk_result) = self.next_chunk(&mut stream).await? {
             let chunk = chunk_result.map_err(|e| ApplicationError::BadRequest(e.to_string()))?;
             buffer.extend_from_slice(&chunk);
         }
         Ok(buffer)
     }
 
+    /// Keep the first `limit` bytes of `stream`, reading the rest only to
+    /// discard it. Returns the head and the total length of the content.
+    async fn stream_head<S, E>(
+        &self,
+        mut stream: S,
+        limit: usize,
+    ) -> Result<(Vec<u8>, u64), ApplicationError>
+    where
+        S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
+        E: std::fmt::Display,
+    {
+        let mut head = Vec::new();
+        let mut total = 0u64;
+        while let Some(chunk_result) = self.next_chunk(&mut stream).await? {
+            let chunk = chunk_result.map_err(|e| ApplicationError::BadRequest(e.to_string()))?;
+            let room = limit.saturating_sub(head.len());
+            head.extend_from_slice(&chunk[..chunk.len().min(room)]);
+            total += chunk.len() as u64;
+        }
+        Ok((head, total))
+    }
+
     async fn stream_to_file<S, E>(
         &self,
         mut stream: S,
+        size_hint: Option<u64>,
     ) -> Result<Box<dyn TemporaryFile>, ApplicationError>
     where
         S: Stream<Item = Result<bytes::Bytes, E>> + Unpin + Send,
         E: std::fmt::Display,
     {
-        let mut tf = self.init_temp_file().await?;
-        while let Some(chunk_result) = stream.next().await {
+        let mut tf = self.init_temp_file(size_hint).await?;
+        while let Some(chunk_result) = self.next_chunk(&mut stream).await? {
             let chunk = chunk_result.map_err(|e| ApplicationError::BadRequest(e.to_string()))?;
             tf.write(&chunk).await.map_err(|e| {
                 ApplicationError::InternalError(format!("Failed to write chunk: {}", e))
@@ -157,7 +1063,10 @@ impl AnalyzeContentUseCase {
         Ok(tf)
     }
 
-    async fn init_temp_file(&self) -> Result<Box<dyn TemporaryFile>, ApplicationError> {
+    async fn init_temp_file(
+        &self,
+        size_hint: Option<u64>,
+    ) -> Result<Box<dyn TemporaryFile>, ApplicationError> {
         let free_space = self
             .config
             .get_free_space_mb(&self.config.analysis.temp_dir);
@@ -168,8 +1077,11 @@ impl AnalyzeContentUseCase {
             )));
         }
 
-        self.temp_storage.create_temp_file().await.map_err(|e| {
-            ApplicationError::InternalError(format!("Failed to create temp file: {}", e))
-        })
+        self.temp_storage
+            .create_temp_file_sized(size_hint)
+            .await
+            .map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to create temp file: {}", e))
+            })
     }
 }
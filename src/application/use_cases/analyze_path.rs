@@ -1,19 +1,65 @@
+use crate::application::analysis_sampler::AnalysisSampler;
+use crate::application::description_redaction::redact_metadata;
 use crate::application::errors::ApplicationError;
+use crate::application::hashing::{digests_hex, digests_hex_file};
+use crate::application::use_cases::analyze_content::{
+    AnalysisOptions, alias_unknown_type, empty_content_result, file_text_encoding, read_prefix,
+    reject_description, text_encoding,
+};
 use crate::domain::entities::magic_result::MagicResult;
 use crate::domain::repositories::magic_repository::MagicRepository;
 use crate::domain::services::sandbox_service::SandboxService;
 use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+use crate::domain::value_objects::mime_type::MimeType;
 use crate::domain::value_objects::path::RelativePath;
 use crate::domain::value_objects::request_id::RequestId;
+use crate::infrastructure::config::server_config::TextConfig;
+use crate::infrastructure::errors::InfrastructureError;
 use crate::infrastructure::filesystem::mmap::MmapHandler;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs::{File, FileType};
+use std::os::unix::fs::{FileExt, FileTypeExt, OpenOptionsExt};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 
+/// Bytes read from the start of a file for `output=description` in file
+/// mode, libmagic's traditional `bytes_max`.
+const DESCRIBE_PREFIX_BYTES: u64 = 1024 * 1024;
+
+/// A sandbox file opened for analysis, with the metadata of that descriptor.
+pub struct OpenedFile {
+    file: File,
+    metadata: std::fs::Metadata,
+    opened_at: Instant,
+}
+
+impl OpenedFile {
+    /// Modification time of the opened file, for answering conditional
+    /// requests without analyzing it.
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.metadata.modified().ok().map(DateTime::<Utc>::from)
+    }
+}
+
 pub struct AnalyzePathUseCase {
     magic_repo: Arc<dyn MagicRepository>,
     sandbox: Arc<dyn SandboxService>,
     analysis_timeout_secs: u64,
+    allow_empty: bool,
+    max_description_length: Option<usize>,
+    mmap_fallback_enabled: bool,
+    use_mmap: bool,
+    retry_on_truncation: bool,
+    map_file: fn(&File) -> Result<MmapHandler, InfrastructureError>,
+    open_file: fn(&Path) -> std::io::Result<File>,
+    text: TextConfig,
+    redact_description_metadata: bool,
+    sampler: Arc<AnalysisSampler>,
+    unknown_type_alias: Option<MimeType>,
+    reject_descriptions: Vec<String>,
 }
 
 impl AnalyzePathUseCase {
@@ -26,50 +72,574 @@ impl AnalyzePathUseCase {
             magic_repo,
             sandbox,
             analysis_timeout_secs,
+            allow_empty: false,
+            max_description_length: None,
+            mmap_fallback_enabled: true,
+            use_mmap: true,
+            retry_on_truncation: false,
+            map_file: MmapHandler::new,
+            open_file: open_for_analysis,
+            text: TextConfig::default(),
+            redact_description_metadata: false,
+            sampler: Arc::new(AnalysisSampler::disabled()),
+            unknown_type_alias: None,
+            reject_descriptions: Vec::new(),
+        }
+    }
+
+    /// Classify zero-length files as `application/x-empty` without calling
+    /// libmagic (`analysis.allow_empty`).
+    pub fn with_allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+
+    /// Truncate long descriptions (`analysis.max_description_length`).
+    pub fn with_max_description_length(mut self, max: Option<usize>) -> Self {
+        self.max_description_length = max;
+        self
+    }
+
+    /// Let libmagic read the file itself when mapping it fails with `ENOMEM`
+    /// (`analysis.mmap_fallback_enabled`).
+    pub fn with_mmap_fallback(mut self, enabled: bool) -> Self {
+        self.mmap_fallback_enabled = enabled;
+        self
+    }
+
+    /// Never map files; libmagic reads every file through its path
+    /// (`analysis.use_mmap`). For filesystems where mmap is unreliable.
+    pub fn with_use_mmap(mut self, enabled: bool) -> Self {
+        self.use_mmap = enabled;
+        self
+    }
+
+    /// Analyze once more in file mode when the file shrank while it was
+    /// mapped (`analysis.retry_on_truncation`).
+    pub fn with_retry_on_truncation(mut self, enabled: bool) -> Self {
+        self.retry_on_truncation = enabled;
+        self
+    }
+
+    /// Charset detection and allowlist for text files (`[analysis.text]`).
+    pub fn with_text_config(mut self, text: TextConfig) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Strip embedded metadata from descriptions
+    /// (`analysis.redact_description_metadata`).
+    pub fn with_description_redaction(mut self, enabled: bool) -> Self {
+        self.redact_description_metadata = enabled;
+        self
+    }
+
+    /// Report `alias` instead of an inconclusive type
+    /// (`analysis.unknown_type_alias`).
+    pub fn with_unknown_type_alias(mut self, alias: Option<MimeType>) -> Self {
+        self.unknown_type_alias = alias;
+        self
+    }
+
+    /// Refuse files whose description contains any of `patterns`
+    /// (`magic.reject_descriptions`).
+    pub fn with_reject_descriptions(mut self, patterns: Vec<String>) -> Self {
+        self.reject_descriptions = patterns;
+        self
+    }
+
+    /// Log a sample of analyses (`logging.sample_rate`).
+    pub fn with_sampler(mut self, sampler: Arc<AnalysisSampler>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    /// Replace the function used to map files into memory.
+    pub fn with_mmap(
+        mut self,
+        map_file: fn(&File) -> Result<MmapHandler, InfrastructureError>,
+    ) -> Self {
+        self.map_file = map_file;
+        self
+    }
+
+    /// Replace the function used to open files for analysis.
+    pub fn with_open(mut self, open_file: fn(&Path) -> std::io::Result<File>) -> Self {
+        self.open_file = open_file;
+        self
+    }
+
+    pub async fn execute(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        path: RelativePath,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        let opened = self.open(&path, options.follow_symlinks)?;
+        self.execute_opened(request_id, filename, path, opened, options)
+            .await
+    }
+
+    /// Open the regular file at `path` for [`Self::execute_opened`].
+    /// `follow_symlinks` resolves the path through symlinks the sandbox
+    /// would otherwise refuse.
+    pub fn open(
+        &self,
+        path: &RelativePath,
+        follow_symlinks: bool,
+    ) -> Result<OpenedFile, ApplicationError> {
+        let resolved_path = if follow_symlinks {
+            self.sandbox.resolve_path_following_symlinks(path)?
+        } else {
+            self.sandbox.resolve_path(path)?
+        };
+
+        let opened_at = Instant::now();
+        let file = (self.open_file)(&resolved_path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                ApplicationError::NotFound(resolved_path.to_string_lossy().to_string())
+            } else {
+                ApplicationError::InternalError(format!("Failed to open file for analysis: {}", e))
+            }
+        })?;
+        let metadata = file.metadata().map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to stat file for analysis: {}", e))
+        })?;
+        if !metadata.is_file() {
+            return Err(ApplicationError::UnsupportedFileType(format!(
+                "{} is a {}, not a regular file",
+                path.as_str(),
+                file_type_name(metadata.file_type())
+            )));
         }
+        Ok(OpenedFile {
+            file,
+            metadata,
+            opened_at,
+        })
     }
 
+    /// Analyze a file returned by [`Self::open`] for `path`. The file is
+    /// opened once: its type, size, modification time and content all come
+    /// from this descriptor, so they describe the same file even if the path
+    /// is replaced meanwhile.
     #[tracing::instrument(
         name = "use_case.analyze_path",
         fields(
             request_id = %request_id,
             analysis.type = "path",
         ),
-        skip(self, filename, path),
+        skip(self, filename, path, opened),
     )]
-    pub async fn execute(
+    pub async fn execute_opened(
         &self,
         request_id: RequestId,
         filename: WindowsCompatibleFilename,
         path: RelativePath,
+        opened: OpenedFile,
+        options: AnalysisOptions,
     ) -> Result<MagicResult, ApplicationError> {
-        let resolved_path = self.sandbox.resolve_path(&path)?;
+        let OpenedFile {
+            file,
+            metadata,
+            opened_at: read_start,
+        } = opened;
+        let modified_at = metadata.modified().ok().map(DateTime::<Utc>::from);
+        let entity_tag = entity_tag(&metadata);
+        // A stale If-Range means the client's range may no longer point at
+        // what it expects, so the whole file is analyzed instead.
+        let range = match options.range {
+            Some(range)
+                if options
+                    .if_range
+                    .as_deref()
+                    .is_none_or(|tag| tag == entity_tag) =>
+            {
+                Some(range.resolve(metadata.len()).ok_or_else(|| {
+                    ApplicationError::RangeNotSatisfiable(format!(
+                        "{} is {} bytes",
+                        path.as_str(),
+                        metadata.len()
+                    ))
+                })?)
+            }
+            _ => None,
+        };
 
-        let file = std::fs::File::open(&resolved_path).map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                ApplicationError::NotFound(resolved_path.to_string_lossy().to_string())
-            } else {
-                ApplicationError::InternalError(format!("Failed to open file for analysis: {}", e))
+        if !self.use_mmap {
+            return self
+                .execute_unmapped(request_id, filename, path, &file, range, options)
+                .await
+                .map(|result| {
+                    result
+                        .with_modified_at(modified_at)
+                        .with_entity_tag(Some(entity_tag))
+                        .with_read_duration(read_start.elapsed())
+                });
+        }
+
+        let mmap = match (self.map_file)(&file) {
+            Ok(mmap) => mmap,
+            Err(InfrastructureError::Io(e))
+                if self.mmap_fallback_enabled && e.raw_os_error() == Some(libc::ENOMEM) =>
+            {
+                tracing::warn!(
+                    path = %path.as_str(),
+                    "mmap failed with ENOMEM, falling back to file-mode detection"
+                );
+                return self
+                    .execute_unmapped(request_id, filename, path, &file, range, options)
+                    .await
+                    .map(|result| {
+                        result
+                            .with_modified_at(modified_at)
+                            .with_entity_tag(Some(entity_tag))
+                            .with_read_duration(read_start.elapsed())
+                    });
             }
-        })?;
+            Err(e) => {
+                return Err(ApplicationError::InternalError(format!(
+                    "Failed to mmap file for analysis: {}",
+                    e
+                )));
+            }
+        };
+        MmapHandler::clear_sigbus_flag();
+        let read_duration = read_start.elapsed();
+        // A writer truncated the file while it was mapped, so pages past the
+        // new end fault when read. Checked before each pass over the mapping;
+        // file mode then reads only what is there now. A range analysis is
+        // not retried since its range may be gone.
+        let mapped_len = mmap.as_slice().len() as u64;
+        let shrank = || {
+            self.retry_on_truncation
+                && range.is_none()
+                && (MmapHandler::check_sigbus_flag()
+                    || file.metadata().is_ok_and(|m| m.len() < mapped_len))
+        };
+        if shrank() {
+            drop(mmap);
+            return self
+                .retry_unmapped(request_id, filename, path, &file, options, read_start)
+                .await;
+        }
+        let content = match range {
+            // The file may have shrunk since it was stat'ed.
+            Some((start, end)) => mmap
+                .as_slice()
+                .get(start as usize..=end as usize)
+                .ok_or_else(|| {
+                    ApplicationError::RangeNotSatisfiable(format!(
+                        "{} changed size during analysis",
+                        path.as_str()
+                    ))
+                })?,
+            None => mmap.as_slice(),
+        };
 
-        let mmap = MmapHandler::new(&file).map_err(|e| {
-            ApplicationError::InternalError(format!("Failed to mmap file for analysis: {}", e))
-        })?;
+        if self.allow_empty && content.is_empty() {
+            let digests = digests_hex(&options.digest_algorithms(false), &[]);
+            return Ok(options
+                .apply_digests(empty_content_result(request_id, filename), &digests)
+                .with_modified_at(modified_at)
+                .with_entity_tag(Some(entity_tag))
+                .with_source_path(Some(path.as_str().to_string()))
+                .with_read_duration(read_duration)
+                .with_description_limit(self.max_description_length));
+        }
 
+        let detect_start = Instant::now();
         let (mime_type, description) = timeout(
             Duration::from_secs(self.analysis_timeout_secs),
             self.magic_repo
-                .analyze_buffer(mmap.as_slice(), filename.as_str()),
+                .analyze_buffer(content, filename.as_str()),
         )
         .await
-        .map_err(|_| ApplicationError::Timeout)??;
-
-        Ok(MagicResult::new(
-            request_id,
-            filename,
-            mime_type,
-            description,
-        ))
+        .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??;
+        if shrank() {
+            drop(mmap);
+            return self
+                .retry_unmapped(request_id, filename, path, &file, options, read_start)
+                .await;
+        }
+        let raw = options.raw.then(|| description.clone());
+        let description = match options.output {
+            Some(output) if output.wants_description() => timeout(
+                Duration::from_secs(self.analysis_timeout_secs),
+                self.magic_repo
+                    .describe_buffer(content, filename.as_str()),
+            )
+            .await
+            .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??,
+            _ => description,
+        };
+        reject_description(&self.reject_descriptions, &description)?;
+        let description = self.redact(description);
+        let encoding = text_encoding(
+            self.magic_repo.as_ref(),
+            &self.text,
+            &mime_type,
+            content,
+            filename.as_str(),
+            self.analysis_timeout_secs,
+        )
+        .await?;
+        let detect_duration = detect_start.elapsed();
+
+        let algorithms = options.digest_algorithms(false);
+        if !algorithms.is_empty() {
+            mmap.advise_sequential();
+        }
+        let digests = digests_hex(&algorithms, content);
+
+        let textual = self.text.is_textual(&mime_type);
+        let result = MagicResult::new(request_id, filename, mime_type, description);
+        let result = options
+            .apply_digests(result, &digests)
+            .with_raw(raw)
+            .with_encoding(encoding)
+            .with_textual(textual)
+            .with_database_variant(self.magic_repo.database_variant(content))
+            .with_modified_at(modified_at)
+            .with_entity_tag(Some(entity_tag))
+            .with_analyzed_range(range)
+            .with_source_path(Some(path.as_str().to_string()))
+            .with_read_duration(read_duration)
+            .with_detect_duration(detect_duration)
+            .with_description_limit(self.max_description_length);
+        let result = alias_unknown_type(result, self.unknown_type_alias.as_ref());
+        self.sampler.record(&result, content.len() as u64, "mmap");
+        Ok(result)
+    }
+
+    /// Re-run an analysis in file mode after the mapped file shrank. The
+    /// modification time and entity tag are taken again from the descriptor
+    /// since the ones seen at open time describe the old content.
+    async fn retry_unmapped(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        path: RelativePath,
+        file: &File,
+        options: AnalysisOptions,
+        read_start: Instant,
+    ) -> Result<MagicResult, ApplicationError> {
+        tracing::warn!(
+            path = %path.as_str(),
+            "File shrank during analysis, retrying in file mode"
+        );
+        let metadata = file.metadata().map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to stat file for analysis: {}", e))
+        })?;
+        self.execute_unmapped(request_id, filename, path, file, None, options)
+            .await
+            .map(|result| {
+                result
+                    .with_modified_at(metadata.modified().ok().map(DateTime::<Utc>::from))
+                    .with_entity_tag(Some(entity_tag(&metadata)))
+                    .with_read_duration(read_start.elapsed())
+            })
+    }
+
+    /// Detection without a memory mapping: libmagic reads the file through
+    /// the open descriptor. `output=description` runs the description cookie
+    /// over the first [`DESCRIBE_PREFIX_BYTES`]. A resolved `range` is read
+    /// into memory and only it is analyzed.
+    async fn execute_unmapped(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        path: RelativePath,
+        file: &File,
+        range: Option<(u64, u64)>,
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        if let Some((start, end)) = range {
+            let mut slice = vec![0; (end - start + 1) as usize];
+            file.read_exact_at(&mut slice, start).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                    // The file may have shrunk since it was stat'ed.
+                    ApplicationError::RangeNotSatisfiable(format!(
+                        "{} changed size during analysis",
+                        path.as_str()
+                    ))
+                } else {
+                    ApplicationError::InternalError(format!("Failed to read file: {}", e))
+                }
+            })?;
+            return self
+                .execute_range(request_id, filename, path, &slice, (start, end), options)
+                .await;
+        }
+
+        let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(false);
+        if self.allow_empty && is_empty {
+            let digests = digests_hex(&options.digest_algorithms(false), &[]);
+            return Ok(options
+                .apply_digests(empty_content_result(request_id, filename), &digests)
+                .with_source_path(Some(path.as_str().to_string()))
+                .with_description_limit(self.max_description_length));
+        }
+
+        let detect_start = Instant::now();
+        let (mime_type, description) = timeout(
+            Duration::from_secs(self.analysis_timeout_secs),
+            self.magic_repo.analyze_file(file, filename.as_str()),
+        )
+        .await
+        .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??;
+        let raw = options.raw.then(|| description.clone());
+        let description = match options.output {
+            Some(output) if output.wants_description() => {
+                let prefix = read_prefix(file, DESCRIBE_PREFIX_BYTES).map_err(|e| {
+                    ApplicationError::InternalError(format!("Failed to read file: {}", e))
+                })?;
+                timeout(
+                    Duration::from_secs(self.analysis_timeout_secs),
+                    self.magic_repo.describe_buffer(&prefix, filename.as_str()),
+                )
+                .await
+                .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??
+            }
+            _ => description,
+        };
+        reject_description(&self.reject_descriptions, &description)?;
+        let encoding = file_text_encoding(
+            self.magic_repo.as_ref(),
+            &self.text,
+            &mime_type,
+            file,
+            filename.as_str(),
+            self.analysis_timeout_secs,
+        )
+        .await?;
+        let detect_duration = detect_start.elapsed();
+
+        let algorithms = options.digest_algorithms(false);
+        let digests = if algorithms.is_empty() {
+            BTreeMap::new()
+        } else {
+            digests_hex_file(&algorithms, file).map_err(|e| {
+                ApplicationError::InternalError(format!("Failed to hash file: {}", e))
+            })?
+        };
+
+        let textual = self.text.is_textual(&mime_type);
+        let result = MagicResult::new(request_id, filename, mime_type, self.redact(description));
+        let result = options
+            .apply_digests(result, &digests)
+            .with_raw(raw)
+            .with_encoding(encoding)
+            .with_textual(textual)
+            .with_database_variant(self.magic_repo.database_variant_for_file(file))
+            .with_source_path(Some(path.as_str().to_string()))
+            .with_detect_duration(detect_duration)
+            .with_description_limit(self.max_description_length);
+        let result = alias_unknown_type(result, self.unknown_type_alias.as_ref());
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.sampler.record(&result, size, "file");
+        Ok(result)
+    }
+
+    /// Detection of `slice`, the bytes of `range` read without a mapping.
+    async fn execute_range(
+        &self,
+        request_id: RequestId,
+        filename: WindowsCompatibleFilename,
+        path: RelativePath,
+        slice: &[u8],
+        range: (u64, u64),
+        options: AnalysisOptions,
+    ) -> Result<MagicResult, ApplicationError> {
+        let detect_start = Instant::now();
+        let (mime_type, description) = timeout(
+            Duration::from_secs(self.analysis_timeout_secs),
+            self.magic_repo.analyze_buffer(slice, filename.as_str()),
+        )
+        .await
+        .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??;
+        let raw = options.raw.then(|| description.clone());
+        let description = match options.output {
+            Some(output) if output.wants_description() => timeout(
+                Duration::from_secs(self.analysis_timeout_secs),
+                self.magic_repo.describe_buffer(slice, filename.as_str()),
+            )
+            .await
+            .map_err(|_| ApplicationError::Timeout(self.analysis_timeout_secs))??,
+            _ => description,
+        };
+        reject_description(&self.reject_descriptions, &description)?;
+        let encoding = text_encoding(
+            self.magic_repo.as_ref(),
+            &self.text,
+            &mime_type,
+            slice,
+            filename.as_str(),
+            self.analysis_timeout_secs,
+        )
+        .await?;
+        let detect_duration = detect_start.elapsed();
+
+        let digests = digests_hex(&options.digest_algorithms(false), slice);
+        let textual = self.text.is_textual(&mime_type);
+        let result = MagicResult::new(request_id, filename, mime_type, self.redact(description));
+        let result = options
+            .apply_digests(result, &digests)
+            .with_raw(raw)
+            .with_encoding(encoding)
+            .with_textual(textual)
+            .with_database_variant(self.magic_repo.database_variant(slice))
+            .with_analyzed_range(Some(range))
+            .with_source_path(Some(path.as_str().to_string()))
+            .with_detect_duration(detect_duration)
+            .with_description_limit(self.max_description_length);
+        let result = alias_unknown_type(result, self.unknown_type_alias.as_ref());
+        self.sampler.record(&result, slice.len() as u64, "file");
+        Ok(result)
+    }
+
+    fn redact(&self, description: String) -> String {
+        if self.redact_description_metadata {
+            redact_metadata(&description)
+        } else {
+            description
+        }
+    }
+}
+
+/// Validator for `If-Range`: the file's size and modification time, so any
+/// write that changes either invalidates ranges computed from the old
+/// content.
+fn entity_tag(metadata: &std::fs::Metadata) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    format!("\"{:x}-{:x}\"", metadata.len(), modified)
+}
+
+/// Open `path` read-only for analysis. O_NONBLOCK keeps opening a FIFO from
+/// waiting for a writer; the caller rejects anything but regular files by
+/// checking the open descriptor.
+fn open_for_analysis(path: &Path) -> std::io::Result<File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+}
+
+fn file_type_name(file_type: FileType) -> &'static str {
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_fifo() {
+        "FIFO"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        "device"
+    } else {
+        "special file"
     }
 }
@@ -1,3 +1,5 @@
 pub mod analyze_content;
 pub mod analyze_path;
 pub mod health_check;
+pub mod readiness_check;
+pub mod test_magic_rule;
@@ -0,0 +1,129 @@
+use crate::domain::services::temp_storage::TempStorageService;
+use crate::infrastructure::config::server_config::ServerConfig;
+use std::fmt;
+use std::sync::Arc;
+
+const PROBE_PAYLOAD: &[u8] = b"magicer-readiness-probe";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadinessError {
+    TempDirNotWritable(String),
+    TempDirNotReadable(String),
+    TempDirNotDeletable(String),
+    InsufficientDiskSpace { free_space_mb: u64, min_free_space_mb: u64 },
+}
+
+impl ReadinessError {
+    /// Stable machine-readable reason reported by the readiness endpoint.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::TempDirNotWritable(_) => "temp_dir_not_writable",
+            Self::TempDirNotReadable(_) => "temp_dir_not_readable",
+            Self::TempDirNotDeletable(_) => "temp_dir_not_deletable",
+            Self::InsufficientDiskSpace { .. } => "insufficient_disk_space",
+        }
+    }
+
+    /// Free space in the temp dir, when that is why the check failed.
+    pub fn free_space_mb(&self) -> Option<u64> {
+        match self {
+            Self::InsufficientDiskSpace { free_space_mb, .. } => Some(*free_space_mb),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReadinessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TempDirNotWritable(msg) => write!(f, "Temp dir not writable: {}", msg),
+            Self::TempDirNotReadable(msg) => write!(f, "Temp dir not readable: {}", msg),
+            Self::TempDirNotDeletable(msg) => write!(f, "Temp dir not deletable: {}", msg),
+            Self::InsufficientDiskSpace {
+                free_space_mb,
+                min_free_space_mb,
+            } => write!(
+                f,
+                "Insufficient disk space: {}MB free, {}MB required",
+                free_space_mb, min_free_space_mb
+            ),
+        }
+    }
+}
+
+/// Outcome of a successful readiness check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadinessReport {
+    /// Free space in `analysis.temp_dir`, when the disk check is enabled.
+    pub free_space_mb: Option<u64>,
+}
+
+/// Verifies the service can actually serve large-file analyses by running a
+/// write + read + delete round-trip through the temp storage backend.
+pub struct ReadinessCheckUseCase {
+    temp_storage: Arc<dyn TempStorageService>,
+    disk: Option<Arc<ServerConfig>>,
+}
+
+impl ReadinessCheckUseCase {
+    pub fn new(temp_storage: Arc<dyn TempStorageService>) -> Self {
+        Self {
+            temp_storage,
+            disk: None,
+        }
+    }
+
+    /// Also report free space in `analysis.temp_dir` and fail once it drops
+    /// below `analysis.min_free_space_mb`.
+    pub fn with_disk_check(mut self, config: Arc<ServerConfig>) -> Self {
+        self.disk = Some(config);
+        self
+    }
+
+    pub async fn execute(&self) -> Result<ReadinessReport, ReadinessError> {
+        let free_space_mb = self.check_disk_space()?;
+
+        let mut tf = self
+            .temp_storage
+            .create_temp_file()
+            .await
+            .map_err(|e| ReadinessError::TempDirNotWritable(e.to_string()))?;
+
+        tf.write(PROBE_PAYLOAD)
+            .await
+            .map_err(|e| ReadinessError::TempDirNotWritable(e.to_string()))?;
+        tf.sync()
+            .await
+            .map_err(|e| ReadinessError::TempDirNotWritable(e.to_string()))?;
+
+        let read_back = tokio::fs::read(tf.path())
+            .await
+            .map_err(|e| ReadinessError::TempDirNotReadable(e.to_string()))?;
+        if read_back != PROBE_PAYLOAD {
+            return Err(ReadinessError::TempDirNotReadable(
+                "Probe content mismatch".to_string(),
+            ));
+        }
+
+        tokio::fs::remove_file(tf.path())
+            .await
+            .map_err(|e| ReadinessError::TempDirNotDeletable(e.to_string()))?;
+
+        Ok(ReadinessReport { free_space_mb })
+    }
+
+    fn check_disk_space(&self) -> Result<Option<u64>, ReadinessError> {
+        let Some(config) = &self.disk else {
+            return Ok(None);
+        };
+        let free_space_mb = config.get_free_space_mb(&config.analysis.temp_dir);
+        let min_free_space_mb = config.analysis.min_free_space_mb;
+        if free_space_mb < min_free_space_mb {
+            return Err(ReadinessError::InsufficientDiskSpace {
+                free_space_mb,
+                min_free_space_mb,
+            });
+        }
+        Ok(Some(free_space_mb))
+    }
+}
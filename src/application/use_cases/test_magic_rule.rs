@@ -0,0 +1,67 @@
+use crate::application::errors::ApplicationError;
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::services::temp_storage::TempStorageService;
+use crate::domain::value_objects::mime_type::MimeType;
+use std::sync::Arc;
+
+/// Upper bound on the size of a user-supplied magic rule.
+pub const MAX_RULE_BYTES: usize = 64 * 1024;
+
+/// Runs a single user-supplied magic rule against content, in isolation from
+/// the server's database. The rule is staged in the temp directory and
+/// removed when the analysis completes.
+pub struct TestMagicRuleUseCase {
+    magic_repo: Arc<dyn MagicRepository>,
+    temp_storage: Arc<dyn TempStorageService>,
+}
+
+impl TestMagicRuleUseCase {
+    pub fn new(
+        magic_repo: Arc<dyn MagicRepository>,
+        temp_storage: Arc<dyn TempStorageService>,
+    ) -> Self {
+        Self {
+            magic_repo,
+            temp_storage,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        rule: &str,
+        content: &[u8],
+    ) -> Result<(MimeType, String), ApplicationError> {
+        if rule.trim().is_empty() {
+            return Err(ApplicationError::BadRequest(
+                "Rule cannot be empty".to_string(),
+            ));
+        }
+        if rule.len() > MAX_RULE_BYTES {
+            return Err(ApplicationError::BadRequest(format!(
+                "Rule exceeds {} bytes",
+                MAX_RULE_BYTES
+            )));
+        }
+
+        let mut rule_file = self.temp_storage.create_temp_file().await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to create rule file: {}", e))
+        })?;
+        rule_file.write(rule.as_bytes()).await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to write rule file: {}", e))
+        })?;
+        rule_file.sync().await.map_err(|e| {
+            ApplicationError::InternalError(format!("Failed to sync rule file: {}", e))
+        })?;
+
+        self.magic_repo
+            .analyze_with_rules(rule_file.path(), content)
+            .await
+            .map_err(|e| match e {
+                MagicError::DatabaseLoadFailed(msg) => {
+                    ApplicationError::BadRequest(format!("Invalid magic rule: {}", msg))
+                }
+                other => other.into(),
+            })
+    }
+}
@@ -0,0 +1,22 @@
+use crate::domain::value_objects::mime_type::MimeType;
+
+/// First member of an archive, detected without extracting the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    name: String,
+    mime_type: MimeType,
+}
+
+impl ArchiveEntry {
+    pub fn new(name: String, mime_type: MimeType) -> Self {
+        Self { name, mime_type }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn mime_type(&self) -> &MimeType {
+        &self.mime_type
+    }
+}
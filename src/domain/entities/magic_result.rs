@@ -1,7 +1,11 @@
+use crate::domain::entities::archive_entry::ArchiveEntry;
+use crate::domain::entities::nested_type::NestedType;
 use crate::domain::value_objects::filename::WindowsCompatibleFilename;
 use crate::domain::value_objects::mime_type::MimeType;
 use crate::domain::value_objects::request_id::RequestId;
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -12,6 +16,27 @@ pub struct MagicResult {
     mime_type: MimeType,
     description: String,
     encoding: Option<String>,
+    textual: Option<bool>,
+    polyglot_types: Option<Vec<MimeType>>,
+    database_variant: Option<String>,
+    archive_first_entry: Option<ArchiveEntry>,
+    nested: Option<NestedType>,
+    hash: Option<String>,
+    hashes: Option<BTreeMap<String, String>>,
+    verified: bool,
+    aliased: bool,
+    description_truncated: bool,
+    modified_at: Option<DateTime<Utc>>,
+    entity_tag: Option<String>,
+    analyzed_range: Option<(u64, u64)>,
+    source_path: Option<String>,
+    raw: Option<String>,
+    trace: Option<String>,
+    hexdump: Option<String>,
+    cached: bool,
+    stale: bool,
+    read_duration: Option<Duration>,
+    detect_duration: Option<Duration>,
     analyzed_at: DateTime<Utc>,
 }
 
@@ -29,6 +54,27 @@ impl MagicResult {
             mime_type,
             description,
             encoding: None,
+            textual: None,
+            polyglot_types: None,
+            database_variant: None,
+            archive_first_entry: None,
+            nested: None,
+            hash: None,
+            hashes: None,
+            verified: true,
+            aliased: false,
+            description_truncated: false,
+            modified_at: None,
+            entity_tag: None,
+            analyzed_range: None,
+            source_path: None,
+            raw: None,
+            trace: None,
+            hexdump: None,
+            cached: false,
+            stale: false,
+            read_duration: None,
+            detect_duration: None,
             analyzed_at: Utc::now(),
         }
     }
@@ -38,6 +84,148 @@ impl MagicResult {
         self
     }
 
+    /// Record whether the detected type is text, reported as `category`.
+    pub fn with_textual(mut self, textual: bool) -> Self {
+        self.textual = Some(textual);
+        self
+    }
+
+    /// Record which database of an A/B experiment produced the detection.
+    pub fn with_database_variant(mut self, variant: Option<&str>) -> Self {
+        self.database_variant = variant.map(str::to_string);
+        self
+    }
+
+    /// Record every type the content strongly matches, for polyglot
+    /// reporting.
+    pub fn with_polyglot_types(mut self, types: Option<Vec<MimeType>>) -> Self {
+        self.polyglot_types = types;
+        self
+    }
+
+    pub fn with_archive_first_entry(mut self, entry: Option<ArchiveEntry>) -> Self {
+        self.archive_first_entry = entry;
+        self
+    }
+
+    pub fn with_nested(mut self, nested: Option<NestedType>) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// Attach the lowercase hex SHA-256 of the analyzed content.
+    pub fn with_hash(mut self, hash: Option<String>) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Attach lowercase hex digests keyed by algorithm name.
+    pub fn with_hashes(mut self, hashes: Option<BTreeMap<String, String>>) -> Self {
+        self.hashes = hashes;
+        self
+    }
+
+    /// Replace the detected type with one declared by the client. The
+    /// result is then marked unverified.
+    pub fn with_declared_type(mut self, declared: MimeType) -> Self {
+        self.mime_type = declared;
+        self.verified = false;
+        self
+    }
+
+    /// Replace an inconclusive detection with a configured placeholder type.
+    /// The result is then marked aliased.
+    pub fn with_alias(mut self, alias: MimeType) -> Self {
+        self.mime_type = alias;
+        self.aliased = true;
+        self
+    }
+
+    /// Cut the description to at most `max` bytes, backing off to a char
+    /// boundary, and append an ellipsis. Shorter descriptions are kept as is.
+    pub fn with_description_limit(mut self, max: Option<usize>) -> Self {
+        if let Some(max) = max
+            && self.description.len() > max
+        {
+            let mut end = max;
+            while !self.description.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.description.truncate(end);
+            self.description.push('…');
+            self.description_truncated = true;
+        }
+        self
+    }
+
+    /// Attach the modification time of the analyzed file.
+    pub fn with_modified_at(mut self, modified_at: Option<DateTime<Utc>>) -> Self {
+        self.modified_at = modified_at;
+        self
+    }
+
+    /// Attach the validator of the analyzed file, sent as `ETag`.
+    pub fn with_entity_tag(mut self, entity_tag: Option<String>) -> Self {
+        self.entity_tag = entity_tag;
+        self
+    }
+
+    /// Record that only bytes `start..=end` of the file were analyzed.
+    pub fn with_analyzed_range(mut self, range: Option<(u64, u64)>) -> Self {
+        self.analyzed_range = range;
+        self
+    }
+
+    /// Attach the sandbox-relative path the content was read from.
+    pub fn with_source_path(mut self, path: Option<String>) -> Self {
+        self.source_path = path;
+        self
+    }
+
+    /// Attach the repository's unparsed output, for debugging detection.
+    pub fn with_raw(mut self, raw: Option<String>) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Attach libmagic's debug output for the detection, for rule authors.
+    pub fn with_trace(mut self, trace: Option<String>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Attach a hex+ASCII dump of the start of the content, for diagnosing
+    /// misclassification.
+    pub fn with_hexdump(mut self, hexdump: Option<String>) -> Self {
+        self.hexdump = hexdump;
+        self
+    }
+
+    /// Mark the detection as served from the content hash cache.
+    pub fn with_cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+
+    /// Mark the detection as a cached one served because libmagic was
+    /// unavailable, so it was not confirmed against the current database.
+    pub fn with_stale(mut self, stale: bool) -> Self {
+        self.stale = stale;
+        self
+    }
+
+    /// Time spent reading the input (draining the body or mapping the file).
+    pub fn with_read_duration(mut self, duration: Duration) -> Self {
+        self.read_duration = Some(duration);
+        self
+    }
+
+    /// Time spent in the libmagic call.
+    pub fn with_detect_duration(mut self, duration: Duration) -> Self {
+        self.detect_duration = Some(duration);
+        self
+    }
+
     pub fn id(&self) -> &Uuid {
         &self.id
     }
@@ -62,6 +250,113 @@ impl MagicResult {
         self.encoding.as_deref()
     }
 
+    /// Whether the detected charset must be converted before the text can
+    /// be read as UTF-8, i.e. it is neither UTF-8 nor ASCII. `None` when no
+    /// charset was detected.
+    pub fn requires_transcoding(&self) -> Option<bool> {
+        self.encoding().map(|charset| {
+            !["utf-8", "us-ascii"]
+                .iter()
+                .any(|native| charset.eq_ignore_ascii_case(native))
+        })
+    }
+
+    pub fn textual(&self) -> Option<bool> {
+        self.textual
+    }
+
+    pub fn database_variant(&self) -> Option<&str> {
+        self.database_variant.as_deref()
+    }
+
+    pub fn polyglot_types(&self) -> Option<&[MimeType]> {
+        self.polyglot_types.as_deref()
+    }
+
+    /// Whether the content matches more than one distinct type. `None` when
+    /// polyglot detection did not run.
+    pub fn is_polyglot(&self) -> Option<bool> {
+        self.polyglot_types.as_ref().map(|types| types.len() > 1)
+    }
+
+    pub fn archive_first_entry(&self) -> Option<&ArchiveEntry> {
+        self.archive_first_entry.as_ref()
+    }
+
+    pub fn nested(&self) -> Option<&NestedType> {
+        self.nested.as_ref()
+    }
+
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    pub fn hashes(&self) -> Option<&BTreeMap<String, String>> {
+        self.hashes.as_ref()
+    }
+
+    /// False when the MIME type was declared by the client rather than
+    /// detected.
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    /// True when the MIME type is a placeholder set by
+    /// [`with_alias`](Self::with_alias).
+    pub fn is_aliased(&self) -> bool {
+        self.aliased
+    }
+
+    /// True when the description was shortened by
+    /// [`with_description_limit`](Self::with_description_limit).
+    pub fn is_description_truncated(&self) -> bool {
+        self.description_truncated
+    }
+
+    pub fn modified_at(&self) -> Option<DateTime<Utc>> {
+        self.modified_at
+    }
+
+    pub fn entity_tag(&self) -> Option<&str> {
+        self.entity_tag.as_deref()
+    }
+
+    pub fn analyzed_range(&self) -> Option<(u64, u64)> {
+        self.analyzed_range
+    }
+
+    pub fn source_path(&self) -> Option<&str> {
+        self.source_path.as_deref()
+    }
+
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    pub fn trace(&self) -> Option<&str> {
+        self.trace.as_deref()
+    }
+
+    pub fn hexdump(&self) -> Option<&str> {
+        self.hexdump.as_deref()
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.cached
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    pub fn read_duration(&self) -> Option<Duration> {
+        self.read_duration
+    }
+
+    pub fn detect_duration(&self) -> Option<Duration> {
+        self.detect_duration
+    }
+
     pub fn analyzed_at(&self) -> DateTime<Utc> {
         self.analyzed_at
     }
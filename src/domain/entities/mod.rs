@@ -1 +1,3 @@
+pub mod archive_entry;
 pub mod magic_result;
+pub mod nested_type;
@@ -0,0 +1,22 @@
+use crate::domain::value_objects::mime_type::MimeType;
+
+/// Type of a compressed container together with the type of its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedType {
+    container: MimeType,
+    inner: MimeType,
+}
+
+impl NestedType {
+    pub fn new(container: MimeType, inner: MimeType) -> Self {
+        Self { container, inner }
+    }
+
+    pub fn container(&self) -> &MimeType {
+        &self.container
+    }
+
+    pub fn inner(&self) -> &MimeType {
+        &self.inner
+    }
+}
@@ -12,6 +12,11 @@ pub enum ValidationError {
     PathTraversal,
     InvalidPath,
     FileNotFound,
+    InvalidAddress,
+    /// The path resolved inside the sandbox but falls under a denied subpath.
+    PermissionDenied,
+    /// The value is not one of the accepted choices.
+    UnsupportedValue,
 }
 
 impl fmt::Display for ValidationError {
@@ -24,6 +29,9 @@ impl fmt::Display for ValidationError {
             Self::PathTraversal => write!(f, "Path traversal not allowed"),
             Self::InvalidPath => write!(f, "Invalid path"),
             Self::FileNotFound => write!(f, "File or directory not found"),
+            Self::InvalidAddress => write!(f, "Invalid bind address"),
+            Self::PermissionDenied => write!(f, "Permission denied"),
+            Self::UnsupportedValue => write!(f, "Unsupported value"),
         }
     }
 }
@@ -33,6 +41,8 @@ pub enum MagicError {
     AnalysisFailed(String),
     DatabaseLoadFailed(String),
     FileNotFound(String),
+    /// libmagic could not be initialised; no analysis is possible.
+    Unavailable(String),
 }
 
 impl fmt::Display for MagicError {
@@ -41,6 +51,7 @@ impl fmt::Display for MagicError {
             Self::AnalysisFailed(msg) => write!(f, "Analysis failed: {}", msg),
             Self::DatabaseLoadFailed(msg) => write!(f, "Database load failed: {}", msg),
             Self::FileNotFound(path) => write!(f, "File not found: {}", path),
+            Self::Unavailable(msg) => write!(f, "Magic unavailable: {}", msg),
         }
     }
 }
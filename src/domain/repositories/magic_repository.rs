@@ -1,11 +1,134 @@
 use crate::domain::errors::MagicError;
 use crate::domain::value_objects::mime_type::MimeType;
 use futures_util::future::BoxFuture;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
 
 pub trait MagicRepository: Send + Sync {
+    /// Detect the type of `data`. Returns the parsed MIME type and the
+    /// repository's unparsed output (libmagic's exact string), which is
+    /// reported as the description.
     fn analyze_buffer<'a>(
         &'a self,
         data: &'a [u8],
         filename: &'a str,
     ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>>;
+
+    /// Detect the type of the payload inside a compressed container (gzip,
+    /// zstd, ...). Repositories without decompression support fall back to
+    /// plain detection, which reports the container type again.
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.analyze_buffer(data, filename)
+    }
+
+    /// Detect the type of the already open `file` without mapping it into
+    /// memory, for when the caller cannot map it (e.g. `mmap` failing with
+    /// `ENOMEM`). The whole file is examined regardless of its offset, which
+    /// is left unchanged. Repositories without file support read the file
+    /// and fall back to [`analyze_buffer`](Self::analyze_buffer).
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move {
+            let data = read_all_at(file).map_err(|e| MagicError::AnalysisFailed(e.to_string()))?;
+            self.analyze_buffer(&data, filename).await
+        })
+    }
+
+    /// Human-readable description of `data` (e.g. "PDF document, version
+    /// 1.4"), for clients that ask for more than the MIME type. Repositories
+    /// whose `analyze_buffer` already describes the content reuse it.
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        Box::pin(async move {
+            self.analyze_buffer(data, filename)
+                .await
+                .map(|(_, description)| description)
+        })
+    }
+
+    /// Character encoding of `data` (e.g. "utf-8", "utf-16le", "binary").
+    /// Repositories that cannot tell return `None`.
+    fn detect_encoding<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// libmagic's debug output while detecting `data`: the rules tried and
+    /// how each fared, for magic rule authors. Repositories that cannot
+    /// trace return an empty string.
+    fn trace_buffer<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        Box::pin(async { Ok(String::new()) })
+    }
+
+    /// Every distinct type `data` strongly matches, strongest first. More
+    /// than one means the content is a polyglot. Repositories that stop at
+    /// the first match report only that.
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        Box::pin(async move {
+            self.analyze_buffer(data, filename)
+                .await
+                .map(|(mime_type, _)| vec![mime_type])
+        })
+    }
+
+    /// Which database of an A/B experiment analyzes `data` (e.g.
+    /// `"candidate"`), reported with the result. `None` outside experiments.
+    fn database_variant(&self, _data: &[u8]) -> Option<&'static str> {
+        None
+    }
+
+    /// Like [`database_variant`](Self::database_variant), for `file` analyzed
+    /// with [`analyze_file`](Self::analyze_file).
+    fn database_variant_for_file(&self, _file: &File) -> Option<&'static str> {
+        None
+    }
+
+    /// Detect `data` using only the magic rules in `rule_path`, ignoring the
+    /// loaded database.
+    fn analyze_with_rules<'a>(
+        &'a self,
+        _rule_path: &'a Path,
+        _data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async {
+            Err(MagicError::AnalysisFailed(
+                "Custom rules are not supported".to_string(),
+            ))
+        })
+    }
+}
+
+/// Content of `file` from its start, read without moving its offset.
+fn read_all_at(file: &File) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = file.read_at(&mut chunk, data.len() as u64)?;
+        if read == 0 {
+            return Ok(data);
+        }
+        data.extend_from_slice(&chunk[..read]);
+    }
 }
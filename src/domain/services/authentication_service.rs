@@ -1,5 +1,5 @@
 use crate::domain::errors::AuthenticationError;
-use crate::domain::value_objects::auth::BasicAuthCredentials;
+use crate::domain::value_objects::auth::{BasicAuthCredentials, Principal};
 use futures_util::future::BoxFuture;
 
 pub trait AuthenticationService: Send + Sync {
@@ -7,4 +7,13 @@ pub trait AuthenticationService: Send + Sync {
         &'a self,
         credentials: &'a BasicAuthCredentials,
     ) -> BoxFuture<'a, Result<(), AuthenticationError>>;
+
+    /// Resolve a Bearer token to a [`Principal`]. Services that do not
+    /// support token authentication reject every token.
+    fn authorize<'a>(
+        &'a self,
+        _token: &'a str,
+    ) -> BoxFuture<'a, Result<Principal, AuthenticationError>> {
+        Box::pin(async { Err(AuthenticationError::InvalidCredentials) })
+    }
 }
@@ -0,0 +1,9 @@
+use crate::domain::errors::MagicError;
+
+/// Replaces the loaded magic database at runtime.
+pub trait MagicReloader: Send + Sync {
+    /// Load a fresh database and swap it in, returning the new database
+    /// version. Analyses already running finish on the old database; on
+    /// failure the old database stays in use.
+    fn reload(&self) -> Result<u64, MagicError>;
+}
@@ -1,3 +1,8 @@
 pub mod authentication_service;
+pub mod magic_reloader;
+pub mod result_sink;
+pub mod result_store;
 pub mod sandbox_service;
 pub mod temp_storage;
+pub mod upload_session_store;
+pub mod upload_store;
@@ -0,0 +1,15 @@
+use crate::domain::entities::magic_result::MagicResult;
+
+/// Receives every successful analysis result in addition to the HTTP
+/// response. Implementations must not block the request: delivery happens
+/// in the background and failures are only logged.
+pub trait ResultSink: Send + Sync {
+    fn publish(&self, result: &MagicResult);
+}
+
+/// Sink used when no integration is configured.
+pub struct NoopResultSink;
+
+impl ResultSink for NoopResultSink {
+    fn publish(&self, _result: &MagicResult) {}
+}
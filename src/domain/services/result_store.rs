@@ -0,0 +1,26 @@
+use crate::domain::entities::magic_result::MagicResult;
+use chrono::{DateTime, Utc};
+
+/// One analysis in the history of a sandbox path.
+#[derive(Debug, Clone)]
+pub struct PathHistoryEntry {
+    pub stored_at: DateTime<Utc>,
+    pub result: MagicResult,
+}
+
+/// Keeps analysis results so they can be fetched again by content id (the
+/// lowercase hex SHA-256 of the analyzed content), and the analyses of each
+/// sandbox path over time.
+pub trait ResultStore: Send + Sync {
+    fn put(&self, content_id: &str, result: MagicResult);
+
+    /// The stored result, or `None` if it was never stored, evicted or has
+    /// expired.
+    fn get(&self, content_id: &str) -> Option<MagicResult>;
+
+    /// Append `result` to the history of the sandbox-relative `path`.
+    fn record_path(&self, path: &str, result: MagicResult);
+
+    /// The unexpired analyses recorded for `path`, oldest first.
+    fn path_history(&self, path: &str) -> Vec<PathHistoryEntry>;
+}
@@ -4,4 +4,14 @@ use std::path::PathBuf;
 
 pub trait SandboxService: Send + Sync {
     fn resolve_path(&self, path: &RelativePath) -> Result<PathBuf, ValidationError>;
+
+    /// Like [`resolve_path`](Self::resolve_path), but following symlinks
+    /// even where the sandbox refuses them. Callers must have checked that
+    /// the request may do so.
+    fn resolve_path_following_symlinks(
+        &self,
+        path: &RelativePath,
+    ) -> Result<PathBuf, ValidationError> {
+        self.resolve_path(path)
+    }
 }
@@ -1,14 +1,34 @@
 use std::path::Path;
 use async_trait::async_trait;
 
+/// A temp file that is deleted when dropped. Use cases rely on this to clean
+/// up promptly when a request is cancelled mid-upload: the file is owned by
+/// the analysis future, so dropping the future removes it.
 #[async_trait]
 pub trait TemporaryFile: Send + Sync {
     fn path(&self) -> &Path;
     async fn write(&mut self, data: &[u8]) -> Result<(), std::io::Error>;
     async fn sync(&mut self) -> Result<(), std::io::Error>;
+
+    /// A read handle on the written content, taken after `sync`. The default
+    /// opens `path()` again; implementations that keep the file open hand out
+    /// a duplicate of that descriptor instead.
+    async fn read_handle(&mut self) -> Result<std::fs::File, std::io::Error> {
+        std::fs::File::open(self.path())
+    }
 }
 
 #[async_trait]
 pub trait TempStorageService: Send + Sync {
     async fn create_temp_file(&self) -> Result<Box<dyn TemporaryFile>, std::io::Error>;
+
+    /// A temp file for content expected to be `size_hint` bytes, when the
+    /// length is known up front. Backends that place files by size
+    /// override this; the default ignores the hint.
+    async fn create_temp_file_sized(
+        &self,
+        _size_hint: Option<u64>,
+    ) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.create_temp_file().await
+    }
 }
@@ -0,0 +1,33 @@
+use crate::domain::services::temp_storage::TemporaryFile;
+use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+use std::sync::Arc;
+
+/// Content assembled from the chunks appended by `PATCH /v1/session/{id}`.
+pub struct UploadSession {
+    pub filename: WindowsCompatibleFilename,
+    /// Owns the temp file; dropping it deletes the file.
+    pub file: Box<dyn TemporaryFile>,
+    /// Bytes appended so far.
+    pub size: u64,
+}
+
+/// A session shared between the store and the request appending to or
+/// analyzing it. The lock serializes requests on the same session.
+pub type SharedUploadSession = Arc<tokio::sync::Mutex<UploadSession>>;
+
+/// Keeps upload sessions by id until they go unused for too long.
+pub trait UploadSessionStore: Send + Sync {
+    /// Keep `session` and return the id it is retrieved by, or `None` when
+    /// the store is full.
+    fn create(&self, session: UploadSession) -> Option<String>;
+
+    /// The session, or `None` if the id is unknown or has expired. Counts as
+    /// activity on the session, restarting its expiry.
+    fn get(&self, id: &str) -> Option<SharedUploadSession>;
+
+    /// Drop the session; its file is deleted once no request holds it.
+    fn remove(&self, id: &str);
+
+    /// Drop expired sessions. Returns how many.
+    fn purge_expired(&self) -> usize;
+}
@@ -0,0 +1,25 @@
+use crate::domain::services::temp_storage::TemporaryFile;
+use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+
+/// Content received by `POST /v1/upload`, kept so it can be analyzed several
+/// times without sending it again.
+pub struct Upload {
+    pub filename: WindowsCompatibleFilename,
+    /// Owns the temp file; dropping it deletes the file.
+    pub file: Box<dyn TemporaryFile>,
+    /// Open read handle on the content, taken after the file was synced.
+    pub handle: std::fs::File,
+}
+
+/// Keeps uploads by an opaque token until they expire.
+pub trait UploadStore: Send + Sync {
+    /// Keep `upload` and return the token it is retrieved by.
+    fn put(&self, upload: Upload) -> String;
+
+    /// Filename and a fresh read handle on the content, or `None` if the
+    /// token is unknown, evicted or has expired.
+    fn get(&self, token: &str) -> Option<(WindowsCompatibleFilename, std::fs::File)>;
+
+    /// Drop expired uploads, deleting their files. Returns how many.
+    fn purge_expired(&self) -> usize;
+}
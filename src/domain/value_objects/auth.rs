@@ -44,3 +44,41 @@ impl PartialEq for BasicAuthCredentials {
 }
 
 impl Eq for BasicAuthCredentials {}
+
+/// Scope required for `/v1/magic/content`.
+pub const SCOPE_ANALYZE: &str = "analyze";
+/// Scope required for `/v1/magic/path`.
+pub const SCOPE_READ_SANDBOX: &str = "read_sandbox";
+/// Scope required for administrative endpoints.
+pub const SCOPE_ADMIN: &str = "admin";
+pub const ALL_SCOPES: &[&str] = &[SCOPE_ANALYZE, SCOPE_READ_SANDBOX, SCOPE_ADMIN];
+
+/// Authenticated caller, as resolved by an [`AuthenticationService`].
+///
+/// [`AuthenticationService`]: crate::domain::services::authentication_service::AuthenticationService
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    username: String,
+    scopes: Vec<String>,
+}
+
+impl Principal {
+    pub fn new(username: &str, scopes: Vec<String>) -> Self {
+        Self {
+            username: username.to_string(),
+            scopes,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
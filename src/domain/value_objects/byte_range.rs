@@ -0,0 +1,49 @@
+/// A single range from a `Range: bytes=...` header, not yet checked against
+/// the size of the file it applies to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ByteRange {
+    /// `bytes=start-` or `bytes=start-end` (`end` inclusive).
+    From { start: u64, end: Option<u64> },
+    /// `bytes=-len`: the last `len` bytes.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parse a `Range` header value. Only single `bytes` ranges are
+    /// understood; anything else is `None`, and the header is ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let spec = value.trim().strip_prefix("bytes=")?.trim();
+        if spec.contains(',') {
+            return None;
+        }
+        let (start, end) = spec.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() {
+            return end.parse().ok().map(Self::Suffix);
+        }
+        let start = start.parse().ok()?;
+        let end = if end.is_empty() {
+            None
+        } else {
+            Some(end.parse().ok()?)
+        };
+        match end {
+            Some(end) if end < start => None,
+            end => Some(Self::From { start, end }),
+        }
+    }
+
+    /// First and last (inclusive) byte of this range within a file of
+    /// `size` bytes. `None` when no byte of the file is selected.
+    pub fn resolve(self, size: u64) -> Option<(u64, u64)> {
+        if size == 0 {
+            return None;
+        }
+        match self {
+            Self::From { start, .. } if start >= size => None,
+            Self::From { start, end } => Some((start, end.map_or(size - 1, |e| e.min(size - 1)))),
+            Self::Suffix(0) => None,
+            Self::Suffix(len) => Some((size.saturating_sub(len), size - 1)),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::domain::errors::ValidationError;
+use crate::domain::value_objects::mime_type::MimeType;
+use base64::{Engine as _, engine::general_purpose};
+
+/// A decoded `data:[<mediatype>][;base64],<data>` URI (RFC 2397).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DataUri {
+    media_type: MimeType,
+    data: Vec<u8>,
+}
+
+impl DataUri {
+    /// Parse and decode `value`. Parameters of the media type such as
+    /// `charset` are dropped; an omitted media type is `text/plain`, as the
+    /// RFC specifies. Base64 data may be wrapped across lines; other data
+    /// is percent-decoded.
+    pub fn parse(value: &str) -> Result<Self, ValidationError> {
+        let value = value.trim();
+        let rest = value
+            .get(..5)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+            .map(|_| &value[5..])
+            .ok_or(ValidationError::UnsupportedValue)?;
+        let (meta, data) = rest.split_once(',').ok_or(ValidationError::InvalidCharacter)?;
+
+        let mut params = meta.split(';');
+        let essence = params.next().unwrap_or_default().trim();
+        let base64 = params.any(|param| param.trim().eq_ignore_ascii_case("base64"));
+        let media_type = if essence.is_empty() {
+            MimeType::new("text/plain")?
+        } else {
+            MimeType::new(&essence.to_ascii_lowercase())?
+        };
+
+        let data = if base64 {
+            let encoded: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+            general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|_| ValidationError::InvalidCharacter)?
+        } else {
+            percent_decode(data)?
+        };
+        Ok(Self { media_type, data })
+    }
+
+    pub fn media_type(&self) -> &MimeType {
+        &self.media_type
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_parts(self) -> (MimeType, Vec<u8>) {
+        (self.media_type, self.data)
+    }
+}
+
+fn percent_decode(data: &str) -> Result<Vec<u8>, ValidationError> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(ValidationError::InvalidCharacter)?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
@@ -21,6 +21,12 @@ impl WindowsCompatibleFilename {
         Ok(Self(filename.to_string()))
     }
 
+    /// Like [`Self::new`], but lowercased, so names that differ only in
+    /// case compare equal as they do on case-insensitive filesystems.
+    pub fn new_normalized(filename: &str) -> Result<Self, ValidationError> {
+        Self::new(filename).map(|f| Self(f.0.to_lowercase()))
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
@@ -44,10 +44,31 @@ impl MimeType {
         format!("{}/{}", self.type_part, self.subtype_part)
     }
 
+    /// Whether both name the same type, ignoring case (RFC 2045 §5.1).
+    pub fn essence_eq(&self, other: &MimeType) -> bool {
+        self.type_part.eq_ignore_ascii_case(&other.type_part)
+            && self.subtype_part.eq_ignore_ascii_case(&other.subtype_part)
+    }
+
     pub fn is_text(&self) -> bool {
         self.type_part == "text"
     }
 
+    /// Whether the content is human-readable text: `text/*`, any type with a
+    /// `+xml` or `+json` structured suffix (e.g. `image/svg+xml`), or an
+    /// `application/*` subtype listed in `textual_subtypes`
+    /// (case-insensitive).
+    pub fn is_textual(&self, textual_subtypes: &[String]) -> bool {
+        let subtype = self.subtype_part.to_ascii_lowercase();
+        self.is_text()
+            || subtype.ends_with("+xml")
+            || subtype.ends_with("+json")
+            || (self.is_application()
+                && textual_subtypes
+                    .iter()
+                    .any(|textual| textual.eq_ignore_ascii_case(&subtype)))
+    }
+
     pub fn is_binary(&self) -> bool {
         !self.is_text()
     }
@@ -1,4 +1,6 @@
 pub mod auth;
+pub mod byte_range;
+pub mod data_uri;
 pub mod filename;
 pub mod mime_type;
 pub mod path;
@@ -0,0 +1,125 @@
+use crate::domain::errors::AuthenticationError;
+use crate::domain::services::authentication_service::AuthenticationService;
+use crate::domain::value_objects::auth::{BasicAuthCredentials, Principal};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const INTROSPECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// RFC 7662 token introspection response (the fields we use).
+#[derive(Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    sub: Option<String>,
+    /// Space-separated scope list.
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Resolves Bearer tokens by POSTing them to an OAuth 2.0 introspection
+/// endpoint (RFC 7662). Active tokens are cached for a short TTL, keyed by
+/// the token's SHA-256 so raw tokens are never held in memory longer than
+/// the request. Basic credentials are delegated to the wrapped service.
+pub struct IntrospectionAuthService {
+    basic: Arc<dyn AuthenticationService>,
+    client: reqwest::Client,
+    introspection_url: String,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<[u8; 32], (Instant, Principal)>>,
+}
+
+impl IntrospectionAuthService {
+    pub fn new(
+        basic: Arc<dyn AuthenticationService>,
+        introspection_url: &str,
+        cache_ttl: Duration,
+    ) -> Result<Self, AuthenticationError> {
+        let client = reqwest::Client::builder()
+            .timeout(INTROSPECTION_TIMEOUT)
+            .build()
+            .map_err(|e| AuthenticationError::InternalError(e.to_string()))?;
+        Ok(Self {
+            basic,
+            client,
+            introspection_url: introspection_url.to_string(),
+            cache_ttl,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn cached(&self, key: &[u8; 32]) -> Option<Principal> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|(at, _)| at.elapsed() < self.cache_ttl)
+            .map(|(_, principal)| principal.clone())
+    }
+
+    fn store(&self, key: [u8; 32], principal: &Principal) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (at, _)| at.elapsed() < self.cache_ttl);
+        cache.insert(key, (Instant::now(), principal.clone()));
+    }
+
+    async fn introspect(&self, token: &str) -> Result<Principal, AuthenticationError> {
+        let response = self
+            .client
+            .post(&self.introspection_url)
+            .form(&[("token", token)])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                AuthenticationError::InternalError(format!("Introspection failed: {}", e))
+            })?;
+
+        let body: IntrospectionResponse = response.json().await.map_err(|e| {
+            AuthenticationError::InternalError(format!("Invalid introspection response: {}", e))
+        })?;
+
+        if !body.active {
+            return Err(AuthenticationError::InvalidCredentials);
+        }
+
+        let username = body.username.or(body.sub).unwrap_or_default();
+        let scopes = body
+            .scope
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        Ok(Principal::new(&username, scopes))
+    }
+}
+
+impl AuthenticationService for IntrospectionAuthService {
+    fn verify_credentials<'a>(
+        &'a self,
+        credentials: &'a BasicAuthCredentials,
+    ) -> BoxFuture<'a, Result<(), AuthenticationError>> {
+        self.basic.verify_credentials(credentials)
+    }
+
+    fn authorize<'a>(
+        &'a self,
+        token: &'a str,
+    ) -> BoxFuture<'a, Result<Principal, AuthenticationError>> {
+        Box::pin(async move {
+            let key: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+            if let Some(principal) = self.cached(&key) {
+                return Ok(principal);
+            }
+
+            let principal = self.introspect(token).await?;
+            self.store(key, &principal);
+            Ok(principal)
+        })
+    }
+}
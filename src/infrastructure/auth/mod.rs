@@ -1 +1,2 @@
 pub mod basic_auth_service;
+pub mod introspection_auth_service;
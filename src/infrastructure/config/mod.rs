@@ -1 +1,2 @@
 pub mod server_config;
+pub mod startup_banner;
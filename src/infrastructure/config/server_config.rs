@@ -1,7 +1,10 @@
 use crate::domain::errors::ValidationError;
+use crate::domain::value_objects::mime_type::MimeType;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::path::Path;
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -18,6 +21,204 @@ pub struct ServerConfig {
     pub magic: MagicConfig,
     #[serde(default)]
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub response: ResponseConfig,
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+    #[serde(default)]
+    pub results: ResultStoreConfig,
+    #[serde(default)]
+    pub uploads: UploadStoreConfig,
+    #[serde(default)]
+    pub sessions: UploadSessionConfig,
+    #[serde(default)]
+    pub endpoints: EndpointsConfig,
+}
+
+/// Bounds of the in-memory store behind `?store=true`,
+/// `GET /v1/results/{content_id}` and `GET /v1/results/by-path`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ResultStoreConfig {
+    #[serde(default = "default_result_store_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "default_result_store_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_result_store_max_history_per_path")]
+    pub max_history_per_path: usize,
+}
+
+fn default_result_store_max_entries() -> usize {
+    1000
+}
+
+fn default_result_store_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_result_store_max_history_per_path() -> usize {
+    10
+}
+
+impl Default for ResultStoreConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_result_store_max_entries(),
+            ttl_secs: default_result_store_ttl_secs(),
+            max_history_per_path: default_result_store_max_history_per_path(),
+        }
+    }
+}
+
+/// Bounds of the uploads kept by `POST /v1/upload` for
+/// `POST /v1/magic/token/{token}`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UploadStoreConfig {
+    #[serde(default = "default_upload_store_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "default_upload_store_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+fn default_upload_store_max_entries() -> usize {
+    100
+}
+
+fn default_upload_store_ttl_secs() -> u64 {
+    300
+}
+
+impl Default for UploadStoreConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_upload_store_max_entries(),
+            ttl_secs: default_upload_store_ttl_secs(),
+        }
+    }
+}
+
+/// Bounds of the chunked uploads started by `POST /v1/session`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UploadSessionConfig {
+    #[serde(default = "default_session_max_entries")]
+    pub max_entries: usize,
+    /// Seconds a session may go without an append or analysis before it
+    /// expires.
+    #[serde(default = "default_session_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Cap on the content assembled by one session.
+    #[serde(default = "default_session_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+fn default_session_max_entries() -> usize {
+    100
+}
+
+fn default_session_ttl_secs() -> u64 {
+    300
+}
+
+fn default_session_max_size_mb() -> u64 {
+    100
+}
+
+impl Default for UploadSessionConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_session_max_entries(),
+            ttl_secs: default_session_ttl_secs(),
+            max_size_mb: default_session_max_size_mb(),
+        }
+    }
+}
+
+/// Which groups of analysis routes are registered. Routes of a disabled
+/// group are not mounted at all and answer `404`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EndpointsConfig {
+    /// Analysis of request bodies: `/v1/magic/content`, `POST
+    /// /v1/magic/is-text`, and the upload and session routes.
+    #[serde(default = "default_endpoint_enabled")]
+    pub content_enabled: bool,
+    /// Analysis of sandbox files: `/v1/magic/path` and `GET
+    /// /v1/magic/is-text`.
+    #[serde(default = "default_endpoint_enabled")]
+    pub path_enabled: bool,
+    /// Batch analysis of sandbox files: `/v1/magic/paths`.
+    #[serde(default = "default_endpoint_enabled")]
+    pub batch_enabled: bool,
+}
+
+fn default_endpoint_enabled() -> bool {
+    true
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            content_enabled: default_endpoint_enabled(),
+            path_enabled: default_endpoint_enabled(),
+            batch_enabled: default_endpoint_enabled(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct IntegrationsConfig {
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+    /// Key for the HMAC-SHA256 `signature` of each analysis response;
+    /// responses are unsigned when unset.
+    #[serde(default)]
+    pub result_signing_key: Option<String>,
+}
+
+impl std::fmt::Debug for IntegrationsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntegrationsConfig")
+            .field("webhook", &self.webhook)
+            .field(
+                "result_signing_key",
+                &self.result_signing_key.as_ref().map(|_| "***"),
+            )
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key for the `X-Signature` HMAC-SHA256 header; unsigned when unset.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// At most [`MAX_WEBHOOK_RETRIES`].
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+    /// Deliveries in progress or awaiting a retry; further results are
+    /// dropped.
+    #[serde(default = "default_webhook_max_pending")]
+    pub max_pending: usize,
+}
+
+pub const MAX_WEBHOOK_RETRIES: u32 = 10;
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_webhook_max_pending() -> usize {
+    256
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &self.secret.as_ref().map(|_| "***"))
+            .field("max_retries", &self.max_retries)
+            .field("max_pending", &self.max_pending)
+            .finish()
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -28,10 +229,47 @@ pub struct ServerSection {
     pub port: u16,
     #[serde(default = "default_max_connections")]
     pub max_connections: u32,
+    /// What happens to requests beyond `max_connections` in flight: `queue`
+    /// them until a slot frees up, or `shed` them with `503`.
+    #[serde(default = "default_overload_policy")]
+    pub overload_policy: String,
     #[serde(default = "default_backlog")]
     pub backlog: u32,
     #[serde(default = "default_max_open_files")]
     pub max_open_files: u32,
+    /// Sets `IPV6_V6ONLY` when binding an IPv6 address. `None` keeps the OS
+    /// default (dual-stack on most Linux systems).
+    #[serde(default)]
+    pub ipv6_only: Option<bool>,
+    /// Set `TCP_NODELAY` on accepted connections, sending small responses
+    /// without waiting to coalesce them (Nagle's algorithm).
+    #[serde(default)]
+    pub tcp_nodelay: bool,
+    /// Set `SO_REUSEPORT` on the listening socket so several server
+    /// processes can bind the same address and share its connections.
+    #[serde(default)]
+    pub reuse_port: bool,
+    /// Add an `X-Detected-Type` header with the detected MIME type to
+    /// successful analysis responses.
+    #[serde(default)]
+    pub emit_detected_type_header: bool,
+    /// Path prefix all routes are mounted under (e.g. `/magicer`).
+    #[serde(default)]
+    pub route_prefix: Option<String>,
+    /// Close a keep-alive connection after it has served this many requests.
+    #[serde(default)]
+    pub max_requests_per_connection: Option<u64>,
+    /// Wrap JSON bodies as `{ok: true, data}` / `{ok: false, error}`.
+    #[serde(default)]
+    pub response_envelope: bool,
+    /// Answer `426 Upgrade Required` unless a trusted proxy reports the
+    /// client connected over HTTPS (`X-Forwarded-Proto: https`).
+    #[serde(default)]
+    pub require_forwarded_proto_https: bool,
+    /// Peers whose `X-Forwarded-*` headers are believed. Empty trusts every
+    /// peer, for deployments only reachable through the proxy.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
     #[serde(default)]
     pub timeouts: TimeoutConfig,
     #[serde(default)]
@@ -47,6 +285,9 @@ fn default_port() -> u16 {
 fn default_max_connections() -> u32 {
     1000
 }
+fn default_overload_policy() -> String {
+    "queue".to_string()
+}
 fn default_backlog() -> u32 {
     1024
 }
@@ -64,8 +305,22 @@ pub struct TimeoutConfig {
     pub analysis_timeout_secs: u64,
     #[serde(default = "default_keepalive")]
     pub keepalive_secs: u64,
+    #[serde(default = "default_stream_idle")]
+    pub stream_idle_secs: u64,
+    /// Cap on a request's whole lifetime, above every other timeout. Unset,
+    /// it is derived from `analysis_timeout_secs`; see
+    /// [`TimeoutConfig::request_timeout_secs`].
+    #[serde(default)]
+    pub request_timeout_secs: Option<u64>,
+    /// Status answered when an analysis exceeds `analysis_timeout_secs`:
+    /// one of [`TIMEOUT_STATUS_CODES`].
+    #[serde(default = "default_timeout_status_code")]
+    pub timeout_status_code: u16,
 }
 
+/// Statuses `server.timeouts.timeout_status_code` may select.
+pub const TIMEOUT_STATUS_CODES: &[u16] = &[408, 503, 504];
+
 fn default_read_timeout() -> u64 {
     60
 }
@@ -75,9 +330,30 @@ fn default_write_timeout() -> u64 {
 fn default_analysis_timeout() -> u64 {
     30
 }
+fn default_stream_idle() -> u64 {
+    10
+}
 fn default_keepalive() -> u64 {
     75
 }
+/// `server.timeouts.request_timeout_secs` when unset and the analysis
+/// timeout is short.
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 120;
+
+fn default_timeout_status_code() -> u16 {
+    504
+}
+
+impl TimeoutConfig {
+    /// `request_timeout_secs`, or when unset the larger of
+    /// [`DEFAULT_REQUEST_TIMEOUT_SECS`] and twice `analysis_timeout_secs`,
+    /// so configurations predating the setting keep working.
+    pub fn request_timeout_secs(&self) -> u64 {
+        self.request_timeout_secs.unwrap_or_else(|| {
+            DEFAULT_REQUEST_TIMEOUT_SECS.max(self.analysis_timeout_secs.saturating_mul(2))
+        })
+    }
+}
 
 impl Default for TimeoutConfig {
     fn default() -> Self {
@@ -86,6 +362,9 @@ impl Default for TimeoutConfig {
             write_timeout_secs: default_write_timeout(),
             analysis_timeout_secs: default_analysis_timeout(),
             keepalive_secs: default_keepalive(),
+            stream_idle_secs: default_stream_idle(),
+            request_timeout_secs: None,
+            timeout_status_code: default_timeout_status_code(),
         }
     }
 }
@@ -98,6 +377,11 @@ pub struct LimitConfig {
     pub max_uri_length: usize,
     #[serde(default = "default_max_header_size")]
     pub max_header_size: usize,
+    #[serde(default = "default_max_batch_items")]
+    pub max_batch_items: usize,
+    /// Entries of one `/v1/magic/paths` batch analyzed at the same time.
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
 }
 
 fn default_max_body_size() -> u64 {
@@ -109,6 +393,12 @@ fn default_max_uri_length() -> usize {
 fn default_max_header_size() -> usize {
     16384
 }
+fn default_max_batch_items() -> usize {
+    100
+}
+fn default_batch_concurrency() -> usize {
+    4
+}
 
 impl Default for LimitConfig {
     fn default() -> Self {
@@ -116,6 +406,8 @@ impl Default for LimitConfig {
             max_body_size_mb: default_max_body_size(),
             max_uri_length: default_max_uri_length(),
             max_header_size: default_max_header_size(),
+            max_batch_items: default_max_batch_items(),
+            batch_concurrency: default_batch_concurrency(),
         }
     }
 }
@@ -134,6 +426,165 @@ pub struct AnalysisConfig {
     pub temp_file_max_age_secs: u64,
     #[serde(default = "default_mmap_fallback")]
     pub mmap_fallback_enabled: bool,
+    /// Memory-map files for path analysis; when off, libmagic always reads
+    /// them itself.
+    #[serde(default = "default_use_mmap")]
+    pub use_mmap: bool,
+    /// Re-analyze a path in file mode when the file shrank while mapped.
+    #[serde(default)]
+    pub retry_on_truncation: bool,
+    #[serde(default)]
+    pub peek_archives: bool,
+    #[serde(default = "default_dedupe_in_flight")]
+    pub dedupe_in_flight: bool,
+    /// Report the client's `Content-Type` (flagged unverified) when libmagic
+    /// only finds `application/octet-stream`.
+    #[serde(default)]
+    pub fallback_to_declared_type: bool,
+    /// Report this type, flagged aliased, instead of
+    /// `application/octet-stream` or `inode/x-empty`.
+    #[serde(default)]
+    pub unknown_type_alias: Option<String>,
+    /// Classify empty input as `application/x-empty` instead of rejecting it.
+    #[serde(default)]
+    pub allow_empty: bool,
+    /// Truncate `description` to this many bytes, marking it truncated.
+    #[serde(default)]
+    pub max_description_length: Option<usize>,
+    /// Strip dimensions and author/software strings from `description`.
+    #[serde(default)]
+    pub redact_description_metadata: bool,
+    #[serde(default)]
+    pub text: TextConfig,
+    /// Detections retained for `If-Content-Hash`; 0 disables the cache.
+    #[serde(default)]
+    pub content_hash_cache_entries: usize,
+    /// Reject uploads shorter than this unless they start with a signature
+    /// that identifies them on its own; 0 disables the check.
+    #[serde(default)]
+    pub min_content_bytes: usize,
+    /// Bytes all in-memory analyses may buffer at once; 0 means unbounded.
+    #[serde(default)]
+    pub max_total_memory_bytes: u64,
+    /// Answer 503 instead of spilling to a temp file once
+    /// `max_total_memory_bytes` is used up.
+    #[serde(default)]
+    pub reject_over_memory_budget: bool,
+    /// Answer 422 instead of 200 when the type detected differs from
+    /// `?expected=`.
+    #[serde(default)]
+    pub strict_expected: bool,
+    /// Largest body `?echo_content=true` returns alongside its result.
+    #[serde(default = "default_max_echo_bytes")]
+    pub max_echo_bytes: usize,
+    /// Bytes of an upload too large for memory that are analyzed; the rest
+    /// is drained and discarded unless `?full=true`. 0 analyzes everything.
+    #[serde(default = "default_head_bytes")]
+    pub head_bytes: usize,
+    /// Bytes `?hexdump=true` dumps, capped at [`MAX_HEXDUMP_BYTES`].
+    #[serde(default = "default_hexdump_bytes")]
+    pub hexdump_bytes: usize,
+    /// RAM-backed directory for temp files of uploads declared to be at
+    /// most `tmpfs_max_mb`; larger or undeclared ones stay in `temp_dir`.
+    #[serde(default)]
+    pub tmpfs_dir: Option<String>,
+    #[serde(default = "default_tmpfs_max_mb")]
+    pub tmpfs_max_mb: u64,
+}
+
+/// Upper bound on `analysis.hexdump_bytes`; each byte takes about four in
+/// the dump.
+pub const MAX_HEXDUMP_BYTES: usize = 4096;
+
+fn default_hexdump_bytes() -> usize {
+    256
+}
+
+fn default_tmpfs_max_mb() -> u64 {
+    64
+}
+
+impl AnalysisConfig {
+    /// `hexdump_bytes`, capped at [`MAX_HEXDUMP_BYTES`].
+    pub fn hexdump_bytes(&self) -> usize {
+        self.hexdump_bytes.min(MAX_HEXDUMP_BYTES)
+    }
+}
+
+fn default_max_echo_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_head_bytes() -> usize {
+    256 * 1024
+}
+
+/// Character encoding checks for `text/*` content, and which other types
+/// count as text.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TextConfig {
+    /// Report the detected charset of text content as `encoding`.
+    #[serde(default)]
+    pub detect_encoding: bool,
+    /// Reject text whose detected charset is not listed (case-insensitive).
+    /// Empty allows every charset; a non-empty list implies detection.
+    #[serde(default)]
+    pub allowed_charsets: Vec<String>,
+    /// `application/*` subtypes reported with category `text`, besides
+    /// `text/*` and `+xml`/`+json` types.
+    #[serde(default = "default_textual_subtypes")]
+    pub textual_subtypes: Vec<String>,
+}
+
+fn default_textual_subtypes() -> Vec<String> {
+    [
+        "json",
+        "xml",
+        "javascript",
+        "x-javascript",
+        "ecmascript",
+        "x-sh",
+        "x-shellscript",
+        "x-perl",
+        "x-python",
+        "x-ruby",
+        "x-php",
+        "yaml",
+        "x-yaml",
+        "toml",
+        "sql",
+    ]
+    .iter()
+    .map(|subtype| subtype.to_string())
+    .collect()
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        Self {
+            detect_encoding: false,
+            allowed_charsets: Vec::new(),
+            textual_subtypes: default_textual_subtypes(),
+        }
+    }
+}
+
+impl TextConfig {
+    pub fn is_textual(&self, mime_type: &MimeType) -> bool {
+        mime_type.is_textual(&self.textual_subtypes)
+    }
+
+    pub fn detects_encoding(&self) -> bool {
+        self.detect_encoding || !self.allowed_charsets.is_empty()
+    }
+
+    pub fn allows(&self, charset: &str) -> bool {
+        self.allowed_charsets.is_empty()
+            || self
+                .allowed_charsets
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(charset))
+    }
 }
 
 fn default_threshold() -> usize {
@@ -154,6 +605,12 @@ fn default_max_age() -> u64 {
 fn default_mmap_fallback() -> bool {
     true
 }
+fn default_use_mmap() -> bool {
+    true
+}
+fn default_dedupe_in_flight() -> bool {
+    true
+}
 
 impl Default for AnalysisConfig {
     fn default() -> Self {
@@ -164,6 +621,26 @@ impl Default for AnalysisConfig {
             min_free_space_mb: default_min_free_space(),
             temp_file_max_age_secs: default_max_age(),
             mmap_fallback_enabled: default_mmap_fallback(),
+            use_mmap: default_use_mmap(),
+            retry_on_truncation: false,
+            peek_archives: false,
+            dedupe_in_flight: default_dedupe_in_flight(),
+            fallback_to_declared_type: false,
+            unknown_type_alias: None,
+            allow_empty: false,
+            max_description_length: None,
+            redact_description_metadata: false,
+            text: TextConfig::default(),
+            content_hash_cache_entries: 0,
+            min_content_bytes: 0,
+            max_total_memory_bytes: 0,
+            reject_over_memory_budget: false,
+            strict_expected: false,
+            max_echo_bytes: default_max_echo_bytes(),
+            head_bytes: default_head_bytes(),
+            hexdump_bytes: default_hexdump_bytes(),
+            tmpfs_dir: None,
+            tmpfs_max_mb: default_tmpfs_max_mb(),
         }
     }
 }
@@ -172,6 +649,21 @@ impl Default for AnalysisConfig {
 pub struct SandboxConfig {
     #[serde(default = "default_sandbox_dir")]
     pub base_dir: String,
+    /// Subpaths of `base_dir` that path analysis refuses with 403, matched
+    /// by whole path components.
+    #[serde(default)]
+    pub denied_subpaths: Vec<String>,
+    /// `base_dir` is on a case-insensitive filesystem: path analysis
+    /// lowercases filenames and matches `denied_subpaths` regardless of case.
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Refuse path analysis through symlinks under `base_dir` with 403.
+    #[serde(default)]
+    pub deny_symlinks: bool,
+    /// Let any client with `read_sandbox` pass `?follow_symlinks=true`, not
+    /// only admins.
+    #[serde(default)]
+    pub allow_symlink_override: bool,
 }
 
 fn default_sandbox_dir() -> String {
@@ -182,6 +674,10 @@ impl Default for SandboxConfig {
     fn default() -> Self {
         Self {
             base_dir: default_sandbox_dir(),
+            denied_subpaths: Vec::new(),
+            case_insensitive: false,
+            deny_symlinks: false,
+            allow_symlink_override: false,
         }
     }
 }
@@ -192,6 +688,27 @@ pub struct AuthConfig {
     pub username: String,
     #[serde(default)]
     pub password: String,
+    #[serde(default)]
+    pub introspection_url: Option<String>,
+    #[serde(default = "default_introspection_cache_ttl")]
+    pub introspection_cache_ttl_secs: u64,
+    #[serde(default)]
+    pub quota: QuotaConfig,
+}
+
+fn default_introspection_cache_ttl() -> u64 {
+    30
+}
+
+/// Per-user limit on analyses, counted by authenticated username.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct QuotaConfig {
+    /// Successful analyses each user may run per day; 0 means unlimited.
+    #[serde(default)]
+    pub daily_limit: u64,
+    /// UTC hour (0-23) at which every user's count starts over.
+    #[serde(default)]
+    pub reset_hour_utc: u32,
 }
 
 impl std::fmt::Debug for AuthConfig {
@@ -199,6 +716,12 @@ impl std::fmt::Debug for AuthConfig {
         f.debug_struct("AuthConfig")
             .field("username", &self.username)
             .field("password", &"***")
+            .field("introspection_url", &self.introspection_url)
+            .field(
+                "introspection_cache_ttl_secs",
+                &self.introspection_cache_ttl_secs,
+            )
+            .field("quota", &self.quota)
             .finish()
     }
 }
@@ -208,6 +731,9 @@ impl Default for AuthConfig {
         Self {
             username: "".to_string(),
             password: "".to_string(),
+            introspection_url: None,
+            introspection_cache_ttl_secs: default_introspection_cache_ttl(),
+            quota: QuotaConfig::default(),
         }
     }
 }
@@ -216,6 +742,112 @@ impl Default for AuthConfig {
 pub struct MagicConfig {
     #[serde(default)]
     pub database_path: Option<String>,
+    /// Candidate databases tried in order when `database_path` is unset.
+    /// Empty leaves the choice to libmagic's compiled-in default.
+    #[serde(default)]
+    pub database_search_paths: Vec<String>,
+    #[serde(default)]
+    pub allow_custom_rules: bool,
+    /// Start without a working libmagic instead of exiting; analysis
+    /// endpoints then answer 503 until the server is restarted.
+    #[serde(default)]
+    pub fail_open_startup: bool,
+    #[serde(default)]
+    pub params: MagicParams,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub experiment: Option<ExperimentConfig>,
+    /// Nested-decompression timeouts in seconds by container MIME pattern
+    /// (`application/gzip`, `application/*`, `application/x-*`).
+    #[serde(default)]
+    pub timeouts: BTreeMap<String, u64>,
+    /// Case-insensitive substrings of libmagic descriptions (`encrypted`,
+    /// `corrupt`) whose content is refused with 422.
+    #[serde(default)]
+    pub reject_descriptions: Vec<String>,
+}
+
+impl MagicConfig {
+    /// Timeout override for `mime_type`; the longest matching pattern wins.
+    pub fn timeout_for(&self, mime_type: &MimeType) -> Option<u64> {
+        let mime = mime_type.as_str().to_ascii_lowercase();
+        self.timeouts
+            .iter()
+            .filter(|(pattern, _)| {
+                let pattern = pattern.to_ascii_lowercase();
+                match pattern.strip_suffix('*') {
+                    Some(prefix) => mime.starts_with(prefix),
+                    None => mime == pattern,
+                }
+            })
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, secs)| *secs)
+    }
+
+    /// Longest override, used to extend the pipeline deadline so an
+    /// override can take effect at all.
+    pub fn max_timeout_override(&self) -> u64 {
+        self.timeouts.values().copied().max().unwrap_or(0)
+    }
+}
+
+/// A/B test of a candidate magic database against `database_path`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExperimentConfig {
+    pub candidate_db: String,
+    /// Share of distinct contents routed to the candidate, 0.0 to 1.0.
+    pub fraction: f64,
+}
+
+/// Fast-fail analysis with 503 once libmagic keeps failing.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default = "default_breaker_window_secs")]
+    pub window_secs: u64,
+    #[serde(default = "default_breaker_open_secs")]
+    pub open_secs: u64,
+}
+
+fn default_breaker_failure_threshold() -> u32 {
+    5
+}
+
+fn default_breaker_window_secs() -> u64 {
+    30
+}
+
+fn default_breaker_open_secs() -> u64 {
+    10
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: default_breaker_failure_threshold(),
+            window_secs: default_breaker_window_secs(),
+            open_secs: default_breaker_open_secs(),
+        }
+    }
+}
+
+/// libmagic scan limits (`magic_setparam`). Unset fields keep the libmagic
+/// defaults.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MagicParams {
+    pub indir_max: Option<usize>,
+    pub name_max: Option<usize>,
+    pub elf_phnum_max: Option<usize>,
+    pub elf_shnum_max: Option<usize>,
+    pub elf_notes_max: Option<usize>,
+    pub regex_max: Option<usize>,
+    pub bytes_max: Option<usize>,
+    pub encoding_max: Option<usize>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -224,6 +856,9 @@ pub struct LoggingConfig {
     pub level: String,
     #[serde(default = "default_log_format")]
     pub format: String,
+    /// Log every Nth successful analysis at info level; 0 disables sampling.
+    #[serde(default)]
+    pub sample_rate: u64,
 }
 
 fn default_log_level() -> String {
@@ -238,18 +873,37 @@ impl Default for LoggingConfig {
         Self {
             level: default_log_level(),
             format: default_log_format(),
+            sample_rate: 0,
         }
     }
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ResponseConfig {
+    /// Reject `?fields=` selections naming unknown fields with 400 instead
+    /// of ignoring them.
+    #[serde(default)]
+    pub reject_unknown_fields: bool,
+}
+
 impl Default for ServerSection {
     fn default() -> Self {
         Self {
             host: default_host(),
             port: default_port(),
             max_connections: default_max_connections(),
+            overload_policy: default_overload_policy(),
             backlog: default_backlog(),
             max_open_files: default_max_open_files(),
+            ipv6_only: None,
+            tcp_nodelay: false,
+            reuse_port: false,
+            emit_detected_type_header: false,
+            route_prefix: None,
+            max_requests_per_connection: None,
+            response_envelope: false,
+            require_forwarded_proto_https: false,
+            trusted_proxies: Vec::new(),
             timeouts: TimeoutConfig::default(),
             limits: LimitConfig::default(),
         }
@@ -257,6 +911,18 @@ impl Default for ServerSection {
 }
 
 
+/// A route prefix must be an absolute path of its own: it starts with `/`,
+/// does not end with one, and carries no query or fragment.
+fn validate_route_prefix(prefix: &str) -> Result<(), ValidationError> {
+    if !prefix.starts_with('/') || prefix.len() < 2 || prefix.ends_with('/') {
+        return Err(ValidationError::InvalidPath);
+    }
+    if prefix.contains(['?', '#']) || prefix.chars().any(|c| c.is_whitespace()) {
+        return Err(ValidationError::InvalidCharacter);
+    }
+    Ok(())
+}
+
 impl ServerConfig {
     pub fn load(config_path: Option<String>) -> Self {
         let mut config = Self::load_from_toml(config_path).unwrap_or_default();
@@ -264,32 +930,127 @@ impl ServerConfig {
         config
     }
 
+    /// Resolve `server.host`/`server.port` to the socket address to bind.
+    /// The host may be an IPv4 address, an IPv6 address (optionally in
+    /// brackets), or a hostname, which resolves to its first address.
+    pub fn bind_addr(&self) -> Result<SocketAddr, ValidationError> {
+        let host = self.server.host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, self.server.port));
+        }
+        if !is_valid_hostname(host) {
+            return Err(ValidationError::InvalidAddress);
+        }
+        (host, self.server.port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or(ValidationError::InvalidAddress)
+    }
+
+    /// First validation failure, if any. See [`Self::validate_all`] for
+    /// every failure at once.
     pub fn validate(&self) -> Result<(), ValidationError> {
+        match self.validate_all() {
+            Ok(()) => Ok(()),
+            Err(errors) => Err(errors.0[0].1.clone()),
+        }
+    }
+
+    /// Check every setting, collecting all failures instead of stopping at
+    /// the first, so a broken config can be fixed in one pass.
+    pub fn validate_all(&self) -> Result<(), ConfigValidationErrors> {
+        let mut errors = Vec::new();
         if self.server.port == 0 {
-            return Err(ValidationError::InvalidPath); // Using InvalidPath as placeholder
+            errors.push(("server.port", ValidationError::InvalidPath)); // Using InvalidPath as placeholder
         }
         if self.server.host.is_empty() {
-            return Err(ValidationError::EmptyValue);
+            errors.push(("server.host", ValidationError::EmptyValue));
+        } else if let Err(e) = self.bind_addr() {
+            errors.push(("server.host", e));
+        }
+        if let Some(Err(e)) = self.server.route_prefix.as_deref().map(validate_route_prefix) {
+            errors.push(("server.route_prefix", e));
         }
 
         // Ensure directories exist instead of just failing
         if fs::create_dir_all(&self.sandbox.base_dir).is_err()
             && !Path::new(&self.sandbox.base_dir).exists()
         {
-            return Err(ValidationError::FileNotFound);
+            errors.push(("sandbox.base_dir", ValidationError::FileNotFound));
         }
 
         if fs::create_dir_all(&self.analysis.temp_dir).is_err()
             && !Path::new(&self.analysis.temp_dir).exists()
         {
-            return Err(ValidationError::FileNotFound);
+            errors.push(("analysis.temp_dir", ValidationError::FileNotFound));
         }
 
-        if self.auth.username.is_empty() || self.auth.password.is_empty() {
-            // In dev, we might allow empty, but for the test we'll require it
-            // return Err(ValidationError::EmptyValue);
+        if let Some(dir) = &self.analysis.tmpfs_dir
+            && fs::create_dir_all(dir).is_err()
+            && !Path::new(dir).exists()
+        {
+            errors.push(("analysis.tmpfs_dir", ValidationError::FileNotFound));
+        }
+
+        if let Some(Err(e)) = self
+            .analysis
+            .unknown_type_alias
+            .as_deref()
+            .map(MimeType::try_from)
+        {
+            errors.push(("analysis.unknown_type_alias", e));
+        }
+
+        // The request timeout is the outermost bound; at or below the
+        // analysis timeout it would pre-empt the more specific error. Only
+        // an explicit value can be, the derived one never is.
+        let timeouts = &self.server.timeouts;
+        if timeouts
+            .request_timeout_secs
+            .is_some_and(|secs| secs <= timeouts.analysis_timeout_secs)
+        {
+            errors.push((
+                "server.timeouts.request_timeout_secs",
+                ValidationError::UnsupportedValue,
+            ));
+        }
+        if !TIMEOUT_STATUS_CODES.contains(&timeouts.timeout_status_code) {
+            errors.push((
+                "server.timeouts.timeout_status_code",
+                ValidationError::UnsupportedValue,
+            ));
+        }
+
+        if !OVERLOAD_POLICIES.contains(&self.server.overload_policy.as_str()) {
+            errors.push(("server.overload_policy", ValidationError::UnsupportedValue));
+        }
+
+        if !LOG_FORMATS.contains(&self.logging.format.as_str()) {
+            errors.push(("logging.format", ValidationError::UnsupportedValue));
+        }
+
+        // Empty credentials leave basic auth disabled (dev setups); setting
+        // only one of them is a mistake.
+        match (self.auth.username.is_empty(), self.auth.password.is_empty()) {
+            (false, true) => errors.push(("auth.password", ValidationError::EmptyValue)),
+            (true, false) => errors.push(("auth.username", ValidationError::EmptyValue)),
+            _ => {}
+        }
+        if self.auth.quota.reset_hour_utc > 23 {
+            errors.push(("auth.quota.reset_hour_utc", ValidationError::UnsupportedValue));
+        }
+        if let Some(webhook) = &self.integrations.webhook
+            && webhook.max_retries > MAX_WEBHOOK_RETRIES
+        {
+            errors.push(("integrations.webhook.max_retries", ValidationError::UnsupportedValue));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigValidationErrors(errors))
         }
-        Ok(())
     }
 
     fn load_from_toml(config_path: Option<String>) -> Option<Self> {
@@ -350,3 +1111,41 @@ impl ServerConfig {
         }
     }
 }
+
+const LOG_FORMATS: &[&str] = &["json", "pretty", "compact"];
+
+const OVERLOAD_POLICIES: &[&str] = &["queue", "shed"];
+
+/// Every failure found by [`ServerConfig::validate_all`], as
+/// `(field, error)` in the order the fields were checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigValidationErrors(pub Vec<(&'static str, ValidationError)>);
+
+impl std::fmt::Display for ConfigValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} invalid setting(s): ", self.0.len())?;
+        for (i, (field, error)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}: {}", field, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationErrors {}
+
+/// RFC 1123 hostname syntax check, so obviously malformed hosts are rejected
+/// without a resolver round-trip.
+fn is_valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
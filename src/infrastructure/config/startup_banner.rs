@@ -0,0 +1,56 @@
+use crate::infrastructure::config::server_config::ServerConfig;
+use std::net::SocketAddr;
+
+/// Effective settings reported once at startup. Built from the loaded
+/// [`ServerConfig`] and carries no secrets: credentials are reduced to the
+/// authentication scheme in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupBanner {
+    pub bind_addr: SocketAddr,
+    pub auth_scheme: &'static str,
+    pub auth_username: String,
+    pub large_file_threshold_mb: usize,
+    pub temp_dir: String,
+    pub sandbox_dir: String,
+    pub magic_database: String,
+    pub max_connections: u32,
+}
+
+impl StartupBanner {
+    pub fn new(config: &ServerConfig, bind_addr: SocketAddr) -> Self {
+        let auth_scheme = if config.auth.introspection_url.is_some() {
+            "basic+bearer"
+        } else {
+            "basic"
+        };
+        Self {
+            bind_addr,
+            auth_scheme,
+            auth_username: config.auth.username.clone(),
+            large_file_threshold_mb: config.analysis.large_file_threshold_mb,
+            temp_dir: config.analysis.temp_dir.clone(),
+            sandbox_dir: config.sandbox.base_dir.clone(),
+            magic_database: config
+                .magic
+                .database_path
+                .clone()
+                .unwrap_or_else(|| "system default".to_string()),
+            max_connections: config.server.max_connections,
+        }
+    }
+
+    /// Emit the banner as a single structured log event.
+    pub fn log(&self) {
+        tracing::info!(
+            server.addr = %self.bind_addr,
+            server.max_connections = self.max_connections,
+            auth.scheme = self.auth_scheme,
+            auth.username = %self.auth_username,
+            analysis.large_file_threshold_mb = self.large_file_threshold_mb,
+            analysis.temp_dir = %self.temp_dir,
+            sandbox.base_dir = %self.sandbox_dir,
+            magic.database = %self.magic_database,
+            "Server configuration loaded"
+        );
+    }
+}
@@ -62,6 +62,17 @@ impl MmapHandler {
         unsafe { std::slice::from_raw_parts(self.addr as *const u8, self.len) }
     }
 
+    /// Hint the kernel that the mapping will be read front to back, so a
+    /// full pass (e.g. hashing) triggers readahead instead of one page fault
+    /// per page.
+    pub fn advise_sequential(&self) {
+        if !self.addr.is_null() {
+            unsafe {
+                libc::madvise(self.addr, self.len, libc::MADV_SEQUENTIAL);
+            }
+        }
+    }
+
     pub fn clear_sigbus_flag() {
         SIGBUS_OCCURRED.store(false, Ordering::SeqCst);
     }
@@ -2,3 +2,4 @@ pub mod mmap;
 pub mod sandbox;
 pub mod temp_file_handler;
 pub mod temp_storage_service;
+pub mod tiered_temp_storage;
@@ -1,20 +1,98 @@
 use crate::domain::errors::ValidationError;
 use crate::domain::services::sandbox_service::SandboxService;
 use crate::domain::value_objects::path::RelativePath;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 pub struct PathSandbox {
     base_dir: PathBuf,
+    denied_subpaths: Vec<PathBuf>,
+    case_insensitive: bool,
+    deny_symlinks: bool,
 }
 
 impl PathSandbox {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            denied_subpaths: Vec::new(),
+            case_insensitive: false,
+            deny_symlinks: false,
+        }
+    }
+
+    /// Refuse paths with a symlink in any component below `base_dir`, so a
+    /// link cannot lead outside the sandbox or into a denied subpath.
+    pub fn with_deny_symlinks(mut self, deny_symlinks: bool) -> Self {
+        self.deny_symlinks = deny_symlinks;
+        self
+    }
+
+    /// Match denied subpaths regardless of case, as the filesystem under
+    /// `base_dir` resolves names. Otherwise `Private/x` would reach a denied
+    /// `private` directory on a case-insensitive mount.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    fn folded(&self, path: PathBuf) -> PathBuf {
+        if self.case_insensitive {
+            PathBuf::from(path.to_string_lossy().to_lowercase())
+        } else {
+            path
+        }
+    }
+
+    /// Refuse paths under any of `subpaths`, given relative to `base_dir`.
+    ///
+    /// Matching is by whole components, so denying `private` leaves
+    /// `private-notes` reachable.
+    pub fn with_denied_subpaths<S: AsRef<str>>(mut self, subpaths: &[S]) -> Self {
+        self.denied_subpaths = subpaths
+            .iter()
+            .map(|s| normalized(Path::new(s.as_ref().trim_start_matches('/'))))
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect();
+        self
+    }
+}
+
+/// Whether any existing component of `relative` below `base_dir` is a
+/// symlink. Components that do not exist end the walk; opening the file
+/// reports them.
+fn has_symlink(base_dir: &Path, relative: &Path) -> bool {
+    let mut current = base_dir.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        match std::fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => return true,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
     }
+    false
+}
+
+/// Drop `.` components so `./private/x` and `private/x` compare equal.
+fn normalized(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
 }
 
 impl SandboxService for PathSandbox {
     fn resolve_path(&self, path: &RelativePath) -> Result<PathBuf, ValidationError> {
+        let full_path = self.resolve_path_following_symlinks(path)?;
+        if self.deny_symlinks && has_symlink(&self.base_dir, Path::new(path.as_str())) {
+            return Err(ValidationError::PermissionDenied);
+        }
+        Ok(full_path)
+    }
+
+    fn resolve_path_following_symlinks(
+        &self,
+        path: &RelativePath,
+    ) -> Result<PathBuf, ValidationError> {
         let full_path = self.base_dir.join(path.as_str());
 
         // Ensure the path is within base_dir
@@ -26,6 +104,17 @@ impl SandboxService for PathSandbox {
             return Err(ValidationError::PathTraversal);
         }
 
+        if let Ok(relative) = full_path.strip_prefix(&self.base_dir) {
+            let relative = self.folded(normalized(relative));
+            if self
+                .denied_subpaths
+                .iter()
+                .any(|denied| relative.starts_with(self.folded(denied.clone())))
+            {
+                return Err(ValidationError::PermissionDenied);
+            }
+        }
+
         Ok(full_path)
     }
 }
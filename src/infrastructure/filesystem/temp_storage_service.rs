@@ -1,6 +1,7 @@
 use crate::domain::services::temp_storage::{TempStorageService, TemporaryFile};
 use crate::infrastructure::filesystem::temp_file_handler::TempFileHandler;
 use async_trait::async_trait;
+use std::io::{Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use tokio::fs::{File, OpenOptions};
 use tokio::io::AsyncWriteExt;
@@ -15,7 +16,9 @@ impl FsTempFile {
         let handler = TempFileHandler::new_empty(base_dir)
             .map_err(|e| std::io::Error::other(e.to_string()))?;
         
+        // Readable too, so the descriptor can be reused for analysis.
         let file = OpenOptions::new()
+            .read(true)
             .write(true)
             .open(handler.path())
             .await?;
@@ -42,11 +45,21 @@ impl TemporaryFile for FsTempFile {
     }
 
     async fn sync(&mut self) -> Result<(), std::io::Error> {
-        if let Some(file) = self.file.take() {
+        if let Some(file) = &mut self.file {
             file.sync_all().await?;
-            Ok(())
-        } else {
-            Ok(())
+        }
+        Ok(())
+    }
+
+    async fn read_handle(&mut self) -> Result<std::fs::File, std::io::Error> {
+        match &self.file {
+            Some(file) => {
+                // The duplicate shares the write offset; rewind it.
+                let mut file = file.try_clone().await?.into_std().await;
+                file.seek(SeekFrom::Start(0))?;
+                Ok(file)
+            }
+            None => std::fs::File::open(self.path()),
         }
     }
 }
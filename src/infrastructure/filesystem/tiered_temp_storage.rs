@@ -0,0 +1,48 @@
+use crate::domain::services::temp_storage::{TempStorageService, TemporaryFile};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Places temp files by size: content declared to be at most `threshold`
+/// bytes goes to `small` (typically a tmpfs directory, so it never touches
+/// disk), everything else to `large`. Content of unknown length, such as a
+/// chunked upload, may be arbitrarily large and goes to `large`.
+pub struct TieredTempStorageService {
+    small: Arc<dyn TempStorageService>,
+    large: Arc<dyn TempStorageService>,
+    threshold: u64,
+}
+
+impl TieredTempStorageService {
+    pub fn new(
+        small: Arc<dyn TempStorageService>,
+        large: Arc<dyn TempStorageService>,
+        threshold: u64,
+    ) -> Self {
+        Self {
+            small,
+            large,
+            threshold,
+        }
+    }
+
+    fn backend_for(&self, size_hint: Option<u64>) -> &Arc<dyn TempStorageService> {
+        match size_hint {
+            Some(size) if size <= self.threshold => &self.small,
+            _ => &self.large,
+        }
+    }
+}
+
+#[async_trait]
+impl TempStorageService for TieredTempStorageService {
+    async fn create_temp_file(&self) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.large.create_temp_file().await
+    }
+
+    async fn create_temp_file_sized(
+        &self,
+        size_hint: Option<u64>,
+    ) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.backend_for(size_hint).create_temp_file().await
+    }
+}
@@ -0,0 +1,2 @@
+pub mod result_signature;
+pub mod webhook_result_sink;
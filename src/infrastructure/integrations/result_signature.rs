@@ -0,0 +1,58 @@
+use crate::infrastructure::integrations::webhook_result_sink::sign;
+use serde_json::Value;
+
+/// Field of a signed response carrying its signature.
+pub const SIGNATURE_FIELD: &str = "signature";
+
+/// `sha256=` followed by the hex HMAC-SHA256 of the canonical form of
+/// `payload` under `key`. A `signature` member of `payload` is not covered,
+/// so a response can be verified by removing it and signing the rest.
+pub fn sign_payload(key: &str, payload: &Value) -> String {
+    let canonical = match payload {
+        Value::Object(map) if map.contains_key(SIGNATURE_FIELD) => {
+            let mut unsigned = map.clone();
+            unsigned.remove(SIGNATURE_FIELD);
+            canonical_json(&Value::Object(unsigned))
+        }
+        _ => canonical_json(payload),
+    };
+    sign(key, canonical.as_bytes())
+}
+
+/// Canonical JSON of `value`: object keys sorted by their UTF-8 bytes, no
+/// whitespace, strings escaped as `serde_json` does.
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
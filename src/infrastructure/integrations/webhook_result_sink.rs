@@ -0,0 +1,135 @@
+use crate::domain::entities::magic_result::MagicResult;
+use crate::domain::services::result_sink::ResultSink;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>`.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// POSTs each result as JSON to a webhook. Delivery runs on a spawned task
+/// and is retried with exponential backoff on transport errors and non-2xx
+/// responses; a result is dropped (and logged) once retries are exhausted.
+/// At most `max_pending` deliveries run at once; while that many are in
+/// progress, further results are dropped (and logged) rather than queued.
+pub struct WebhookResultSink {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<String>,
+    max_retries: u32,
+    pending: Arc<Semaphore>,
+}
+
+impl WebhookResultSink {
+    pub fn new(
+        url: &str,
+        secret: Option<String>,
+        max_retries: u32,
+    ) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            secret,
+            max_retries,
+            pending: Arc::new(Semaphore::new(256)),
+        })
+    }
+
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.pending = Arc::new(Semaphore::new(max_pending));
+        self
+    }
+
+    fn payload(result: &MagicResult) -> Vec<u8> {
+        serde_json::json!({
+            "request_id": result.request_id().as_str(),
+            "filename": result.filename().as_str(),
+            "mime_type": result.mime_type().as_str(),
+            "description": result.description(),
+            "analyzed_at": result.analyzed_at().to_rfc3339(),
+        })
+        .to_string()
+        .into_bytes()
+    }
+}
+
+/// Wait before retry `attempt` (1-based): 500ms doubling per attempt, capped
+/// at a minute.
+pub fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `body` under `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+impl ResultSink for WebhookResultSink {
+    fn publish(&self, result: &MagicResult) {
+        let Ok(permit) = Arc::clone(&self.pending).try_acquire_owned() else {
+            tracing::error!(
+                request_id = %result.request_id(),
+                "Dropping analysis result: too many webhook deliveries pending"
+            );
+            return;
+        };
+        let body = Self::payload(result);
+        let signature = self.secret.as_deref().map(|secret| sign(secret, &body));
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let max_retries = self.max_retries;
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            for attempt in 0..=max_retries {
+                if attempt > 0 {
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                }
+                let mut request = client
+                    .post(&url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body.clone());
+                if let Some(signature) = &signature {
+                    request = request.header(SIGNATURE_HEADER, signature);
+                }
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => return,
+                    Ok(response) => tracing::warn!(
+                        webhook.attempt = attempt + 1,
+                        http.response.status_code = response.status().as_u16(),
+                        "Webhook delivery rejected"
+                    ),
+                    Err(e) => tracing::warn!(
+                        webhook.attempt = attempt + 1,
+                        error = %e,
+                        "Webhook delivery failed"
+                    ),
+                }
+            }
+            tracing::error!(
+                webhook.attempts = max_retries.saturating_add(1),
+                "Dropping analysis result after exhausting webhook retries"
+            );
+        });
+    }
+}
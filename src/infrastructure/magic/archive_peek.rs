@@ -0,0 +1,41 @@
+use std::io::Read;
+
+/// Maximum number of bytes of the first member handed to libmagic.
+pub const PEEK_PREFIX_BYTES: usize = 8192;
+
+/// Name and leading bytes of the first member of an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivePeek {
+    pub name: String,
+    pub prefix: Vec<u8>,
+}
+
+/// Reads the first zip local file header and at most [`PEEK_PREFIX_BYTES`]
+/// of its (decompressed) content. Only stored and deflate members are
+/// supported; anything else yields `None`.
+pub fn peek_zip(data: &[u8]) -> Option<ArchivePeek> {
+    let mut reader = data;
+    let member = zip::read::read_zipfile_from_stream(&mut reader).ok()??;
+    let name = member.name().to_string();
+    let prefix = read_prefix(member)?;
+    Some(ArchivePeek { name, prefix })
+}
+
+/// Reads the first tar header and at most [`PEEK_PREFIX_BYTES`] of the
+/// member's content. The header checksum must be valid.
+pub fn peek_tar(data: &[u8]) -> Option<ArchivePeek> {
+    let mut archive = tar::Archive::new(data);
+    let member = archive.entries().ok()?.next()?.ok()?;
+    let name = member.path().ok()?.to_string_lossy().into_owned();
+    let prefix = read_prefix(member)?;
+    Some(ArchivePeek { name, prefix })
+}
+
+fn read_prefix(member: impl Read) -> Option<Vec<u8>> {
+    let mut prefix = Vec::with_capacity(PEEK_PREFIX_BYTES);
+    member
+        .take(PEEK_PREFIX_BYTES as u64)
+        .read_to_end(&mut prefix)
+        .ok()?;
+    Some(prefix)
+}
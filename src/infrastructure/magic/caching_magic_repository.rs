@@ -0,0 +1,132 @@
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::value_objects::mime_type::MimeType;
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell};
+
+type ContentHash = [u8; 32];
+type AnalysisOutcome = Result<(MimeType, String), MagicError>;
+
+/// Decorator that collapses concurrent analyses of identical content into a
+/// single call to the wrapped repository (singleflight).
+///
+/// Requests are keyed on the SHA-256 of the buffer. The first caller for a
+/// key runs the analysis; callers arriving while it is in flight await the
+/// same [`OnceCell`] and receive a clone of its outcome. The entry is
+/// dropped once the flight completes, so results are not retained.
+pub struct CachingMagicRepository {
+    inner: Arc<dyn MagicRepository>,
+    in_flight: Mutex<HashMap<ContentHash, Arc<OnceCell<AnalysisOutcome>>>>,
+}
+
+impl CachingMagicRepository {
+    pub fn new(inner: Arc<dyn MagicRepository>) -> Self {
+        Self {
+            inner,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn complete_flight(&self, key: &ContentHash, cell: &Arc<OnceCell<AnalysisOutcome>>) {
+        let mut in_flight = self.in_flight.lock().await;
+        if in_flight.get(key).is_some_and(|c| Arc::ptr_eq(c, cell)) {
+            in_flight.remove(key);
+        }
+    }
+}
+
+impl MagicRepository for CachingMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move {
+            let key: ContentHash = Sha256::digest(data).into();
+
+            let cell = {
+                let mut in_flight = self.in_flight.lock().await;
+                in_flight.entry(key).or_default().clone()
+            };
+
+            // If the caller driving the cell is cancelled, the next waiter
+            // takes over the initialisation.
+            let outcome = cell
+                .get_or_init(|| self.inner.analyze_buffer(data, filename))
+                .await
+                .clone();
+
+            self.complete_flight(&key, &cell).await;
+            outcome
+        })
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.inner.analyze_buffer_decompressed(data, filename)
+    }
+
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.inner.analyze_file(file, filename)
+    }
+
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.inner.describe_buffer(data, filename)
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        self.inner.detect_encoding(data, filename)
+    }
+
+    fn trace_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.inner.trace_buffer(data, filename)
+    }
+
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        self.inner.analyze_all_types(data, filename)
+    }
+
+    fn database_variant(&self, data: &[u8]) -> Option<&'static str> {
+        self.inner.database_variant(data)
+    }
+
+    fn database_variant_for_file(&self, file: &File) -> Option<&'static str> {
+        self.inner.database_variant_for_file(file)
+    }
+
+    fn analyze_with_rules<'a>(
+        &'a self,
+        rule_path: &'a Path,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.inner.analyze_with_rules(rule_path, data)
+    }
+}
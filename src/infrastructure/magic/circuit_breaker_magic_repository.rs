@@ -0,0 +1,320 @@
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::value_objects::mime_type::MimeType;
+use futures_util::future::BoxFuture;
+use opentelemetry::metrics::Gauge;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Breaker state as reported by the `app.magic.circuit_breaker.state` gauge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed = 0,
+    HalfOpen = 1,
+    Open = 2,
+}
+
+#[derive(Debug)]
+enum State {
+    /// Calls pass through; `failures` consecutive failures have been seen
+    /// since `window_start`.
+    Closed {
+        failures: u32,
+        window_start: Instant,
+    },
+    /// Calls fail fast until `until`.
+    Open { until: Instant },
+    /// A single probe started at `probe_started` is deciding whether to
+    /// close again. Everyone else still fails fast.
+    HalfOpen { probe_started: Instant },
+}
+
+/// Decorator that stops calling a repository which keeps failing.
+///
+/// After `failure_threshold` consecutive failures within `window` the breaker
+/// opens and every call fails immediately with [`MagicError::Unavailable`]
+/// instead of paying for another doomed analysis. Once `open_duration` has
+/// elapsed one call is let through as a probe: success closes the breaker,
+/// failure opens it for another `open_duration`. A probe that never reports
+/// back is replaced after `open_duration`.
+///
+/// Only analysis failures count; a missing file says nothing about libmagic.
+/// With a call timeout (see [`Self::with_call_timeout`]) a call that runs out
+/// of it counts as a failure too, whether the timeout fires here or in the
+/// caller. A call dropped earlier, e.g. because the client went away, counts
+/// as neither.
+pub struct CircuitBreakerMagicRepository {
+    inner: Arc<dyn MagicRepository>,
+    failure_threshold: u32,
+    window: Duration,
+    open_duration: Duration,
+    call_timeout: Option<Duration>,
+    state: Mutex<State>,
+    state_gauge: Option<Gauge<u64>>,
+}
+
+/// Callers start their own timeout just before the call is admitted, so a
+/// drop this close to the call timeout is taken as that timeout firing.
+const DEADLINE_SLACK: Duration = Duration::from_millis(50);
+
+impl CircuitBreakerMagicRepository {
+    pub fn new(
+        inner: Arc<dyn MagicRepository>,
+        failure_threshold: u32,
+        window: Duration,
+        open_duration: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            failure_threshold: failure_threshold.max(1),
+            window,
+            open_duration,
+            call_timeout: None,
+            state: Mutex::new(State::Closed {
+                failures: 0,
+                window_start: Instant::now(),
+            }),
+            state_gauge: None,
+        }
+    }
+
+    /// Record every state change on `gauge` (0 closed, 1 half-open, 2 open).
+    pub fn with_state_gauge(mut self, gauge: Gauge<u64>) -> Self {
+        gauge.record(BreakerState::Closed as u64, &[]);
+        self.state_gauge = Some(gauge);
+        self
+    }
+
+    /// Fail calls that take longer than `timeout` and count them, along
+    /// with calls dropped by a caller's timeout of the same length, as
+    /// failures.
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = Some(timeout);
+        self
+    }
+
+    pub fn state(&self) -> BreakerState {
+        breaker_state(&self.state.lock().unwrap())
+    }
+
+    fn transition(&self, state: &mut State, next: State) {
+        let before = breaker_state(state);
+        *state = next;
+        let after = breaker_state(state);
+        if before != after {
+            match after {
+                BreakerState::Open => tracing::warn!("libmagic circuit breaker opened"),
+                BreakerState::Closed => tracing::info!("libmagic circuit breaker closed"),
+                BreakerState::HalfOpen => {}
+            }
+            if let Some(gauge) = &self.state_gauge {
+                gauge.record(after as u64, &[]);
+            }
+        }
+    }
+
+    /// Decide whether a call may proceed, moving an expired open breaker to
+    /// half-open for the caller that becomes the probe.
+    fn admit(&self) -> Result<(), MagicError> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now >= until => {
+                self.transition(&mut state, State::HalfOpen { probe_started: now });
+                Ok(())
+            }
+            State::HalfOpen { probe_started }
+                if now.duration_since(probe_started) >= self.open_duration =>
+            {
+                *state = State::HalfOpen { probe_started: now };
+                Ok(())
+            }
+            State::Open { .. } | State::HalfOpen { .. } => Err(MagicError::Unavailable(
+                "libmagic circuit breaker is open".to_string(),
+            )),
+        }
+    }
+
+    fn record<T>(&self, outcome: &Result<T, MagicError>) {
+        self.record_failed(matches!(
+            outcome,
+            Err(MagicError::AnalysisFailed(_)
+                | MagicError::DatabaseLoadFailed(_)
+                | MagicError::Unavailable(_))
+        ));
+    }
+
+    fn record_failed(&self, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let next = match (&*state, failed) {
+            (State::Closed { .. }, false) | (State::HalfOpen { .. }, false) => State::Closed {
+                failures: 0,
+                window_start: now,
+            },
+            (
+                State::Closed {
+                    failures,
+                    window_start,
+                },
+                true,
+            ) => {
+                let (failures, window_start) = if now.duration_since(*window_start) > self.window {
+                    (1, now)
+                } else {
+                    (failures + 1, *window_start)
+                };
+                if failures >= self.failure_threshold {
+                    State::Open {
+                        until: now + self.open_duration,
+                    }
+                } else {
+                    State::Closed {
+                        failures,
+                        window_start,
+                    }
+                }
+            }
+            (State::HalfOpen { .. }, true) => State::Open {
+                until: now + self.open_duration,
+            },
+            // A call admitted before the breaker opened finished late; the
+            // breaker already made its decision.
+            (State::Open { .. }, _) => return,
+        };
+        self.transition(&mut state, next);
+    }
+
+    fn guarded<'a, T: Send + 'a>(
+        &'a self,
+        call: BoxFuture<'a, Result<T, MagicError>>,
+    ) -> BoxFuture<'a, Result<T, MagicError>> {
+        Box::pin(async move {
+            self.admit()?;
+            let mut pending = PendingCall {
+                breaker: self,
+                started: Instant::now(),
+                finished: false,
+            };
+            let outcome = match self.call_timeout {
+                Some(limit) => tokio::time::timeout(limit, call).await.unwrap_or_else(|_| {
+                    Err(MagicError::AnalysisFailed(format!(
+                        "libmagic did not answer within {}s",
+                        limit.as_secs_f64()
+                    )))
+                }),
+                None => call.await,
+            };
+            pending.finished = true;
+            self.record(&outcome);
+            outcome
+        })
+    }
+}
+
+/// Records a failure if the admitted call is dropped once its call timeout
+/// has run out.
+struct PendingCall<'a> {
+    breaker: &'a CircuitBreakerMagicRepository,
+    started: Instant,
+    finished: bool,
+}
+
+impl Drop for PendingCall<'_> {
+    fn drop(&mut self) {
+        let timed_out = self
+            .breaker
+            .call_timeout
+            .is_some_and(|limit| self.started.elapsed() + DEADLINE_SLACK >= limit);
+        if !self.finished && timed_out {
+            self.breaker.record_failed(true);
+        }
+    }
+}
+
+fn breaker_state(state: &State) -> BreakerState {
+    match state {
+        State::Closed { .. } => BreakerState::Closed,
+        State::Open { .. } => BreakerState::Open,
+        State::HalfOpen { .. } => BreakerState::HalfOpen,
+    }
+}
+
+impl MagicRepository for CircuitBreakerMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.guarded(self.inner.analyze_buffer(data, filename))
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.guarded(self.inner.analyze_buffer_decompressed(data, filename))
+    }
+
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.guarded(self.inner.analyze_file(file, filename))
+    }
+
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.guarded(self.inner.describe_buffer(data, filename))
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        self.guarded(self.inner.detect_encoding(data, filename))
+    }
+
+    fn trace_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.guarded(self.inner.trace_buffer(data, filename))
+    }
+
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        self.guarded(self.inner.analyze_all_types(data, filename))
+    }
+
+    fn database_variant(&self, data: &[u8]) -> Option<&'static str> {
+        self.inner.database_variant(data)
+    }
+
+    fn database_variant_for_file(&self, file: &File) -> Option<&'static str> {
+        self.inner.database_variant_for_file(file)
+    }
+
+    /// Custom rules run against a fresh cookie, so their failures say nothing
+    /// about the loaded database and bypass the breaker.
+    fn analyze_with_rules<'a>(
+        &'a self,
+        rule_path: &'a Path,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.inner.analyze_with_rules(rule_path, data)
+    }
+}
@@ -0,0 +1,160 @@
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::value_objects::mime_type::MimeType;
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Which database of a `[magic.experiment]` analyzed the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExperimentArm {
+    Control,
+    Candidate,
+}
+
+impl ExperimentArm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Control => "control",
+            Self::Candidate => "candidate",
+        }
+    }
+}
+
+/// Decorator splitting detections between the configured database
+/// (`control`) and a candidate database being evaluated.
+///
+/// Content is assigned by its SHA-256, so the same bytes always reach the
+/// same database and repeated requests can be compared. On average
+/// `fraction` of distinct contents go to the candidate. Files analyzed
+/// without mapping are assigned by device, inode and size instead, since
+/// hashing their content would mean reading it all.
+pub struct ExperimentMagicRepository {
+    control: Arc<dyn MagicRepository>,
+    candidate: Arc<dyn MagicRepository>,
+    fraction: f64,
+}
+
+impl ExperimentMagicRepository {
+    pub fn new(
+        control: Arc<dyn MagicRepository>,
+        candidate: Arc<dyn MagicRepository>,
+        fraction: f64,
+    ) -> Self {
+        Self {
+            control,
+            candidate,
+            fraction: fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn assign(&self, data: &[u8]) -> ExperimentArm {
+        let digest = Sha256::digest(data);
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        let position = u64::from_be_bytes(prefix) as f64 / u64::MAX as f64;
+        if position < self.fraction {
+            ExperimentArm::Candidate
+        } else {
+            ExperimentArm::Control
+        }
+    }
+
+    /// The arm for an open file, keyed by its identity rather than content.
+    pub fn assign_file(&self, file: &File) -> ExperimentArm {
+        let key = file
+            .metadata()
+            .map(|m| format!("{}:{}:{}", m.dev(), m.ino(), m.len()))
+            .unwrap_or_default();
+        self.assign(key.as_bytes())
+    }
+
+    fn route(&self, data: &[u8]) -> &Arc<dyn MagicRepository> {
+        self.arm(self.assign(data))
+    }
+
+    fn arm(&self, arm: ExperimentArm) -> &Arc<dyn MagicRepository> {
+        match arm {
+            ExperimentArm::Control => &self.control,
+            ExperimentArm::Candidate => &self.candidate,
+        }
+    }
+}
+
+impl MagicRepository for ExperimentMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.route(data).analyze_buffer(data, filename)
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.route(data).analyze_buffer_decompressed(data, filename)
+    }
+
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.arm(self.assign_file(file)).analyze_file(file, filename)
+    }
+
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.route(data).describe_buffer(data, filename)
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        self.route(data).detect_encoding(data, filename)
+    }
+
+    fn trace_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.route(data).trace_buffer(data, filename)
+    }
+
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        self.route(data).analyze_all_types(data, filename)
+    }
+
+    fn database_variant(&self, data: &[u8]) -> Option<&'static str> {
+        Some(self.assign(data).name())
+    }
+
+    fn database_variant_for_file(&self, file: &File) -> Option<&'static str> {
+        Some(self.assign_file(file).name())
+    }
+
+    /// Custom rules replace the database, so there is nothing to compare.
+    fn analyze_with_rules<'a>(
+        &'a self,
+        rule_path: &'a Path,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.control.analyze_with_rules(rule_path, data)
+    }
+}
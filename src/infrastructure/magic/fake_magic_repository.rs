@@ -1,16 +1,117 @@
 use crate::domain::errors::MagicError;
 use crate::domain::repositories::magic_repository::MagicRepository;
 use crate::domain::value_objects::mime_type::MimeType;
+use flate2::read::GzDecoder;
 use futures_util::future::BoxFuture;
+use std::io::Read;
+use std::time::Duration;
 
-pub struct FakeMagicRepository;
+/// Test double for [`MagicRepository`]. Detection is by byte prefix: custom
+/// mappings registered through [`FakeMagicRepositoryBuilder`] are tried in
+/// registration order, then the built-in PDF/PNG/shell/zip/gzip/tar rules
+/// when enabled, and anything else is `application/octet-stream`.
+pub struct FakeMagicRepository {
+    mappings: Vec<(Vec<u8>, MimeType, String)>,
+    defaults: bool,
+    delay: Option<Duration>,
+    prefix_delays: Vec<(Vec<u8>, Duration)>,
+}
+
+#[derive(Default)]
+pub struct FakeMagicRepositoryBuilder {
+    mappings: Vec<(Vec<u8>, MimeType, String)>,
+    defaults: bool,
+    delay: Option<Duration>,
+    prefix_delays: Vec<(Vec<u8>, Duration)>,
+}
+
+impl FakeMagicRepositoryBuilder {
+    /// Enable the built-in detection rules.
+    pub fn with_defaults(mut self) -> Self {
+        self.defaults = true;
+        self
+    }
+
+    /// Report `mime_type`/`description` for content starting with `prefix`.
+    pub fn with_mapping(mut self, prefix: &[u8], mime_type: &str, description: &str) -> Self {
+        let mime_type = MimeType::try_from(mime_type).expect("valid MIME type");
+        self.mappings
+            .push((prefix.to_vec(), mime_type, description.to_string()));
+        self
+    }
+
+    /// Sleep for `delay` before answering each call.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sleep for `delay` before answering calls for content starting with
+    /// `prefix`, instead of any delay set with [`with_delay`](Self::with_delay).
+    pub fn with_delay_for(mut self, prefix: &[u8], delay: Duration) -> Self {
+        self.prefix_delays.push((prefix.to_vec(), delay));
+        self
+    }
+
+    pub fn build(self) -> FakeMagicRepository {
+        FakeMagicRepository {
+            mappings: self.mappings,
+            defaults: self.defaults,
+            delay: self.delay,
+            prefix_delays: self.prefix_delays,
+        }
+    }
+}
 
 impl FakeMagicRepository {
     pub fn new() -> Result<Self, MagicError> {
-        Ok(Self)
+        Ok(Self::builder().with_defaults().build())
+    }
+
+    pub fn builder() -> FakeMagicRepositoryBuilder {
+        FakeMagicRepositoryBuilder::default()
+    }
+
+    fn detect(&self, data: &[u8]) -> (MimeType, String) {
+        let custom = self
+            .mappings
+            .iter()
+            .find(|(prefix, _, _)| data.starts_with(prefix))
+            .map(|(_, mime_type, description)| (mime_type.clone(), description.clone()));
+        let builtin = || self.defaults.then(|| default_detect(data)).flatten();
+        custom.or_else(builtin).unwrap_or_else(|| {
+            (
+                MimeType::try_from("application/octet-stream").unwrap(),
+                "data".to_string(),
+            )
+        })
     }
 }
 
+fn default_detect(data: &[u8]) -> Option<(MimeType, String)> {
+    let (mime_type, description) = if data.starts_with(b"%PDF") {
+        ("application/pdf", "PDF document")
+    } else if data.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
+        ("image/png", "PNG image data")
+    } else if data.starts_with(b"#!/bin/sh") {
+        ("text/x-shellscript", "shell script")
+    } else if data.starts_with(b"PK\x03\x04") {
+        ("application/zip", "Zip archive data")
+    } else if data.starts_with(&[0x1F, 0x8B]) {
+        ("application/gzip", "gzip compressed data")
+    } else if data.starts_with(&[0xFF, 0xFE]) || data.starts_with(&[0xFE, 0xFF]) {
+        ("text/plain", "Unicode text, UTF-16 text")
+    } else if data.get(257..262) == Some(b"ustar".as_slice()) {
+        ("application/x-tar", "POSIX tar archive")
+    } else {
+        return None;
+    };
+    Some((
+        MimeType::try_from(mime_type).unwrap(),
+        description.to_string(),
+    ))
+}
+
 impl MagicRepository for FakeMagicRepository {
     fn analyze_buffer<'a>(
         &'a self,
@@ -18,28 +119,79 @@ impl MagicRepository for FakeMagicRepository {
         _filename: &'a str,
     ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
         Box::pin(async move {
-            if data.starts_with(b"%PDF") {
-                return Ok((
-                    MimeType::try_from("application/pdf").unwrap(),
-                    "PDF document".to_string(),
-                ));
+            let delay = self
+                .prefix_delays
+                .iter()
+                .find(|(prefix, _)| data.starts_with(prefix))
+                .map(|(_, delay)| *delay)
+                .or(self.delay);
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
             }
-            if data.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
-                return Ok((
-                    MimeType::try_from("image/png").unwrap(),
-                    "PNG image data".to_string(),
-                ));
+            Ok(self.detect(data))
+        })
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move {
+            if !data.starts_with(&[0x1F, 0x8B]) {
+                return self.analyze_buffer(data, filename).await;
             }
-            if data.starts_with(b"#!/bin/sh") {
-                return Ok((
-                    MimeType::try_from("text/x-shellscript").unwrap(),
-                    "shell script".to_string(),
-                ));
+            let mut decoded = Vec::new();
+            GzDecoder::new(data)
+                .read_to_end(&mut decoded)
+                .map_err(|e| MagicError::AnalysisFailed(e.to_string()))?;
+            self.analyze_buffer(&decoded, filename).await
+        })
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        Box::pin(async move { Ok(Some(default_encoding(data).to_string())) })
+    }
+
+    /// Every custom mapping whose prefix matches, in registration order, so
+    /// overlapping mappings stand in for a polyglot.
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        Box::pin(async move {
+            let mut types: Vec<MimeType> = Vec::new();
+            for (prefix, mime_type, _) in &self.mappings {
+                if data.starts_with(prefix) && !types.contains(mime_type) {
+                    types.push(mime_type.clone());
+                }
             }
-            Ok((
-                MimeType::try_from("application/octet-stream").unwrap(),
-                "data".to_string(),
-            ))
+            if types.is_empty() {
+                let (mime_type, _) = self.analyze_buffer(data, filename).await?;
+                types.push(mime_type);
+            }
+            Ok(types)
         })
     }
 }
+
+/// Rough stand-in for libmagic's `--mime-encoding`: BOM-marked UTF-16,
+/// then ASCII/UTF-8 by validity.
+fn default_encoding(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0xFF, 0xFE]) {
+        "utf-16le"
+    } else if data.starts_with(&[0xFE, 0xFF]) {
+        "utf-16be"
+    } else if data.is_ascii() {
+        "us-ascii"
+    } else if std::str::from_utf8(data).is_ok() {
+        "utf-8"
+    } else {
+        "binary"
+    }
+}
@@ -3,15 +3,36 @@ use std::os::raw::{c_char, c_int, c_void};
 pub type MagicT = *mut c_void;
 
 pub const MAGIC_NONE: c_int = 0x000000;
+pub const MAGIC_DEBUG: c_int = 0x000001;
+pub const MAGIC_COMPRESS: c_int = 0x000004;
 pub const MAGIC_MIME_TYPE: c_int = 0x000010;
+pub const MAGIC_CONTINUE: c_int = 0x000020;
+pub const MAGIC_CHECK: c_int = 0x000040;
 pub const MAGIC_ERROR: c_int = 0x000200;
+pub const MAGIC_MIME_ENCODING: c_int = 0x000400;
+
+pub const MAGIC_PARAM_INDIR_MAX: c_int = 0;
+pub const MAGIC_PARAM_NAME_MAX: c_int = 1;
+pub const MAGIC_PARAM_ELF_PHNUM_MAX: c_int = 2;
+pub const MAGIC_PARAM_ELF_SHNUM_MAX: c_int = 3;
+pub const MAGIC_PARAM_ELF_NOTES_MAX: c_int = 4;
+pub const MAGIC_PARAM_REGEX_MAX: c_int = 5;
+pub const MAGIC_PARAM_BYTES_MAX: c_int = 6;
+pub const MAGIC_PARAM_ENCODING_MAX: c_int = 7;
 
 #[link(name = "magic")]
 unsafe extern "C" {
     pub fn magic_open(flags: c_int) -> MagicT;
     pub fn magic_close(ms: MagicT);
     pub fn magic_error(ms: MagicT) -> *const c_char;
+    pub fn magic_errno(ms: MagicT) -> c_int;
     pub fn magic_load(ms: MagicT, filename: *const c_char) -> c_int;
+    pub fn magic_check(ms: MagicT, filename: *const c_char) -> c_int;
     pub fn magic_buffer(ms: MagicT, buf: *const c_void, nb: usize) -> *const c_char;
     pub fn magic_file(ms: MagicT, filename: *const c_char) -> *const c_char;
+    pub fn magic_descriptor(ms: MagicT, fd: c_int) -> *const c_char;
+    pub fn magic_getflags(ms: MagicT) -> c_int;
+    pub fn magic_setflags(ms: MagicT, flags: c_int) -> c_int;
+    pub fn magic_setparam(ms: MagicT, param: c_int, value: *const c_void) -> c_int;
+    pub fn magic_version() -> c_int;
 }
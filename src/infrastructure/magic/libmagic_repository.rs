@@ -1,21 +1,160 @@
 use crate::domain::errors::MagicError;
 use crate::domain::repositories::magic_repository::MagicRepository;
 use crate::domain::value_objects::mime_type::MimeType;
+use crate::infrastructure::config::server_config::{MagicConfig, MagicParams};
 use crate::infrastructure::magic::ffi::*;
 use crate::infrastructure::magic::wrapper::MagicCookie;
 use futures_util::future::BoxFuture;
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::os::fd::AsRawFd;
+use std::path::Path;
 use std::sync::Arc;
 
 pub struct LibmagicRepository {
     cookie: Arc<MagicCookie>,
+    compress_cookie: Arc<MagicCookie>,
+    /// `MAGIC_CONTINUE` cookie reporting every match, for polyglot checks.
+    continue_cookie: Arc<MagicCookie>,
+    describe_cookie: Arc<MagicCookie>,
+    encoding_cookie: Arc<MagicCookie>,
+    /// libmagic only applies `MAGIC_PARAM_BYTES_MAX` when it reads a file
+    /// itself, so buffers are truncated here to honour it.
+    bytes_max: Option<usize>,
 }
 
 impl LibmagicRepository {
-    pub fn new(_mmap_fallback_enabled: bool) -> Result<Self, MagicError> {
+    pub fn new(mmap_fallback_enabled: bool) -> Result<Self, MagicError> {
+        Self::with_params(mmap_fallback_enabled, &MagicParams::default())
+    }
+
+    /// Like [`new`](Self::new), applying `params` to every cookie after the
+    /// database is loaded.
+    pub fn with_params(
+        mmap_fallback_enabled: bool,
+        params: &MagicParams,
+    ) -> Result<Self, MagicError> {
+        Self::with_database(mmap_fallback_enabled, params, None)
+    }
+
+    /// Load the database `[magic]` names: `database_path` when set,
+    /// otherwise the first of `database_search_paths` that exists, otherwise
+    /// libmagic's compiled-in default.
+    pub fn from_config(
+        mmap_fallback_enabled: bool,
+        magic: &MagicConfig,
+    ) -> Result<Self, MagicError> {
+        let database =
+            Self::resolve_database(magic.database_path.as_deref(), &magic.database_search_paths)?;
+        Self::with_database(mmap_fallback_enabled, &magic.params, database.as_deref())
+    }
+
+    /// The database to load: `database_path` when given, otherwise the first
+    /// of `search_paths` that exists. `None` (libmagic's default) when both
+    /// are unset; an error when candidates are listed but none exists.
+    pub fn resolve_database(
+        database_path: Option<&str>,
+        search_paths: &[String],
+    ) -> Result<Option<String>, MagicError> {
+        if let Some(path) = database_path {
+            return Ok(Some(path.to_string()));
+        }
+        if search_paths.is_empty() {
+            return Ok(None);
+        }
+        match search_paths.iter().find(|path| Path::new(path).exists()) {
+            Some(path) => {
+                tracing::info!(magic.database = %path, "Using magic database from search path");
+                Ok(Some(path.clone()))
+            }
+            None => Err(MagicError::DatabaseLoadFailed(format!(
+                "no magic database found in magic.database_search_paths: {}",
+                search_paths.join(", ")
+            ))),
+        }
+    }
+
+    /// Like [`with_params`](Self::with_params), loading `database` (a `.mgc`
+    /// file or source rules) instead of the system default when given.
+    pub fn with_database(
+        _mmap_fallback_enabled: bool,
+        params: &MagicParams,
+        database: Option<&str>,
+    ) -> Result<Self, MagicError> {
         let cookie = MagicCookie::open(MAGIC_MIME_TYPE)?;
-        cookie.load(None)?; // Load default database
+        cookie.load(database)?;
+        Self::apply_params(&cookie, params)?;
+        let compress_cookie = MagicCookie::open(MAGIC_MIME_TYPE | MAGIC_COMPRESS)?;
+        compress_cookie.load(database)?;
+        Self::apply_params(&compress_cookie, params)?;
+        let continue_cookie = MagicCookie::open(MAGIC_MIME_TYPE | MAGIC_CONTINUE)?;
+        continue_cookie.load(database)?;
+        Self::apply_params(&continue_cookie, params)?;
+        let describe_cookie = MagicCookie::open(MAGIC_NONE)?;
+        describe_cookie.load(database)?;
+        Self::apply_params(&describe_cookie, params)?;
+        let encoding_cookie = MagicCookie::open(MAGIC_MIME_ENCODING)?;
+        encoding_cookie.load(database)?;
+        Self::apply_params(&encoding_cookie, params)?;
         Ok(Self {
             cookie: Arc::new(cookie),
+            compress_cookie: Arc::new(compress_cookie),
+            continue_cookie: Arc::new(continue_cookie),
+            describe_cookie: Arc::new(describe_cookie),
+            encoding_cookie: Arc::new(encoding_cookie),
+            bytes_max: params.bytes_max,
+        })
+    }
+
+    fn apply_params(cookie: &MagicCookie, params: &MagicParams) -> Result<(), MagicError> {
+        let settings = [
+            (MAGIC_PARAM_INDIR_MAX, params.indir_max),
+            (MAGIC_PARAM_NAME_MAX, params.name_max),
+            (MAGIC_PARAM_ELF_PHNUM_MAX, params.elf_phnum_max),
+            (MAGIC_PARAM_ELF_SHNUM_MAX, params.elf_shnum_max),
+            (MAGIC_PARAM_ELF_NOTES_MAX, params.elf_notes_max),
+            (MAGIC_PARAM_REGEX_MAX, params.regex_max),
+            (MAGIC_PARAM_BYTES_MAX, params.bytes_max),
+            (MAGIC_PARAM_ENCODING_MAX, params.encoding_max),
+        ];
+        for (param, value) in settings {
+            if let Some(value) = value {
+                cookie.set_param(param, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn detect(
+        &self,
+        cookie: Arc<MagicCookie>,
+        data: &[u8],
+    ) -> BoxFuture<'static, Result<(MimeType, String), MagicError>> {
+        let raw = self.run(cookie, data);
+        Box::pin(async move {
+            let mime = raw.await?;
+            Ok((
+                MimeType::try_from(mime.as_str()).map_err(|_| {
+                    MagicError::AnalysisFailed("Invalid MIME returned".to_string())
+                })?,
+                mime,
+            ))
+        })
+    }
+
+    /// Run `cookie` over `data` on the blocking pool, returning libmagic's
+    /// raw output.
+    fn run(
+        &self,
+        cookie: Arc<MagicCookie>,
+        data: &[u8],
+    ) -> BoxFuture<'static, Result<String, MagicError>> {
+        let len = self.bytes_max.map_or(data.len(), |max| data.len().min(max));
+        let data_vec = data[..len].to_vec();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || cookie.buffer(&data_vec))
+                .await
+                .map_err(|e| MagicError::AnalysisFailed(e.to_string()))?
         })
     }
 }
@@ -25,17 +164,115 @@ impl MagicRepository for LibmagicRepository {
         &'a self,
         data: &'a [u8],
         _filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.detect(self.cookie.clone(), data)
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.detect(self.compress_cookie.clone(), data)
+    }
+
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        self.run(self.describe_cookie.clone(), data)
+    }
+
+    /// Runs the MIME cookie with `MAGIC_DEBUG` set for the one call. The
+    /// cookie is held meanwhile, so concurrent analyses wait for the trace.
+    fn trace_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        let cookie = self.cookie.clone();
+        let len = self.bytes_max.map_or(data.len(), |max| data.len().min(max));
+        let data_vec = data[..len].to_vec();
+        Box::pin(async move {
+            let (_, trace) = tokio::task::spawn_blocking(move || cookie.buffer_traced(&data_vec))
+                .await
+                .map_err(|e| MagicError::AnalysisFailed(e.to_string()))??;
+            Ok(trace)
+        })
+    }
+
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        let raw = self.run(self.continue_cookie.clone(), data);
+        Box::pin(async move { Ok(continued_types(&raw.await?)) })
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        let encoding = self.run(self.encoding_cookie.clone(), data);
+        Box::pin(async move { encoding.await.map(Some) })
+    }
+
+    /// Let libmagic read the file itself, which honours `bytes_max` natively.
+    /// It reads through a duplicate of the caller's descriptor, so the file
+    /// analyzed is the one the caller opened even if its path was replaced.
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        _filename: &'a str,
     ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
         let cookie = self.cookie.clone();
+        let file = file.try_clone();
+        Box::pin(async move {
+            let mut file = file.map_err(|e| MagicError::AnalysisFailed(e.to_string()))?;
+            let mime = tokio::task::spawn_blocking(move || {
+                // The duplicate shares the caller's offset and libmagic
+                // reads from it, so rewind for libmagic and restore it after.
+                let offset = file
+                    .stream_position()
+                    .map_err(|e| MagicError::AnalysisFailed(e.to_string()))?;
+                file.seek(SeekFrom::Start(0))
+                    .map_err(|e| MagicError::AnalysisFailed(e.to_string()))?;
+                let mime = cookie.descriptor(file.as_raw_fd());
+                let _ = file.seek(SeekFrom::Start(offset));
+                mime
+            })
+            .await
+            .map_err(|e| MagicError::AnalysisFailed(e.to_string()))??;
+            Ok((
+                MimeType::try_from(mime.as_str()).map_err(|_| {
+                    MagicError::AnalysisFailed("Invalid MIME returned".to_string())
+                })?,
+                mime,
+            ))
+        })
+    }
+
+    fn analyze_with_rules<'a>(
+        &'a self,
+        rule_path: &'a Path,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let rule_path = rule_path.to_string_lossy().into_owned();
         let data_vec = data.to_vec();
         Box::pin(async move {
             tokio::task::spawn_blocking(move || {
-                let mime = cookie.buffer(&data_vec)?;
+                let mime_cookie = MagicCookie::compile_rule(MAGIC_MIME_TYPE, &rule_path)?;
+                let desc_cookie = MagicCookie::compile_rule(MAGIC_NONE, &rule_path)?;
+                let mime = mime_cookie.buffer(&data_vec)?;
+                let description = desc_cookie.buffer(&data_vec)?;
                 Ok((
                     MimeType::try_from(mime.as_str()).map_err(|_| {
                         MagicError::AnalysisFailed("Invalid MIME returned".to_string())
                     })?,
-                    mime,
+                    description,
                 ))
             })
             .await
@@ -43,3 +280,19 @@ impl MagicRepository for LibmagicRepository {
         })
     }
 }
+
+/// Split `MAGIC_CONTINUE` output into its distinct MIME types. libmagic
+/// separates matches with `\n- `, escaped as `\012- ` unless `MAGIC_RAW` is
+/// set. The `application/octet-stream` fallback is not a match of its own.
+fn continued_types(raw: &str) -> Vec<MimeType> {
+    let mut types: Vec<MimeType> = Vec::new();
+    for part in raw.split("\\012- ").flat_map(|part| part.split("\n- ")) {
+        let Ok(mime_type) = MimeType::try_from(part.trim()) else {
+            continue;
+        };
+        if mime_type.as_str() != "application/octet-stream" && !types.contains(&mime_type) {
+            types.push(mime_type);
+        }
+    }
+    types
+}
@@ -1,4 +1,10 @@
+pub mod archive_peek;
+pub mod caching_magic_repository;
+pub mod circuit_breaker_magic_repository;
+pub mod experiment_magic_repository;
 pub mod fake_magic_repository;
 pub mod ffi;
 pub mod libmagic_repository;
+pub mod reloadable_magic_repository;
+pub mod unavailable_magic_repository;
 pub mod wrapper;
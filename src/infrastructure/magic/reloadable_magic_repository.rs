@@ -0,0 +1,134 @@
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::services::magic_reloader::MagicReloader;
+use crate::domain::value_objects::mime_type::MimeType;
+use futures_util::future::BoxFuture;
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Builds a fresh repository, e.g. a new libmagic cookie set from the
+/// configured database.
+pub type MagicLoader = Box<dyn Fn() -> Result<Arc<dyn MagicRepository>, MagicError> + Send + Sync>;
+
+/// Decorator whose wrapped repository can be replaced at runtime.
+///
+/// Each call clones the current `Arc` before awaiting, so a reload swaps the
+/// repository for new calls while in-flight analyses keep the old one alive
+/// until they finish. The version starts at 1 and increments per successful
+/// reload.
+pub struct ReloadableMagicRepository {
+    current: RwLock<Arc<dyn MagicRepository>>,
+    loader: MagicLoader,
+    version: AtomicU64,
+}
+
+impl ReloadableMagicRepository {
+    pub fn new(initial: Arc<dyn MagicRepository>, loader: MagicLoader) -> Self {
+        Self {
+            current: RwLock::new(initial),
+            loader,
+            version: AtomicU64::new(1),
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    fn current(&self) -> Arc<dyn MagicRepository> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+impl MagicReloader for ReloadableMagicRepository {
+    fn reload(&self) -> Result<u64, MagicError> {
+        let fresh = (self.loader)()?;
+        let mut current = self.current.write().unwrap();
+        *current = fresh;
+        Ok(self.version.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+}
+
+impl MagicRepository for ReloadableMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.analyze_buffer(data, filename).await })
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.analyze_buffer_decompressed(data, filename).await })
+    }
+
+    fn analyze_file<'a>(
+        &'a self,
+        file: &'a File,
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.analyze_file(file, filename).await })
+    }
+
+    fn describe_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.describe_buffer(data, filename).await })
+    }
+
+    fn detect_encoding<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.detect_encoding(data, filename).await })
+    }
+
+    fn trace_buffer<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<String, MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.trace_buffer(data, filename).await })
+    }
+
+    fn analyze_all_types<'a>(
+        &'a self,
+        data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.analyze_all_types(data, filename).await })
+    }
+
+    fn database_variant(&self, data: &[u8]) -> Option<&'static str> {
+        self.current().database_variant(data)
+    }
+
+    fn database_variant_for_file(&self, file: &File) -> Option<&'static str> {
+        self.current().database_variant_for_file(file)
+    }
+
+    fn analyze_with_rules<'a>(
+        &'a self,
+        rule_path: &'a Path,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let repo = self.current();
+        Box::pin(async move { repo.analyze_with_rules(rule_path, data).await })
+    }
+}
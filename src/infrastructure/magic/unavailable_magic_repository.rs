@@ -0,0 +1,51 @@
+use crate::domain::errors::MagicError;
+use crate::domain::repositories::magic_repository::MagicRepository;
+use crate::domain::value_objects::mime_type::MimeType;
+use futures_util::future::BoxFuture;
+use std::path::Path;
+
+/// Stand-in repository used when libmagic failed to initialise and
+/// `magic.fail_open_startup` is set. Every analysis fails with
+/// [`MagicError::Unavailable`], which the HTTP layer reports as 503.
+pub struct UnavailableMagicRepository {
+    reason: String,
+}
+
+impl UnavailableMagicRepository {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+
+    fn unavailable<'a>(&self) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let reason = self.reason.clone();
+        Box::pin(async move { Err(MagicError::Unavailable(reason)) })
+    }
+}
+
+impl MagicRepository for UnavailableMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.unavailable()
+    }
+
+    fn analyze_buffer_decompressed<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.unavailable()
+    }
+
+    fn analyze_with_rules<'a>(
+        &'a self,
+        _rule_path: &'a Path,
+        _data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.unavailable()
+    }
+}
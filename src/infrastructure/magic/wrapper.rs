@@ -1,9 +1,15 @@
 use crate::domain::errors::MagicError;
 use crate::infrastructure::magic::ffi::*;
 use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
 use std::ptr;
 use std::sync::Mutex;
 
+/// Serializes redirections of the process-wide stderr descriptor.
+static STDERR_CAPTURE: Mutex<()> = Mutex::new(());
+
 pub struct MagicCookie {
     inner: Mutex<MagicT>,
 }
@@ -43,7 +49,35 @@ impl MagicCookie {
 
         if result != 0 {
             let err = self.get_error(*lock);
-            return Err(MagicError::DatabaseLoadFailed(err));
+            return Err(MagicError::DatabaseLoadFailed(
+                describe_version_mismatch(&err).unwrap_or(err),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse the database at `path` (the system default when `None`)
+    /// without loading it, failing with libmagic's complaint about the first
+    /// invalid entry. Open the cookie with `MAGIC_CHECK` for libmagic to also
+    /// print warnings about suspicious entries to stderr.
+    pub fn check(&self, path: Option<&str>) -> Result<(), MagicError> {
+        let c_path = match path {
+            Some(p) => Some(
+                CString::new(p)
+                    .map_err(|_| MagicError::DatabaseLoadFailed("Invalid path".to_string()))?,
+            ),
+            None => None,
+        };
+        let path_ptr = c_path.as_ref().map_or(ptr::null(), |p| p.as_ptr());
+
+        let lock = self.inner.lock().unwrap();
+        let result = unsafe { magic_check(*lock, path_ptr) };
+
+        if result != 0 {
+            let err = self.get_error(*lock);
+            return Err(MagicError::DatabaseLoadFailed(
+                describe_version_mismatch(&err).unwrap_or(err),
+            ));
         }
         Ok(())
     }
@@ -76,17 +110,150 @@ impl MagicCookie {
         Ok(c_str.to_string_lossy().into_owned())
     }
 
+    /// Like [`buffer`](Self::buffer), with `MAGIC_DEBUG` set for the call,
+    /// also returning the debug output libmagic printed while matching.
+    /// libmagic only prints it to stderr, so the process's stderr is
+    /// redirected for the duration; anything else written there meanwhile
+    /// ends up in the trace too.
+    pub fn buffer_traced(&self, data: &[u8]) -> Result<(String, String), MagicError> {
+        let lock = self.inner.lock().unwrap();
+        let flags = unsafe { magic_getflags(*lock) };
+        unsafe { magic_setflags(*lock, flags | MAGIC_DEBUG) };
+        let captured = capture_stderr(|| {
+            let result = unsafe { magic_buffer(*lock, data.as_ptr() as *const _, data.len()) };
+            if result.is_null() {
+                return Err(MagicError::AnalysisFailed(self.get_error(*lock)));
+            }
+            Ok(unsafe { CStr::from_ptr(result) }
+                .to_string_lossy()
+                .into_owned())
+        });
+        unsafe { magic_setflags(*lock, flags) };
+
+        let (result, trace) =
+            captured.map_err(|e| MagicError::AnalysisFailed(format!("Trace capture failed: {}", e)))?;
+        Ok((result?, trace))
+    }
+
+    /// Analyze the open file `fd` from its current offset. libmagic restores
+    /// the offset afterwards.
+    pub fn descriptor(&self, fd: RawFd) -> Result<String, MagicError> {
+        let lock = self.inner.lock().unwrap();
+        let result = unsafe { magic_descriptor(*lock, fd) };
+
+        if result.is_null() {
+            let err = self.get_error(*lock);
+            return Err(MagicError::AnalysisFailed(err));
+        }
+
+        let c_str = unsafe { CStr::from_ptr(result) };
+        Ok(c_str.to_string_lossy().into_owned())
+    }
+
+    /// Set a `MAGIC_PARAM_*` limit on this cookie.
+    pub fn set_param(&self, param: i32, value: usize) -> Result<(), MagicError> {
+        let lock = self.inner.lock().unwrap();
+        let result =
+            unsafe { magic_setparam(*lock, param, &value as *const usize as *const _) };
+
+        if result != 0 {
+            return Err(MagicError::AnalysisFailed(format!(
+                "Invalid value {} for magic parameter {}",
+                value, param
+            )));
+        }
+        Ok(())
+    }
+
+    /// Open an isolated cookie that knows only the rules in `rule_path`.
+    ///
+    /// The rule file is loaded in its source form, which libmagic compiles in
+    /// memory. `magic_compile` is deliberately avoided: it always writes
+    /// `<basename>.mgc` into the process working directory.
+    pub fn compile_rule(flags: i32, rule_path: &str) -> Result<Self, MagicError> {
+        let cookie = Self::open(flags)?;
+        cookie.load(Some(rule_path))?;
+        Ok(cookie)
+    }
+
+    /// libmagic's last error, passed on verbatim so any position it names
+    /// (a rule line, an offset) or OS error it quotes survives. Without a
+    /// message, the OS error it recorded is reported instead.
     fn get_error(&self, ms: MagicT) -> String {
         let err = unsafe { magic_error(ms) };
-        if err.is_null() {
-            "Unknown magic error".to_string()
-        } else {
-            let c_str = unsafe { CStr::from_ptr(err) };
-            c_str.to_string_lossy().into_owned()
+        if !err.is_null() {
+            return unsafe { CStr::from_ptr(err) }.to_string_lossy().into_owned();
+        }
+        match unsafe { magic_errno(ms) } {
+            0 => "Unknown magic error".to_string(),
+            errno => std::io::Error::from_raw_os_error(errno).to_string(),
         }
     }
 }
 
+/// Run `f` with stderr pointed at an anonymous file, returning its result
+/// and what was written to stderr meanwhile.
+fn capture_stderr<T>(f: impl FnOnce() -> T) -> std::io::Result<(T, String)> {
+    let _guard = STDERR_CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+    let fd = unsafe { libc::memfd_create(c"magic-trace".as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut capture = unsafe { File::from_raw_fd(fd) };
+
+    let saved = unsafe { libc::dup(libc::STDERR_FILENO) };
+    if saved < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let saved = unsafe { File::from_raw_fd(saved) };
+    if unsafe { libc::dup2(capture.as_raw_fd(), libc::STDERR_FILENO) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let value = f();
+    // libmagic writes through stdio; stderr is unbuffered, but flush anyway
+    // before the descriptor is swapped back.
+    unsafe { libc::fflush(ptr::null_mut()) };
+    if unsafe { libc::dup2(saved.as_raw_fd(), libc::STDERR_FILENO) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut output = Vec::new();
+    capture.seek(SeekFrom::Start(0))?;
+    capture.read_to_end(&mut output)?;
+    Ok((value, String::from_utf8_lossy(&output).into_owned()))
+}
+
+/// Validate the magic database at `path` (the system default when `None`)
+/// in libmagic's check mode, as `file -c` does.
+pub fn check_database(path: Option<&str>) -> Result<(), MagicError> {
+    MagicCookie::open(MAGIC_CHECK)?.check(path)
+}
+
+/// Version of the linked libmagic as `major.minor` (e.g. `5.45`).
+pub fn library_version() -> String {
+    let version = unsafe { magic_version() };
+    format!("{}.{:02}", version / 100, version % 100)
+}
+
+/// Rewrite libmagic's "File 5.45 supports only version 18 magic files.
+/// `x.mgc' is version 17" into a message naming both format versions and
+/// the fix. `None` for any other load error.
+fn describe_version_mismatch(err: &str) -> Option<String> {
+    let (_, rest) = err.split_once("supports only version ")?;
+    let (expected, rest) = rest.split_once(" magic files. `")?;
+    let (path, found) = rest.rsplit_once("' is version ")?;
+    let expected: u32 = expected.parse().ok()?;
+    let found: u32 = found.trim().parse().ok()?;
+    Some(format!(
+        "Magic database {} has format version {}, but libmagic {} expects version {}; \
+         recompile it with this libmagic (file -C) or point magic.database_path at a matching database",
+        path,
+        found,
+        library_version(),
+        expected
+    ))
+}
+
 impl Drop for MagicCookie {
     fn drop(&mut self) {
         let lock = self.inner.lock().unwrap();
@@ -2,5 +2,8 @@ pub mod auth;
 pub mod config;
 pub mod errors;
 pub mod filesystem;
+pub mod integrations;
 pub mod magic;
+pub mod results;
 pub mod telemetry;
+pub mod uploads;
@@ -0,0 +1,132 @@
+use crate::domain::entities::magic_result::MagicResult;
+use crate::domain::services::result_store::{PathHistoryEntry, ResultStore};
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Process-local [`ResultStore`] bounded by entry count and age.
+///
+/// Entries expire `ttl` after they were stored. When full, the oldest entry
+/// is evicted to make room. Storing a content id again replaces its result
+/// and restarts its TTL. Path histories are bounded the same way, by
+/// `max_entries` paths of at most `max_history_per_path` analyses each; the
+/// path recorded least recently is evicted first.
+pub struct InMemoryResultStore {
+    max_entries: usize,
+    max_history_per_path: usize,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+/// `order` holds every stored id exactly once, oldest first; `path_order`
+/// likewise holds every recorded path.
+#[derive(Default)]
+struct Entries {
+    by_id: HashMap<String, (Instant, MagicResult)>,
+    order: VecDeque<String>,
+    by_path: HashMap<String, VecDeque<(Instant, PathHistoryEntry)>>,
+    path_order: VecDeque<String>,
+}
+
+impl InMemoryResultStore {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            max_entries,
+            max_history_per_path: 10,
+            ttl,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Keep at most `max` analyses per path; 0 disables path history.
+    pub fn with_max_history_per_path(mut self, max: usize) -> Self {
+        self.max_history_per_path = max;
+        self
+    }
+}
+
+impl ResultStore for InMemoryResultStore {
+    fn put(&self, content_id: &str, result: MagicResult) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        if entries.by_id.remove(content_id).is_some() {
+            entries.order.retain(|id| id != content_id);
+        }
+        while let Some(oldest) = entries.order.front() {
+            let expired = entries
+                .by_id
+                .get(oldest)
+                .is_none_or(|(at, _)| now.duration_since(*at) >= self.ttl);
+            if !expired && entries.by_id.len() < self.max_entries {
+                break;
+            }
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.by_id.remove(&oldest);
+            }
+        }
+        entries.by_id.insert(content_id.to_string(), (now, result));
+        entries.order.push_back(content_id.to_string());
+    }
+
+    fn get(&self, content_id: &str) -> Option<MagicResult> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .by_id
+            .get(content_id)
+            .filter(|(at, _)| at.elapsed() < self.ttl)
+            .map(|(_, result)| result.clone())
+    }
+
+    fn record_path(&self, path: &str, result: MagicResult) {
+        if self.max_entries == 0 || self.max_history_per_path == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        let mut history = match entries.by_path.remove(path) {
+            Some(history) => {
+                entries.path_order.retain(|p| p != path);
+                history
+            }
+            None => VecDeque::new(),
+        };
+        while entries.by_path.len() >= self.max_entries {
+            match entries.path_order.pop_front() {
+                Some(oldest) => entries.by_path.remove(&oldest),
+                None => break,
+            };
+        }
+        history.retain(|(at, _)| now.duration_since(*at) < self.ttl);
+        while history.len() >= self.max_history_per_path {
+            history.pop_front();
+        }
+        history.push_back((
+            now,
+            PathHistoryEntry {
+                stored_at: Utc::now(),
+                result,
+            },
+        ));
+        entries.by_path.insert(path.to_string(), history);
+        entries.path_order.push_back(path.to_string());
+    }
+
+    fn path_history(&self, path: &str) -> Vec<PathHistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .by_path
+            .get(path)
+            .map(|history| {
+                history
+                    .iter()
+                    .filter(|(at, _)| at.elapsed() < self.ttl)
+                    .map(|(_, entry)| entry.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -1,4 +1,18 @@
-use opentelemetry::metrics::{Counter, Histogram, Meter, UpDownCounter};
+use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter, UpDownCounter};
+
+/// `app.analysis.body_size` buckets: 1KiB to 100MiB, the body size limit.
+const BODY_SIZE_BOUNDARIES: &[f64] = &[
+    1024.0,
+    16384.0,
+    65536.0,
+    262144.0,
+    1048576.0,
+    4194304.0,
+    10485760.0,
+    26214400.0,
+    52428800.0,
+    104857600.0,
+];
 
 /// Application-level OTel metric instruments.
 ///
@@ -22,6 +36,16 @@ pub struct AppMetrics {
     /// Name: `app.analysis.errors`, unit: `{error}`.
     pub analysis_errors: Counter<u64>,
 
+    /// Content requests by body handling path, labelled `analysis.strategy`
+    /// (`in_memory` or `temp_file`).
+    /// Name: `app.analysis.strategy`, unit: `{request}`.
+    pub analysis_strategy: Counter<u64>,
+
+    /// Bytes received per successfully analyzed content request, labelled
+    /// `analysis.strategy`. Percentiles guide `large_file_threshold_mb`.
+    /// Name: `app.analysis.body_size`, unit: `By`.
+    pub analysis_body_size: Histogram<u64>,
+
     /// Duration of each background cleanup scan cycle.
     /// Name: `app.tempfile.cleanup.duration`, unit: `ms`.
     pub tempfile_cleanup_duration: Histogram<f64>,
@@ -29,6 +53,10 @@ pub struct AppMetrics {
     /// Total orphaned temp files removed across all scan cycles.
     /// Name: `app.tempfile.cleanup.removed`, unit: `{file}`.
     pub tempfile_cleanup_removed: Counter<u64>,
+
+    /// libmagic circuit breaker state: 0 closed, 1 half-open, 2 open.
+    /// Name: `app.magic.circuit_breaker.state`, unit: `{state}`.
+    pub magic_circuit_breaker_state: Gauge<u64>,
 }
 
 impl AppMetrics {
@@ -68,6 +96,19 @@ impl AppMetrics {
                 .with_unit("{error}")
                 .build(),
 
+            analysis_strategy: meter
+                .u64_counter("app.analysis.strategy")
+                .with_description("Content requests by body handling path (in_memory or temp_file).")
+                .with_unit("{request}")
+                .build(),
+
+            analysis_body_size: meter
+                .u64_histogram("app.analysis.body_size")
+                .with_description("Bytes received per successfully analyzed content request.")
+                .with_unit("By")
+                .with_boundaries(BODY_SIZE_BOUNDARIES.to_vec())
+                .build(),
+
             tempfile_cleanup_duration: meter
                 .f64_histogram("app.tempfile.cleanup.duration")
                 .with_description("Duration of each background cleanup scan cycle.")
@@ -81,6 +122,12 @@ impl AppMetrics {
                 )
                 .with_unit("{file}")
                 .build(),
+
+            magic_circuit_breaker_state: meter
+                .u64_gauge("app.magic.circuit_breaker.state")
+                .with_description("libmagic circuit breaker state: 0 closed, 1 half-open, 2 open.")
+                .with_unit("{state}")
+                .build(),
         }
     }
 }
@@ -0,0 +1,65 @@
+use crate::domain::services::upload_session_store::{
+    SharedUploadSession, UploadSession, UploadSessionStore,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Process-local [`UploadSessionStore`] bounded by session count and idle
+/// time.
+///
+/// A session expires `ttl` after it was last used. Unlike uploads, sessions
+/// are never evicted to make room: a client may be halfway through sending
+/// one, so new sessions are refused while the store is full.
+pub struct InMemoryUploadSessionStore {
+    max_entries: usize,
+    ttl: Duration,
+    sessions: Mutex<HashMap<String, (Instant, SharedUploadSession)>>,
+}
+
+impl InMemoryUploadSessionStore {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            max_entries,
+            ttl,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl UploadSessionStore for InMemoryUploadSessionStore {
+    fn create(&self, session: UploadSession) -> Option<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, (used, _)| used.elapsed() < self.ttl);
+        if sessions.len() >= self.max_entries {
+            return None;
+        }
+        let id = Uuid::new_v4().simple().to_string();
+        sessions.insert(
+            id.clone(),
+            (Instant::now(), Arc::new(tokio::sync::Mutex::new(session))),
+        );
+        Some(id)
+    }
+
+    fn get(&self, id: &str) -> Option<SharedUploadSession> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let (used, session) = sessions
+            .get_mut(id)
+            .filter(|(used, _)| used.elapsed() < self.ttl)?;
+        *used = Instant::now();
+        Some(Arc::clone(session))
+    }
+
+    fn remove(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+
+    fn purge_expired(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, (used, _)| used.elapsed() < self.ttl);
+        before - sessions.len()
+    }
+}
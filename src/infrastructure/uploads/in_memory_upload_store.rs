@@ -0,0 +1,87 @@
+use crate::domain::services::upload_store::{Upload, UploadStore};
+use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Process-local [`UploadStore`] bounded by entry count and age.
+///
+/// Uploads expire `ttl` after they were stored and are then no longer
+/// returned; their files are deleted by [`UploadStore::purge_expired`] or
+/// when the upload is evicted. When full, the oldest upload is evicted.
+pub struct InMemoryUploadStore {
+    max_entries: usize,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+/// `order` holds every stored token exactly once, oldest first.
+#[derive(Default)]
+struct Entries {
+    by_token: HashMap<String, (Instant, Upload)>,
+    order: VecDeque<String>,
+}
+
+impl InMemoryUploadStore {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            max_entries,
+            ttl,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+}
+
+impl UploadStore for InMemoryUploadStore {
+    fn put(&self, upload: Upload) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        if self.max_entries == 0 {
+            return token;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        while entries.by_token.len() >= self.max_entries {
+            match entries.order.pop_front() {
+                Some(oldest) => {
+                    entries.by_token.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        entries
+            .by_token
+            .insert(token.clone(), (Instant::now(), upload));
+        entries.order.push_back(token.clone());
+        token
+    }
+
+    fn get(&self, token: &str) -> Option<(WindowsCompatibleFilename, std::fs::File)> {
+        let entries = self.entries.lock().unwrap();
+        let (_, upload) = entries
+            .by_token
+            .get(token)
+            .filter(|(at, _)| at.elapsed() < self.ttl)?;
+        let handle = upload.handle.try_clone().ok()?;
+        Some((upload.filename.clone(), handle))
+    }
+
+    fn purge_expired(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let mut purged = 0;
+        while let Some(oldest) = entries.order.front() {
+            let expired = entries
+                .by_token
+                .get(oldest)
+                .is_none_or(|(at, _)| at.elapsed() >= self.ttl);
+            if !expired {
+                break;
+            }
+            if let Some(oldest) = entries.order.pop_front()
+                && entries.by_token.remove(&oldest).is_some()
+            {
+                purged += 1;
+            }
+        }
+        purged
+    }
+}
@@ -1,17 +1,39 @@
 use axum::{extract::DefaultBodyLimit, middleware};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::domain::services::authentication_service::AuthenticationService;
+use magicer::domain::services::temp_storage::TempStorageService;
 use magicer::infrastructure::auth::basic_auth_service::BasicAuthService;
+use magicer::infrastructure::auth::introspection_auth_service::IntrospectionAuthService;
 use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::config::startup_banner::StartupBanner;
 use magicer::infrastructure::filesystem::sandbox::PathSandbox;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use magicer::infrastructure::filesystem::tiered_temp_storage::TieredTempStorageService;
+use magicer::infrastructure::integrations::webhook_result_sink::WebhookResultSink;
+use magicer::infrastructure::magic::caching_magic_repository::CachingMagicRepository;
+use magicer::infrastructure::magic::circuit_breaker_magic_repository::CircuitBreakerMagicRepository;
+use magicer::infrastructure::magic::experiment_magic_repository::ExperimentMagicRepository;
+use magicer::infrastructure::magic::libmagic_repository::LibmagicRepository;
+use magicer::infrastructure::magic::reloadable_magic_repository::ReloadableMagicRepository;
+use magicer::infrastructure::magic::unavailable_magic_repository::UnavailableMagicRepository;
+use magicer::infrastructure::magic::wrapper::check_database;
 use magicer::infrastructure::telemetry::metrics::AppMetrics;
 use magicer::infrastructure::telemetry::Telemetry;
-use magicer::presentation::http::middleware::request_id;
+use magicer::presentation::http::middleware::idle_connections::{
+    ConnectionTracker, TrackedListener,
+};
+use magicer::presentation::http::middleware::{load_shedding, request_id, request_timeout};
 use magicer::presentation::http::router::create_router;
+use magicer::presentation::http::server;
 use magicer::presentation::state::app_state::AppState;
+use std::io::Read;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
 use tower::limit::concurrency::ConcurrencyLimitLayer;
 use tower_http::timeout::TimeoutLayer;
 
@@ -21,21 +43,148 @@ struct Args {
     /// Path to the configuration file
     #[arg(short, long, env = "MAGICER_CONFIG_PATH")]
     config: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Detect the type of a file or of stdin with libmagic and exit, without
+    /// starting the server
+    Analyze {
+        /// File to analyze; stdin is read when omitted
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Validate a magic database in libmagic's check mode and exit; parse
+    /// warnings are printed to stderr
+    CheckDatabase {
+        /// Database to check (a `.mgc` file or source rules); defaults to
+        /// the one the server would load: `magic.database_path`, then the
+        /// first existing `magic.database_search_paths` entry, then the
+        /// system database
+        #[arg(long)]
+        database: Option<PathBuf>,
+    },
+}
+
+/// Check `database` (or the configured one) and report `ok` or the reason it
+/// is invalid.
+fn check_database_command(config: &ServerConfig, database: Option<PathBuf>) -> ExitCode {
+    let database = match database {
+        Some(path) => Some(path.to_string_lossy().into_owned()),
+        None => match LibmagicRepository::resolve_database(
+            config.magic.database_path.as_deref(),
+            &config.magic.database_search_paths,
+        ) {
+            Ok(database) => database,
+            Err(e) => {
+                eprintln!("magicer: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    match check_database(database.as_deref()) {
+        Ok(()) => {
+            println!("ok");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("magicer: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Print `<mime type>\t<description>` for `file` (or stdin) using the
+/// `[magic]` settings of `config`.
+async fn analyze_command(config: &ServerConfig, file: Option<PathBuf>) -> ExitCode {
+    let repo = match LibmagicRepository::from_config(
+        config.analysis.mmap_fallback_enabled,
+        &config.magic,
+    ) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("magicer: failed to initialize libmagic: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (data, filename) = match &file {
+        Some(path) => match std::fs::read(path) {
+            Ok(data) => {
+                let filename = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                (data, filename)
+            }
+            Err(e) => {
+                eprintln!("magicer: failed to read {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => {
+            let mut data = Vec::new();
+            if let Err(e) = std::io::stdin().read_to_end(&mut data) {
+                eprintln!("magicer: failed to read stdin: {}", e);
+                return ExitCode::FAILURE;
+            }
+            (data, "stdin".to_string())
+        }
+    };
+    let outcome = match repo.analyze_buffer(&data, &filename).await {
+        Ok((mime_type, _)) => repo
+            .describe_buffer(&data, &filename)
+            .await
+            .map(|description| (mime_type, description)),
+        Err(e) => Err(e),
+    };
+    match outcome {
+        Ok((mime_type, description)) => {
+            println!("{}\t{}", mime_type.as_str(), description);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("magicer: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     // Parse CLI arguments
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Analyze { file }) => {
+            return analyze_command(&ServerConfig::load(args.config), file).await;
+        }
+        Some(Command::CheckDatabase { database }) => {
+            return check_database_command(&ServerConfig::load(args.config), database);
+        }
+        None => {}
+    }
+
     // Initialise OpenTelemetry (traces + metrics + logs) before anything else.
     // Replaces the previous `tracing_subscriber::fmt::init()` call.
     let _telemetry = Telemetry::init();
 
     // Load configuration
     let config = ServerConfig::load(args.config);
-    config.validate().expect("Failed to validate configuration");
-    tracing::info!("Server configuration loaded: {:?}", config);
+    if let Err(errors) = config.validate_all() {
+        for (field, error) in &errors.0 {
+            tracing::error!(config.field = field, error = %error, "Invalid configuration");
+        }
+        eprintln!("Failed to validate configuration: {errors}");
+        return ExitCode::FAILURE;
+    }
+
+    // Address to bind to
+    let socket_addr = config.bind_addr().expect("Invalid bind address");
+    let addr = socket_addr.to_string();
+    StartupBanner::new(&config, socket_addr).log();
 
     // Apply max_open_files limit
     if let Err(e) = rlimit::setrlimit(
@@ -48,59 +197,165 @@ async fn main() {
 
     // Initialize infrastructure
     // Use real LibmagicRepository built from source
-    let magic_repo = Arc::new(
-        magicer::infrastructure::magic::libmagic_repository::LibmagicRepository::new(
-            config.analysis.mmap_fallback_enabled,
-        )
-        .expect("Failed to initialize real libmagic repository"),
-    );
-
-    let sandbox = Arc::new(PathSandbox::new(PathBuf::from(&config.sandbox.base_dir)));
+    let load_libmagic = {
+        let mmap_fallback_enabled = config.analysis.mmap_fallback_enabled;
+        let magic = config.magic.clone();
+        move || {
+            LibmagicRepository::from_config(mmap_fallback_enabled, &magic)
+                .map(|repo| Arc::new(repo) as Arc<dyn MagicRepository>)
+        }
+    };
+    let libmagic_repo: Arc<dyn MagicRepository> = match load_libmagic() {
+        Ok(repo) => repo,
+        Err(e) if config.magic.fail_open_startup => {
+            tracing::error!(
+                error = %e,
+                "Failed to initialize libmagic; analysis endpoints will return 503"
+            );
+            Arc::new(UnavailableMagicRepository::new(e.to_string()))
+        }
+        Err(e) => panic!("Failed to initialize real libmagic repository: {}", e),
+    };
+    // Reloading can also recover a server started with fail_open_startup.
+    let reloadable_repo = Arc::new(ReloadableMagicRepository::new(
+        libmagic_repo,
+        Box::new(load_libmagic),
+    ));
+    let libmagic_repo: Arc<dyn MagicRepository> = reloadable_repo.clone();
+    let libmagic_repo: Arc<dyn MagicRepository> = match &config.magic.experiment {
+        Some(experiment) => {
+            let candidate = LibmagicRepository::with_database(
+                config.analysis.mmap_fallback_enabled,
+                &config.magic.params,
+                Some(&experiment.candidate_db),
+            )
+            .expect("Failed to load magic.experiment.candidate_db");
+            tracing::info!(
+                magic.experiment.candidate_db = %experiment.candidate_db,
+                magic.experiment.fraction = experiment.fraction,
+                "Routing part of the analyses to the candidate magic database"
+            );
+            Arc::new(ExperimentMagicRepository::new(
+                libmagic_repo,
+                Arc::new(candidate),
+                experiment.fraction,
+            ))
+        }
+        None => libmagic_repo,
+    };
 
-    let temp_storage = Arc::new(
-        magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService::new(
-            PathBuf::from(&config.analysis.temp_dir),
-        ),
+    let sandbox = Arc::new(
+        PathSandbox::new(PathBuf::from(&config.sandbox.base_dir))
+            .with_denied_subpaths(&config.sandbox.denied_subpaths)
+            .with_case_insensitive(config.sandbox.case_insensitive)
+            .with_deny_symlinks(config.sandbox.deny_symlinks),
     );
 
-    let auth_service = Arc::new(BasicAuthService::new(
+    let disk_storage: Arc<dyn TempStorageService> = Arc::new(FsTempStorageService::new(
+        PathBuf::from(&config.analysis.temp_dir),
+    ));
+    let temp_storage: Arc<dyn TempStorageService> = match &config.analysis.tmpfs_dir {
+        Some(dir) => Arc::new(TieredTempStorageService::new(
+            Arc::new(FsTempStorageService::new(PathBuf::from(dir))),
+            disk_storage,
+            config.analysis.tmpfs_max_mb * 1024 * 1024,
+        )),
+        None => disk_storage,
+    };
+
+    let basic_auth: Arc<dyn AuthenticationService> = Arc::new(BasicAuthService::new(
         &config.auth.username,
         &config.auth.password,
     ));
+    let auth_service: Arc<dyn AuthenticationService> = match &config.auth.introspection_url {
+        Some(url) => Arc::new(
+            IntrospectionAuthService::new(
+                basic_auth,
+                url,
+                Duration::from_secs(config.auth.introspection_cache_ttl_secs),
+            )
+            .expect("Failed to initialize token introspection client"),
+        ),
+        None => basic_auth,
+    };
 
     // Build OTel metric instruments from the global meter provider (set by Telemetry::init).
     let meter = opentelemetry::global::meter(env!("CARGO_PKG_NAME"));
     let metrics = Arc::new(AppMetrics::new(&meter));
 
-    // Address to bind to
-    let addr = format!("{}:{}", config.server.host, config.server.port);
-    let socket_addr: std::net::SocketAddr = addr.parse().expect("Invalid bind address");
+    let breaker = &config.magic.circuit_breaker;
+    let libmagic_repo: Arc<dyn MagicRepository> = if breaker.enabled {
+        Arc::new(
+            CircuitBreakerMagicRepository::new(
+                libmagic_repo,
+                breaker.failure_threshold,
+                Duration::from_secs(breaker.window_secs),
+                Duration::from_secs(breaker.open_secs),
+            )
+            .with_call_timeout(Duration::from_secs(
+                config
+                    .server
+                    .timeouts
+                    .analysis_timeout_secs
+                    .max(config.magic.max_timeout_override()),
+            ))
+            .with_state_gauge(metrics.magic_circuit_breaker_state.clone()),
+        )
+    } else {
+        libmagic_repo
+    };
+    let magic_repo: Arc<dyn MagicRepository> = if config.analysis.dedupe_in_flight {
+        Arc::new(CachingMagicRepository::new(libmagic_repo))
+    } else {
+        libmagic_repo
+    };
 
     // Initialize application state
-    let app_state = Arc::new(AppState::new(
+    let mut app_state = AppState::new(
         magic_repo,
         sandbox,
         temp_storage,
         auth_service,
         Arc::new(config.clone()),
         Arc::clone(&metrics),
-    ));
+    )
+    .with_magic_reloader(reloadable_repo);
+    if let Some(webhook) = &config.integrations.webhook {
+        let sink = WebhookResultSink::new(&webhook.url, webhook.secret.clone(), webhook.max_retries)
+            .expect("Failed to initialize webhook client")
+            .with_max_pending(webhook.max_pending);
+        app_state = app_state.with_result_sink(Arc::new(sink));
+    }
+    let app_state = Arc::new(app_state);
+    let upload_store = Arc::clone(&app_state.upload_store);
+    let session_store = Arc::clone(&app_state.session_store);
 
     // Build router with middleware and limits
     let app = create_router(app_state)
         .layer(middleware::from_fn(
             magicer::presentation::http::middleware::error_handler::handle_error,
         ))
-        .layer(middleware::from_fn(request_id::add_request_id))
-        .layer(ConcurrencyLimitLayer::new(
-            config.server.max_connections as usize,
+        .layer(middleware::from_fn(request_id::add_request_id));
+    let max_in_flight = config.server.max_connections as usize;
+    let app = if config.server.overload_policy == "shed" {
+        app.layer(middleware::from_fn_with_state(
+            Arc::new(Semaphore::new(max_in_flight)),
+            load_shedding::shed_overload,
         ))
+    } else {
+        app.layer(ConcurrencyLimitLayer::new(max_in_flight))
+    };
+    let app = app
         .layer(DefaultBodyLimit::max(
             (config.server.limits.max_body_size_mb * 1024 * 1024) as usize,
         ))
         .layer(TimeoutLayer::with_status_code(
             axum::http::StatusCode::GATEWAY_TIMEOUT,
             Duration::from_secs(config.server.timeouts.read_timeout_secs),
+        ))
+        .layer(middleware::from_fn_with_state(
+            Duration::from_secs(config.server.timeouts.request_timeout_secs()),
+            request_timeout::enforce_request_timeout,
         ));
 
     // Create a TCP listener with custom backlog
@@ -112,12 +367,23 @@ async fn main() {
     .unwrap();
 
     socket.set_reuse_address(true).unwrap();
+    if config.server.reuse_port {
+        socket.set_reuse_port(true).unwrap();
+    }
+    if socket_addr.is_ipv6()
+        && let Some(ipv6_only) = config.server.ipv6_only
+    {
+        socket.set_only_v6(ipv6_only).unwrap();
+    }
     socket.bind(&socket_addr.into()).unwrap();
     socket.listen(config.server.backlog as i32).unwrap();
 
     let std_listener: std::net::TcpListener = socket.into();
     std_listener.set_nonblocking(true).unwrap();
     let listener = TcpListener::from_std(std_listener).unwrap();
+    let connections = Arc::new(ConnectionTracker::new());
+    let listener = TrackedListener::new(listener, Arc::clone(&connections))
+        .with_tcp_nodelay(config.server.tcp_nodelay);
 
     // L-01: server.addr and server.backlog are structured fields — not interpolated strings.
     tracing::info!(
@@ -126,6 +392,12 @@ async fn main() {
         "Server listening"
     );
 
+    // Close keep-alive connections left idle past the keep-alive timeout.
+    let keepalive_secs = config.server.timeouts.keepalive_secs;
+    if keepalive_secs > 0 {
+        connections.spawn_reaper(Duration::from_secs(keepalive_secs));
+    }
+
     // Start background cleanup task
     let cleanup_config = config.clone();
     let cleanup_metrics = Arc::clone(&metrics);
@@ -133,45 +405,51 @@ async fn main() {
         let mut interval = tokio::time::interval(Duration::from_secs(300)); // Every 5 minutes
         loop {
             interval.tick().await;
-            let temp_dir = &cleanup_config.analysis.temp_dir;
+            let temp_dirs = std::iter::once(&cleanup_config.analysis.temp_dir)
+                .chain(cleanup_config.analysis.tmpfs_dir.as_ref());
             let max_age = cleanup_config.analysis.temp_file_max_age_secs;
 
             let cycle_start = Instant::now();
-            let mut removed_count: u64 = 0;
+            // Expired uploads and sessions delete their own files when
+            // dropped.
+            let mut removed_count =
+                (upload_store.purge_expired() + session_store.purge_expired()) as u64;
 
-            if let Ok(mut entries) = tokio::fs::read_dir(temp_dir).await {
-                while let Ok(Some(entry)) = entries.next_entry().await {
-                    let is_expired = entry.metadata().await.ok().and_then(|m| {
-                        if !m.is_file() {
-                            return None;
-                        }
-                        m.modified()
-                            .ok()
-                            .and_then(|t| t.elapsed().ok())
-                            .map(|e| e.as_secs() > max_age)
-                    });
-
-                    if is_expired == Some(true) {
-                        let path = entry.path();
-                        // L-02: log only the filename component, never the
-                        // full resolved sandbox path.
-                        let file_name = path
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .into_owned();
-                        if let Err(e) = tokio::fs::remove_file(&path).await {
-                            tracing::warn!(
-                                file.name = %file_name,
-                                error = %e,
-                                "Failed to remove orphaned temp file"
-                            );
-                        } else {
-                            tracing::info!(
-                                file.name = %file_name,
-                                "Removed orphaned temp file"
-                            );
-                            removed_count += 1;
+            for temp_dir in temp_dirs {
+                if let Ok(mut entries) = tokio::fs::read_dir(temp_dir).await {
+                    while let Ok(Some(entry)) = entries.next_entry().await {
+                        let is_expired = entry.metadata().await.ok().and_then(|m| {
+                            if !m.is_file() {
+                                return None;
+                            }
+                            m.modified()
+                                .ok()
+                                .and_then(|t| t.elapsed().ok())
+                                .map(|e| e.as_secs() > max_age)
+                        });
+
+                        if is_expired == Some(true) {
+                            let path = entry.path();
+                            // L-02: log only the filename component, never the
+                            // full resolved sandbox path.
+                            let file_name = path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .into_owned();
+                            if let Err(e) = tokio::fs::remove_file(&path).await {
+                                tracing::warn!(
+                                    file.name = %file_name,
+                                    error = %e,
+                                    "Failed to remove orphaned temp file"
+                                );
+                            } else {
+                                tracing::info!(
+                                    file.name = %file_name,
+                                    "Removed orphaned temp file"
+                                );
+                                removed_count += 1;
+                            }
                         }
                     }
                 }
@@ -195,13 +473,11 @@ async fn main() {
         }
     });
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    server::serve(listener, app, &config.server.limits, shutdown_signal()).await;
 
     // Flush all in-flight telemetry before the process exits.
     _telemetry.shutdown();
+    ExitCode::SUCCESS
 }
 
 async fn shutdown_signal() {
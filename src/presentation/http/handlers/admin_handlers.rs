@@ -0,0 +1,167 @@
+use crate::application::errors::ApplicationError;
+use crate::domain::value_objects::request_id::RequestId;
+use crate::infrastructure::magic::libmagic_repository::LibmagicRepository;
+use crate::infrastructure::magic::wrapper::check_database;
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+/// Most requests one `POST /admin/debug-capture` may arm.
+pub const MAX_DEBUG_CAPTURES: u64 = 1000;
+
+#[derive(Serialize)]
+pub struct ReloadMagicResponse {
+    pub request_id: String,
+    /// Increments with every successful reload; 1 is the database loaded at
+    /// startup.
+    pub database_version: u64,
+}
+
+#[derive(Serialize)]
+pub struct CheckMagicResponse {
+    pub request_id: String,
+    pub valid: bool,
+    /// libmagic's complaint about the database when it is not valid.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DebugCaptureQuery {
+    /// Requests to capture; 0 disarms. Defaults to 10.
+    pub requests: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct DebugCaptureResponse {
+    pub request_id: String,
+    /// Requests that will be captured, replacing any still armed.
+    pub requests: u64,
+}
+
+/// `POST /admin/debug-capture?requests=N`: log the headers and response
+/// bodies of the next `N` requests in full, then return to normal logging
+/// without another call. Arming again replaces the remaining count.
+#[tracing::instrument(name = "handler.arm_debug_capture", skip(state, request_id))]
+pub async fn arm_debug_capture(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DebugCaptureQuery>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    let requests = query.requests.unwrap_or(10).min(MAX_DEBUG_CAPTURES);
+    state
+        .debug_captures_remaining
+        .store(requests, Ordering::Release);
+    tracing::info!(requests, "Debug capture armed");
+    Json(DebugCaptureResponse {
+        request_id: request_id.as_str().to_string(),
+        requests,
+    })
+}
+
+/// `POST /admin/check-magic`: parse the configured magic database in
+/// libmagic's check mode without loading it, so a database can be validated
+/// before `POST /admin/reload-magic` swaps it in. An invalid database is
+/// still a `200`; the report says what is wrong.
+#[tracing::instrument(name = "handler.check_magic", skip(state, request_id))]
+pub async fn check_magic(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    let magic = state.config.magic.clone();
+    let outcome = tokio::task::spawn_blocking(move || {
+        LibmagicRepository::resolve_database(
+            magic.database_path.as_deref(),
+            &magic.database_search_paths,
+        )
+        .and_then(|database| check_database(database.as_deref()))
+    })
+    .await;
+
+    match outcome {
+        Ok(checked) => (
+            StatusCode::OK,
+            Json(CheckMagicResponse {
+                request_id: request_id.as_str().to_string(),
+                valid: checked.is_ok(),
+                error: checked.err().map(|e| e.to_string()),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Check failed: {}", e),
+                code: None,
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// `POST /admin/reload-magic`: load the magic database again and swap it in
+/// for new analyses. Analyses already running finish on the old database;
+/// the content-hash detection cache is emptied.
+#[tracing::instrument(name = "handler.reload_magic", skip(state, request_id))]
+pub async fn reload_magic(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    let Some(reloader) = state.magic_reloader.clone() else {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ErrorResponse {
+                error: "Magic database reload is not supported".to_string(),
+                code: Some("reload_unsupported".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    };
+
+    // Loading a database parses the whole file; keep it off the runtime.
+    let outcome = tokio::task::spawn_blocking(move || reloader.reload())
+        .await
+        .map_err(|e| ApplicationError::InternalError(e.to_string()))
+        .and_then(|r| r.map_err(ApplicationError::from));
+
+    match outcome {
+        Ok(database_version) => {
+            // Cached detections came from the previous database.
+            state.analyze_content_use_case.clear_detection_cache();
+            tracing::info!(database_version, "Magic database reloaded");
+            (
+                StatusCode::OK,
+                Json(ReloadMagicResponse {
+                    request_id: request_id.as_str().to_string(),
+                    database_version,
+                }),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Magic database reload failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Reload failed: {}", e),
+                    code: Some("reload_failed".to_string()),
+                    request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
+                }),
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::application::hashing::HashAlgorithm;
 use crate::domain::value_objects::request_id::RequestId;
 use crate::presentation::state::app_state::AppState;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
@@ -26,3 +27,122 @@ pub async fn ping(
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
 }
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_space_mb: Option<u64>,
+    pub request_id: String,
+}
+
+pub async fn ready(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    match state.readiness_check_use_case.execute().await {
+        Ok(report) => (
+            StatusCode::OK,
+            Json(ReadinessResponse {
+                status: "ready".to_string(),
+                reason: None,
+                free_space_mb: report.free_space_mb,
+                request_id: request_id.as_str().to_string(),
+            }),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::warn!(readiness.reason = e.reason(), error = %e, "Readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ReadinessResponse {
+                    status: "not_ready".to_string(),
+                    reason: Some(e.reason().to_string()),
+                    free_space_mb: e.free_space_mb(),
+                    request_id: request_id.as_str().to_string(),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CapabilitiesResponse {
+    pub version: &'static str,
+    pub auth: AuthCapabilities,
+    pub limits: LimitCapabilities,
+    pub features: FeatureCapabilities,
+    pub request_id: String,
+}
+
+#[derive(Serialize)]
+pub struct AuthCapabilities {
+    /// `Authorization` schemes accepted on protected endpoints.
+    pub schemes: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct LimitCapabilities {
+    pub max_body_size_mb: u64,
+    pub max_batch_items: usize,
+    pub max_session_size_mb: u64,
+    pub analysis_timeout_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_description_length: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct FeatureCapabilities {
+    /// Algorithms accepted by `?hash=`.
+    pub hash_algorithms: Vec<&'static str>,
+    pub custom_rules: bool,
+    pub archive_peek: bool,
+    pub declared_type_fallback: bool,
+    pub encoding_detection: bool,
+    pub content_hash_cache: bool,
+    pub response_envelope: bool,
+    /// Whether the route groups of `[endpoints]` are mounted.
+    pub content_analysis: bool,
+    pub path_analysis: bool,
+    pub batch_analysis: bool,
+}
+
+/// What this instance supports, derived from its effective configuration,
+/// so clients can adapt without reading the operator's config file.
+pub async fn capabilities(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    let config = &state.config;
+    let mut schemes = vec!["basic"];
+    if config.auth.introspection_url.is_some() {
+        schemes.push("bearer");
+    }
+    Json(CapabilitiesResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        auth: AuthCapabilities { schemes },
+        limits: LimitCapabilities {
+            max_body_size_mb: config.server.limits.max_body_size_mb,
+            max_batch_items: config.server.limits.max_batch_items,
+            max_session_size_mb: config.sessions.max_size_mb,
+            analysis_timeout_secs: config.server.timeouts.analysis_timeout_secs,
+            max_description_length: config.analysis.max_description_length,
+        },
+        features: FeatureCapabilities {
+            hash_algorithms: HashAlgorithm::ALL.iter().map(|a| a.name()).collect(),
+            custom_rules: config.magic.allow_custom_rules,
+            archive_peek: config.analysis.peek_archives,
+            declared_type_fallback: config.analysis.fallback_to_declared_type,
+            encoding_detection: config.analysis.text.detects_encoding(),
+            content_hash_cache: config.analysis.content_hash_cache_entries > 0,
+            response_envelope: config.server.response_envelope,
+            content_analysis: config.endpoints.content_enabled,
+            path_analysis: config.endpoints.path_enabled,
+            batch_analysis: config.endpoints.batch_enabled,
+        },
+        request_id: request_id.as_str().to_string(),
+    })
+}
@@ -1,43 +1,648 @@
 use crate::application::errors::ApplicationError;
+use crate::application::hashing::HashAlgorithm;
+use crate::application::memory_budget::MemoryReservation;
+use crate::application::use_cases::analyze_content::{AnalysisOptions, OutputMode};
+use crate::domain::entities::magic_result::MagicResult;
+use crate::domain::errors::ValidationError;
+use crate::domain::services::upload_session_store::UploadSession;
+use crate::domain::services::upload_store::Upload;
+use crate::domain::value_objects::auth::{Principal, SCOPE_ADMIN};
+use crate::domain::value_objects::byte_range::ByteRange;
+use crate::domain::value_objects::data_uri::DataUri;
 use crate::domain::value_objects::filename::WindowsCompatibleFilename;
+use crate::domain::value_objects::mime_type::MimeType;
 use crate::domain::value_objects::request_id::RequestId;
+use crate::infrastructure::integrations::result_signature::{SIGNATURE_FIELD, sign_payload};
 use crate::presentation::http::responses::error_response::ErrorResponse;
-use crate::presentation::http::responses::magic_response::MagicResponse;
+use crate::presentation::http::responses::magic_response::{
+    BatchPathItem, BatchPathResponse, CompareResponse, EchoedContent, IsTextResponse, MagicAnalysisResult, MagicResponse, PathHistoryItem, PathHistoryResponse, TypeVerdict,
+    RESULT_FIELDS, RuleTestResponse, UploadResponse, UploadSessionResponse,
+};
 use crate::presentation::state::app_state::AppState;
 use axum::{
     body::Body,
-    extract::{Query, State},
-    http::{HeaderMap, StatusCode},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     Extension, Json,
 };
 use opentelemetry::KeyValue;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer, Visitor};
 use serde::Deserialize;
-use std::sync::Arc;
-use std::time::Instant;
+use serde_json::Value;
+use futures_util::StreamExt;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Analysis flags shared by every content query.
+#[derive(Deserialize, Debug)]
+pub struct AnalyzeOptionsQuery {
+    #[serde(default, deserialize_with = "query_flag")]
+    pub echo_content_type: bool,
+    #[serde(default, deserialize_with = "query_flag")]
+    pub nested: bool,
+    #[serde(default, deserialize_with = "query_flag")]
+    pub polyglot: bool,
+    pub fields: Option<String>,
+    pub hash: Option<String>,
+    pub output: Option<String>,
+    #[serde(default, deserialize_with = "query_flag")]
+    pub raw_magic: bool,
+    #[serde(default, deserialize_with = "query_flag")]
+    pub store: bool,
+    /// Include libmagic's debug output. Requires the `admin` scope.
+    #[serde(default, deserialize_with = "query_flag")]
+    pub trace: bool,
+    /// Include a hex dump of the start of the content. Requires the `admin`
+    /// scope.
+    #[serde(default, deserialize_with = "query_flag")]
+    pub hexdump: bool,
+    /// Return the body with the result, up to `analysis.max_echo_bytes`.
+    #[serde(default, deserialize_with = "query_flag")]
+    pub echo_content: bool,
+    /// Gzip the echoed body.
+    #[serde(default, deserialize_with = "query_flag")]
+    pub echo_gzip: bool,
+    /// Type the client expects the content to be; see [`TypeVerdict`].
+    pub expected: Option<String>,
+    /// Analyze all of an upload too large for memory, not just its first
+    /// `analysis.head_bytes`.
+    #[serde(default, deserialize_with = "query_flag")]
+    pub full: bool,
+}
 
 #[derive(Deserialize, Debug)]
 pub struct AnalyzeQuery {
     pub filename: String,
+    #[serde(flatten)]
+    pub options: AnalyzeOptionsQuery,
+}
+
+/// Query for `POST /v1/magic/content/{filename}`: [`AnalyzeQuery`] with the
+/// filename taken from the path instead.
+#[derive(Deserialize, Debug)]
+pub struct AnalyzeNamedQuery {
+    #[serde(flatten)]
+    pub options: AnalyzeOptionsQuery,
+}
+
+impl AnalyzeNamedQuery {
+    fn with_filename(self, filename: String) -> AnalyzeQuery {
+        AnalyzeQuery {
+            filename,
+            options: self.options,
+        }
+    }
+}
+
+/// Parse a `true`/`false` query flag. Fields under `#[serde(flatten)]` are
+/// buffered as strings, which serde's own `bool` does not accept.
+fn query_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    struct FlagVisitor;
+
+    impl Visitor<'_> for FlagVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("true or false")
+        }
+
+        fn visit_bool<E: de::Error>(self, value: bool) -> Result<bool, E> {
+            Ok(value)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<bool, E> {
+            value
+                .parse()
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(FlagVisitor)
 }
 
 #[derive(Deserialize, Debug)]
 pub struct AnalyzePathQuery {
     pub filename: String,
     pub path: String,
+    #[serde(default)]
+    pub echo_content_type: bool,
+    pub fields: Option<String>,
+    pub hash: Option<String>,
+    pub output: Option<String>,
+    #[serde(default)]
+    pub raw_magic: bool,
+    /// Record the result in the path's history and store it by content id.
+    #[serde(default)]
+    pub store: bool,
+    /// Follow symlinks despite `sandbox.deny_symlinks`; needs the `admin`
+    /// scope unless `sandbox.allow_symlink_override` is set.
+    #[serde(default)]
+    pub follow_symlinks: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PathHistoryQuery {
+    pub path: String,
+}
+
+/// Query for `/v1/magic/is-text`: `path` selects a sandbox file for `GET`;
+/// `POST` analyzes the body. `filename` defaults to the path's basename, or
+/// `upload` for a body.
+#[derive(Deserialize, Debug)]
+pub struct IsTextQuery {
+    pub filename: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnalyzePathsRequest {
+    /// Filename reported for every entry; defaults to each path's basename.
+    pub filename_default: Option<String>,
+    pub paths: Vec<String>,
+}
+
+/// Query of `/v1/magic/data-uri`; `filename` defaults to `upload`.
+#[derive(Deserialize, Debug)]
+pub struct DataUriQuery {
+    pub filename: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ComparePathsRequest {
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UploadQuery {
+    pub filename: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TestRuleRequest {
+    pub rule: String,
+    pub content_base64: String,
 }
 
 /// Map an [`ApplicationError`] to the `error.kind` string value as defined in
 /// `docs/reference/OBSERVABILITY.md` §7.2.
 fn error_kind(e: &ApplicationError) -> &'static str {
     match e {
-        ApplicationError::Timeout => "timeout",
+        ApplicationError::Timeout(_) => "timeout",
+        ApplicationError::RequestTimeout => "request_timeout",
         ApplicationError::BadRequest(_) => "bad_request",
         ApplicationError::NotFound(_) => "not_found",
-        ApplicationError::InternalError(_) | ApplicationError::UnprocessableEntity(_) => "internal",
+        ApplicationError::InternalError(_)
+        | ApplicationError::UnprocessableEntity(_)
+        | ApplicationError::AnalysisFailed(_) => "internal",
         ApplicationError::InsufficientStorage(_) => "insufficient_storage",
         ApplicationError::Unauthorized(_) => "unauthorized",
         ApplicationError::Forbidden(_) => "forbidden",
+        ApplicationError::ServiceUnavailable(_) => "unavailable",
+        ApplicationError::DisallowedEncoding(_) => "disallowed_encoding",
+        ApplicationError::UnsupportedFileType(_) => "unsupported_file_type",
+        ApplicationError::ContentTooShort(_) => "content_too_short",
+        ApplicationError::RejectedContent(_) => "rejected_content",
+        ApplicationError::RangeNotSatisfiable(_) => "range_not_satisfiable",
+        ApplicationError::PayloadTooLarge(_) => "payload_too_large",
+    }
+}
+
+/// Structured error code for an analysis failure, if it has one. Clients
+/// use `analysis_unavailable` to back off instead of retrying immediately.
+fn error_code(e: &ApplicationError) -> Option<String> {
+    match e {
+        ApplicationError::Timeout(_) => Some("analysis_timeout".to_string()),
+        ApplicationError::ServiceUnavailable(_) => Some("analysis_unavailable".to_string()),
+        ApplicationError::DisallowedEncoding(_) => Some("disallowed_encoding".to_string()),
+        ApplicationError::UnsupportedFileType(_) => Some("unsupported_file_type".to_string()),
+        ApplicationError::ContentTooShort(_) => Some("content_too_short".to_string()),
+        ApplicationError::RejectedContent(_) => Some("rejected_content".to_string()),
+        ApplicationError::RangeNotSatisfiable(_) => Some("range_not_satisfiable".to_string()),
+        ApplicationError::PayloadTooLarge(_) => Some("payload_too_large".to_string()),
+        _ => None,
+    }
+}
+
+/// Error response for a failed analysis, recorded on the span and in the
+/// error metric by its `error.kind`.
+fn analysis_error_response(
+    state: &AppState,
+    e: &ApplicationError,
+    request_id: &RequestId,
+) -> axum::response::Response {
+    let kind = error_kind(e);
+    tracing::Span::current().record("error.kind", kind);
+    state
+        .metrics
+        .analysis_errors
+        .add(1, &[KeyValue::new("error.kind", kind)]);
+    (
+        error_status(state, e),
+        Json(ErrorResponse {
+            error: format!("Analysis failed: {}", e),
+            code: error_code(e),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: e.detail().map(str::to_string),
+        }),
+    )
+        .into_response()
+}
+
+/// Status for `e`; analysis timeouts answer
+/// `server.timeouts.timeout_status_code` instead of a fixed `504`.
+fn error_status(state: &AppState, e: &ApplicationError) -> StatusCode {
+    match e {
+        ApplicationError::Timeout(_) => {
+            StatusCode::from_u16(state.config.server.timeouts.timeout_status_code)
+                .unwrap_or(StatusCode::GATEWAY_TIMEOUT)
+        }
+        _ => e.status_code(),
+    }
+}
+
+/// Map a filename [`ValidationError`] to the structured error code reported
+/// to clients, so "too long" and "bad character" can be told apart.
+fn filename_error_code(e: &ValidationError) -> &'static str {
+    match e {
+        ValidationError::ExceedsMaxLength => "filename_too_long",
+        ValidationError::EmptyValue => "filename_empty",
+        _ => "filename_invalid_char",
+    }
+}
+
+fn invalid_filename_response(e: &ValidationError, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: format!("Invalid filename: {}", e),
+            code: Some(filename_error_code(e).to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Parse a `?fields=` selection. Unknown names are dropped, or returned as
+/// the error when `response.reject_unknown_fields` is set.
+fn parse_fields(fields: Option<&str>, reject_unknown: bool) -> Result<Option<Vec<&str>>, &str> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let mut selected = Vec::new();
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if RESULT_FIELDS.contains(&field) {
+            selected.push(field);
+        } else if reject_unknown {
+            return Err(field);
+        }
+    }
+    Ok(Some(selected))
+}
+
+/// `?trace=true` exposes rule internals and `?hexdump=true` the content
+/// itself, so both are reserved for callers holding the `admin` scope.
+fn admin_option_forbidden_response(
+    option: &str,
+    request_id: &RequestId,
+) -> axum::response::Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse {
+            error: format!("Missing required scope for {}: {}", option, SCOPE_ADMIN),
+            code: Some("insufficient_scope".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+fn echo_too_large_response(max_echo: usize, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(ErrorResponse {
+            error: format!(
+                "Content echo is limited to bodies of at most {} bytes analyzed in memory",
+                max_echo
+            ),
+            code: Some("echo_too_large".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Base64 of `data` for `?echo_content=true`, gzipped first when asked.
+fn echo_content(data: &[u8], gzip: bool) -> EchoedContent {
+    if gzip {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        // Writing to a Vec cannot fail.
+        let compressed = encoder
+            .write_all(data)
+            .and_then(|()| encoder.finish())
+            .unwrap_or_default();
+        return EchoedContent {
+            encoding: "gzip",
+            data: general_purpose::STANDARD.encode(compressed),
+        };
+    }
+    EchoedContent {
+        encoding: "identity",
+        data: general_purpose::STANDARD.encode(data),
+    }
+}
+
+/// Parse `?expected=`, ignoring any parameters; the rejected value is
+/// returned as the error.
+fn parse_expected(value: Option<&str>) -> Result<Option<MimeType>, &str> {
+    value
+        .map(|v| {
+            let essence = v.split(';').next().unwrap_or(v).trim();
+            MimeType::new(&essence.to_ascii_lowercase()).map_err(|_| v)
+        })
+        .transpose()
+}
+
+fn invalid_expected_response(value: &str, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: format!("Invalid expected type: {}", value),
+            code: Some("invalid_expected_type".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// With `analysis.strict_expected`, the `422` answering a result whose type
+/// is not the expected one.
+fn expected_mismatch(
+    state: &AppState,
+    result: &MagicResult,
+    expected: Option<&MimeType>,
+    request_id: &RequestId,
+) -> Option<axum::response::Response> {
+    let expected = expected?;
+    if !state.config.analysis.strict_expected || result.mime_type().essence_eq(expected) {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse {
+                error: format!(
+                    "Detected type {} does not match expected type {}",
+                    result.mime_type(),
+                    expected
+                ),
+                code: Some("type_mismatch".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response(),
+    )
+}
+
+fn has_admin_scope(principal: Option<&Principal>) -> bool {
+    principal.is_some_and(|p| p.has_scope(SCOPE_ADMIN))
+}
+
+fn unknown_field_response(field: &str, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: format!("Unknown response field: {}", field),
+            code: Some("unknown_field".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Parse `?hash=`, a comma-separated list of algorithms; the first unknown
+/// algorithm is returned as the error.
+fn parse_hash(hash: Option<&str>) -> Result<Vec<HashAlgorithm>, &str> {
+    let mut algorithms = Vec::new();
+    for name in hash.into_iter().flat_map(|h| h.split(',')).map(str::trim) {
+        let algorithm = HashAlgorithm::parse(name).ok_or(name)?;
+        if !algorithms.contains(&algorithm) {
+            algorithms.push(algorithm);
+        }
+    }
+    Ok(algorithms)
+}
+
+fn unsupported_hash_response(algorithm: &str, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: format!(
+                "Unsupported hash algorithm: {} (supported: {})",
+                algorithm,
+                HashAlgorithm::ALL
+                    .iter()
+                    .map(|a| a.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            code: Some("unsupported_hash".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Parse `?output=`; an unknown mode is returned as the error.
+fn parse_output(output: Option<&str>) -> Result<Option<OutputMode>, &str> {
+    match output {
+        None => Ok(None),
+        Some(value) => OutputMode::parse(value).map(Some).ok_or(value),
+    }
+}
+
+fn unsupported_output_response(output: &str, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: format!("Unsupported output mode: {}", output),
+            code: Some("unsupported_output".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Narrow the `?fields=` selection (all fields when absent) to what
+/// `?output=` asked for.
+fn restrict_to_output(fields: Option<Vec<&str>>, output: Option<OutputMode>) -> Option<Vec<&str>> {
+    let excluded = match output {
+        Some(OutputMode::Mime) => "description",
+        Some(OutputMode::Description) => "mime_type",
+        Some(OutputMode::Both) | None => return fields,
+    };
+    let mut fields = fields.unwrap_or_else(|| RESULT_FIELDS.to_vec());
+    fields.retain(|field| *field != excluded);
+    Some(fields)
+}
+
+/// Routing hint header carrying the detected MIME type.
+const X_DETECTED_TYPE: &str = "x-detected-type";
+
+const SERVER_TIMING: &str = "server-timing";
+
+/// Build the 200 response. With `echo_content_type`, the `Content-Type`
+/// header carries the detected MIME type instead of `application/json` so
+/// proxies can route on it; the body is still the JSON `MagicResponse`.
+/// Error responses always keep `application/json`. With
+/// `detected_type_header`, the type is also sent as `X-Detected-Type`.
+fn success_response(
+    state: &AppState,
+    result: MagicResult,
+    result_url: Option<String>,
+    content: Option<EchoedContent>,
+    expected: Option<&MimeType>,
+    echo_content_type: bool,
+    fields: Option<&[&str]>,
+) -> axum::response::Response {
+    let detected = HeaderValue::from_str(&result.mime_type().as_str()).ok();
+    let signing_key = state.config.integrations.result_signing_key.as_deref();
+    let verdict = expected.map(|expected| TypeVerdict {
+        detected: result.mime_type().as_str(),
+        expected: expected.as_str(),
+        matches: result.mime_type().essence_eq(expected),
+    });
+    let mut body = MagicResponse::from(result);
+    body.result_url = result_url;
+    body.content = content;
+    body.verdict = verdict;
+    let mut response = match fields {
+        Some(fields) => {
+            // Signed as sent, after the unselected fields are dropped.
+            let mut json = body.to_json_with_fields(fields);
+            if let (Some(key), Value::Object(map)) = (signing_key, &mut json) {
+                let signature = sign_payload(key, &Value::Object(map.clone()));
+                map.insert(SIGNATURE_FIELD.to_string(), Value::String(signature));
+            }
+            (StatusCode::OK, Json(json)).into_response()
+        }
+        None => (StatusCode::OK, Json(body.signed(signing_key))).into_response(),
+    };
+    if let Some(value) = detected {
+        if state.config.server.emit_detected_type_header {
+            response.headers_mut().insert(X_DETECTED_TYPE, value.clone());
+        }
+        if echo_content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, value);
+        }
+    }
+    response
+}
+
+/// Where `GET /v1/results/{content_id}` serves a stored result, including
+/// `server.route_prefix`.
+fn result_url(state: &AppState, content_id: &str) -> String {
+    format!(
+        "{}/v1/results/{}",
+        state.config.server.route_prefix.as_deref().unwrap_or(""),
+        content_id
+    )
+}
+
+/// Format `time` as an HTTP IMF-fixdate (RFC 9110 §5.6.7).
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Whether a file modified at `modified` is unchanged since `since`. HTTP
+/// dates have whole-second precision, so the mtime is truncated before
+/// comparing. A `since` later than the server clock is invalid and never
+/// matches (RFC 9110 §13.1.3).
+fn is_not_modified(modified: DateTime<Utc>, since: DateTime<Utc>) -> bool {
+    since <= Utc::now() && modified.timestamp() <= since.timestamp()
+}
+
+fn insert_last_modified(response: &mut axum::response::Response, modified: DateTime<Utc>) {
+    if let Ok(value) = HeaderValue::from_str(&http_date(modified)) {
+        response.headers_mut().insert(header::LAST_MODIFIED, value);
+    }
+}
+
+/// `Server-Timing` value (milliseconds) for the phases recorded on `result`
+/// plus the handler's `total`.
+fn server_timing(result: &MagicResult, total: Duration) -> String {
+    let phases = [
+        ("read", result.read_duration()),
+        ("detect", result.detect_duration()),
+        ("total", Some(total)),
+    ];
+    phases
+        .iter()
+        .filter_map(|(name, duration)| {
+            duration.map(|d| format!("{};dur={:.3}", name, d.as_secs_f64() * 1000.0))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn insert_server_timing(response: &mut axum::response::Response, timing: &str) {
+    if let Ok(value) = HeaderValue::from_str(timing) {
+        response.headers_mut().insert(SERVER_TIMING, value);
+    }
+}
+
+/// Whether to spool an upload to a temp file rather than buffer it, with the
+/// in-memory budget reservation to hold otherwise. Chunked and large bodies
+/// always go to a file; others spill there (or are rejected) when concurrent
+/// in-memory analyses use up the budget.
+fn content_strategy(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(bool, Option<MemoryReservation>), ApplicationError> {
+    let is_chunked = headers
+        .get(axum::http::header::TRANSFER_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.contains("chunked"))
+        .unwrap_or(false);
+
+    let content_length = headers
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+
+    let threshold = (state.config.analysis.large_file_threshold_mb * 1024 * 1024) as u64;
+
+    if is_chunked || content_length.map(|l| l > threshold).unwrap_or(false) {
+        return Ok((true, None));
+    }
+    match state
+        .memory_budget
+        .try_reserve(content_length.unwrap_or(threshold))
+    {
+        Some(reservation) => Ok((false, Some(reservation))),
+        None if state.config.analysis.reject_over_memory_budget => Err(
+            ApplicationError::ServiceUnavailable("In-memory analysis budget exhausted".to_string()),
+        ),
+        None => Ok((true, None)),
     }
 }
 
@@ -48,47 +653,98 @@ fn error_kind(e: &ApplicationError) -> &'static str {
         analysis.strategy = tracing::field::Empty,
         error.kind = tracing::field::Empty,
     ),
-    skip(state, headers, body, request_id, query),
+    skip(state, headers, body, request_id, principal, query),
 )]
 pub async fn analyze_content(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Query(query): Query<AnalyzeQuery>,
     Extension(request_id): Extension<RequestId>,
+    principal: Option<Extension<Principal>>,
     body: Body,
 ) -> impl IntoResponse {
-    let is_chunked = headers
-        .get(axum::http::header::TRANSFER_ENCODING)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.contains("chunked"))
-        .unwrap_or(false);
+    let received = Arc::new(AtomicU64::new(0));
+    let max_echo = state.config.analysis.max_echo_bytes;
+    // Copied up to one byte past the cap, enough to tell it was exceeded.
+    let echoed = query.options.echo_content.then(|| Arc::new(Mutex::new(Vec::new())));
+    let body_stream = body.into_data_stream().inspect({
+        let received = received.clone();
+        let echoed = echoed.clone();
+        move |chunk| {
+            if let Ok(chunk) = chunk {
+                received.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                if let Some(echoed) = &echoed {
+                    let mut echoed = echoed.lock().unwrap();
+                    let room = (max_echo + 1).saturating_sub(echoed.len());
+                    echoed.extend_from_slice(&chunk[..chunk.len().min(room)]);
+                }
+            }
+        }
+    });
+    let filename = match WindowsCompatibleFilename::new(&query.filename) {
+        Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+    if query.options.trace && !has_admin_scope(principal.as_deref()) {
+        return admin_option_forbidden_response("trace", &request_id);
+    }
+    if query.options.hexdump && !has_admin_scope(principal.as_deref()) {
+        return admin_option_forbidden_response("hexdump", &request_id);
+    }
+    let reject_unknown = state.config.response.reject_unknown_fields;
+    let fields = match parse_fields(query.options.fields.as_deref(), reject_unknown) {
+        Ok(f) => f,
+        Err(field) => return unknown_field_response(field, &request_id),
+    };
+    let hashes = match parse_hash(query.options.hash.as_deref()) {
+        Ok(h) => h,
+        Err(algorithm) => return unsupported_hash_response(algorithm, &request_id),
+    };
+    let output = match parse_output(query.options.output.as_deref()) {
+        Ok(o) => o,
+        Err(output) => return unsupported_output_response(output, &request_id),
+    };
+    let fields = restrict_to_output(fields, output);
+    let expected = match parse_expected(query.options.expected.as_deref()) {
+        Ok(e) => e,
+        Err(value) => return invalid_expected_response(value, &request_id),
+    };
 
-    let content_length = headers
-        .get(axum::http::header::CONTENT_LENGTH)
+    // Held until the response is built.
+    let (force_to_file, _memory) = match content_strategy(&state, &headers) {
+        Ok(strategy) => strategy,
+        Err(e) => return analysis_error_response(&state, &e, &request_id),
+    };
+    // Only bodies buffered in memory are echoed; spooled ones are too large.
+    let declared_length = headers
+        .get(header::CONTENT_LENGTH)
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok());
+    if query.options.echo_content
+        && (force_to_file || declared_length.is_some_and(|l| l > max_echo as u64))
+    {
+        return echo_too_large_response(max_echo, &request_id);
+    }
 
-    let threshold = (state.config.analysis.large_file_threshold_mb * 1024 * 1024) as u64;
-
-    let force_to_file = is_chunked || content_length.map(|l| l > threshold).unwrap_or(false);
-
-    let body_stream = body.into_data_stream();
-    let filename = match WindowsCompatibleFilename::new(&query.filename) {
-        Ok(f) => f,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: format!("Invalid filename: {}", e),
-                    request_id: Some(request_id.as_str().to_string()),
-                }),
-            )
-                .into_response()
-        }
+    let if_content_hash = headers
+        .get("if-content-hash")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string());
+    // Digests cover every byte, so asking for one implies a full read.
+    let head_only = force_to_file
+        && !query.options.full
+        && state.config.analysis.head_bytes > 0
+        && !query.options.store
+        && query.options.hash.is_none()
+        && if_content_hash.is_none();
+    let strategy_str = match (force_to_file, head_only) {
+        (true, true) => "head",
+        (true, false) => "temp_file",
+        _ => "in_memory",
     };
-
-    let strategy_str = if force_to_file { "temp_file" } else { "in_memory" };
     tracing::Span::current().record("analysis.strategy", strategy_str);
+    let strategy_labels = [KeyValue::new("analysis.strategy", strategy_str)];
+    state.metrics.analysis_strategy.add(1, &strategy_labels);
 
     // Track active requests
     let active_labels = [
@@ -97,91 +753,565 @@ pub async fn analyze_content(
     ];
     state.metrics.http_active_requests.add(1, &active_labels);
 
+    let declared_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .and_then(|essence| MimeType::new(&essence.trim().to_ascii_lowercase()).ok());
+    let options = AnalysisOptions {
+        nested: query.options.nested,
+        polyglot: query.options.polyglot,
+        // The stored result is keyed by the content's SHA-256.
+        hash: query.options.store || hashes.contains(&HashAlgorithm::Sha256),
+        hashes,
+        declared_type,
+        output,
+        raw: query.options.raw_magic,
+        trace: query.options.trace,
+        hexdump: query.options.hexdump,
+        if_content_hash,
+        size_hint: declared_length,
+        ..AnalysisOptions::default()
+    };
     let start = Instant::now();
 
-    let result = if force_to_file {
+    let result = if head_only {
         state
             .analyze_content_use_case
-            .analyze_to_temp_file(request_id.clone(), filename, body_stream)
+            .analyze_head(request_id.clone(), filename, body_stream, options)
+            .await
+    } else if force_to_file {
+        state
+            .analyze_content_use_case
+            .analyze_to_temp_file(request_id.clone(), filename, body_stream, options)
             .await
     } else {
         state
             .analyze_content_use_case
-            .analyze_in_memory(request_id.clone(), filename, body_stream)
+            .analyze_in_memory(request_id.clone(), filename, body_stream, options)
             .await
     };
 
-    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
-    let analysis_type = if force_to_file {
-        "content_to_file"
-    } else {
-        "content_in_memory"
+    let elapsed = start.elapsed();
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    let analysis_type = match (force_to_file, head_only) {
+        (true, true) => "content_head",
+        (true, false) => "content_to_file",
+        _ => "content_in_memory",
     };
 
     state.metrics.http_active_requests.add(-1, &active_labels);
 
     match result {
         Ok(res) => {
+            if let Some(response) = expected_mismatch(&state, &res, expected.as_ref(), &request_id) {
+                return response;
+            }
+            // An overflowing echo fails the request, so nothing is published
+            // or stored for it.
+            let content = match echoed {
+                Some(echoed) => {
+                    let data = std::mem::take(&mut *echoed.lock().unwrap());
+                    if data.len() > max_echo {
+                        return echo_too_large_response(max_echo, &request_id);
+                    }
+                    Some(echo_content(&data, query.options.echo_gzip))
+                }
+                None => None,
+            };
             state.metrics.analysis_duration.record(
                 elapsed_ms,
                 &[KeyValue::new("analysis.type", analysis_type)],
             );
-            (StatusCode::OK, Json(MagicResponse::from(res))).into_response()
-        }
-        Err(e) => {
-            let kind = error_kind(&e);
-            tracing::Span::current().record("error.kind", kind);
             state
                 .metrics
-                .analysis_errors
-                .add(1, &[KeyValue::new("error.kind", kind)]);
-            (
-                e.status_code(),
-                Json(ErrorResponse {
-                    error: format!("Analysis failed: {}", e),
-                    request_id: Some(request_id.as_str().to_string()),
-                }),
-            )
-                .into_response()
+                .analysis_body_size
+                .record(received.load(Ordering::Relaxed), &strategy_labels);
+            state.result_sink.publish(&res);
+            let result_url = match res.hash() {
+                Some(content_id) if query.options.store => {
+                    state.result_store.put(content_id, res.clone());
+                    Some(result_url(&state, content_id))
+                }
+                _ => None,
+            };
+            let timing = server_timing(&res, elapsed);
+            let mut response = success_response(
+                &state,
+                res,
+                result_url,
+                content,
+                expected.as_ref(),
+                query.options.echo_content_type,
+                fields.as_deref(),
+            );
+            insert_server_timing(&mut response, &timing);
+            response
         }
+        Err(e) => analysis_error_response(&state, &e, &request_id),
     }
 }
 
+/// `POST /v1/magic/content/{filename}`. The path segment arrives
+/// percent-decoded, so an encoded slash (`%2F`) decodes to `/` and is
+/// rejected by filename validation like any other separator.
+pub async fn analyze_named_content(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(filename): Path<String>,
+    Query(query): Query<AnalyzeNamedQuery>,
+    request_id: Extension<RequestId>,
+    principal: Option<Extension<Principal>>,
+    body: Body,
+) -> axum::response::Response {
+    analyze_content(
+        state,
+        headers,
+        Query(query.with_filename(filename)),
+        request_id,
+        principal,
+        body,
+    )
+    .await
+    .into_response()
+}
+
 #[tracing::instrument(
     name = "handler.analyze_path",
     fields(
         analysis.filename = %query.filename,
         error.kind = tracing::field::Empty,
     ),
-    skip(state, request_id, query),
+    skip(state, headers, request_id, principal, query),
 )]
 pub async fn analyze_path(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(query): Query<AnalyzePathQuery>,
     Extension(request_id): Extension<RequestId>,
+    principal: Option<Extension<Principal>>,
 ) -> impl IntoResponse {
-    let filename = match WindowsCompatibleFilename::new(&query.filename) {
+    if query.follow_symlinks
+        && !state.config.sandbox.allow_symlink_override
+        && !has_admin_scope(principal.as_deref())
+    {
+        return admin_option_forbidden_response("follow_symlinks", &request_id);
+    }
+    let filename = match sandbox_filename(&state, &query.filename) {
         Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+
+    let path = match crate::domain::value_objects::path::RelativePath::new(&query.path) {
+        Ok(p) => p,
         Err(e) => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: format!("Invalid filename: {}", e),
+                    error: format!("Invalid path: {}", e),
+                    code: None,
                     request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
                 }),
             )
                 .into_response()
         }
     };
+    let reject_unknown = state.config.response.reject_unknown_fields;
+    let fields = match parse_fields(query.fields.as_deref(), reject_unknown) {
+        Ok(f) => f,
+        Err(field) => return unknown_field_response(field, &request_id),
+    };
+    let hashes = match parse_hash(query.hash.as_deref()) {
+        Ok(h) => h,
+        Err(algorithm) => return unsupported_hash_response(algorithm, &request_id),
+    };
+    let output = match parse_output(query.output.as_deref()) {
+        Ok(o) => o,
+        Err(output) => return unsupported_output_response(output, &request_id),
+    };
+    let fields = restrict_to_output(fields, output);
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(ByteRange::parse);
+    let if_range = headers
+        .get(header::IF_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string());
+    let options = AnalysisOptions {
+        // The stored result is keyed by the content's SHA-256.
+        hash: query.store || hashes.contains(&HashAlgorithm::Sha256),
+        hashes,
+        output,
+        raw: query.raw_magic,
+        range,
+        if_range,
+        follow_symlinks: query.follow_symlinks,
+        ..AnalysisOptions::default()
+    };
 
-    let path = match crate::domain::value_objects::path::RelativePath::new(&query.path) {
-        Ok(p) => p,
+    let start = Instant::now();
+    let opened = match state
+        .analyze_path_use_case
+        .open(&path, options.follow_symlinks)
+    {
+        Ok(opened) => opened,
+        Err(e) => return analysis_error_response(&state, &e, &request_id),
+    };
+
+    // Answer If-Modified-Since from the opened file's metadata, without
+    // analyzing it.
+    let if_modified_since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_http_date);
+    if let Some(since) = if_modified_since
+        && let Some(modified) = opened.modified()
+        && is_not_modified(modified, since)
+    {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        insert_last_modified(&mut response, modified);
+        return response;
+    }
+
+    match state
+        .analyze_path_use_case
+        .execute_opened(request_id.clone(), filename, path, opened, options)
+        .await
+    {
+        Ok(result) => {
+            state.result_sink.publish(&result);
+            let result_url = if query.store {
+                if let Some(path) = result.source_path() {
+                    state.result_store.record_path(path, result.clone());
+                }
+                result.hash().map(|content_id| {
+                    state.result_store.put(content_id, result.clone());
+                    result_url(&state, content_id)
+                })
+            } else {
+                None
+            };
+            let modified = result.modified_at();
+            let entity_tag = result.entity_tag().map(str::to_string);
+            let timing = server_timing(&result, start.elapsed());
+            let mut response = success_response(
+                &state,
+                result,
+                result_url,
+                None,
+                None,
+                query.echo_content_type,
+                fields.as_deref(),
+            );
+            if let Some(modified) = modified {
+                insert_last_modified(&mut response, modified);
+            }
+            if let Some(value) = entity_tag.and_then(|tag| HeaderValue::from_str(&tag).ok()) {
+                response.headers_mut().insert(header::ETAG, value);
+            }
+            insert_server_timing(&mut response, &timing);
+            response
+        }
+        Err(e) => analysis_error_response(&state, &e, &request_id),
+    }
+}
+
+/// Analyze an explicit list of sandbox-relative paths. Failures are reported
+/// per entry; the response is 200 as long as the request itself is valid.
+#[tracing::instrument(
+    name = "handler.analyze_paths",
+    fields(analysis.batch_size = body.paths.len()),
+    skip(state, request_id, body),
+)]
+pub async fn analyze_paths(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    Json(body): Json<AnalyzePathsRequest>,
+) -> impl IntoResponse {
+    let max_items = state.config.server.limits.max_batch_items;
+    if body.paths.len() > max_items {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Batch exceeds {} paths", max_items),
+                code: Some("batch_too_large".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    }
+
+    // Entries run concurrently and finish in any order; each keeps its input
+    // index so the response lists them as requested.
+    let filename_default = body.filename_default.as_deref();
+    let (state, request_id) = (&state, &request_id);
+    let mut indexed: Vec<(usize, BatchPathItem)> = futures_util::stream::iter(
+        body.paths.into_iter().enumerate(),
+    )
+    .map(|(index, raw_path)| async move {
+        let outcome = analyze_batch_entry(state, request_id, filename_default, &raw_path).await;
+        let item = match outcome {
+            Ok(result) => {
+                state.result_sink.publish(&result);
+                let signing_key = state.config.integrations.result_signing_key.as_deref();
+                BatchPathItem::success(raw_path, MagicResponse::from(result).signed(signing_key))
+            }
+            Err(e) => {
+                state
+                    .metrics
+                    .analysis_errors
+                    .add(1, &[KeyValue::new("error.kind", error_kind(&e))]);
+                BatchPathItem::failure(raw_path, error_status(state, &e).as_u16(), e.to_string())
+            }
+        };
+        (index, item)
+    })
+    .buffer_unordered(state.config.server.limits.batch_concurrency.max(1))
+    .collect()
+    .await;
+    indexed.sort_by_key(|(index, _)| *index);
+    let results = indexed.into_iter().map(|(_, item)| item).collect();
+
+    (
+        StatusCode::OK,
+        Json(BatchPathResponse {
+            request_id: request_id.as_str().to_string(),
+            results,
+        }),
+    )
+        .into_response()
+}
+
+/// `POST /v1/magic/compare`: analyze two sandbox paths and report whether
+/// their types match. Unlike a batch, the request fails if either does.
+#[tracing::instrument(name = "handler.compare_paths", skip(state, request_id, body))]
+pub async fn compare_paths(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    Json(body): Json<ComparePathsRequest>,
+) -> axum::response::Response {
+    let (a, b) = futures_util::join!(
+        analyze_batch_entry(&state, &request_id, None, &body.a),
+        analyze_batch_entry(&state, &request_id, None, &body.b),
+    );
+    let (a, b) = match (a, b) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => return analysis_error_response(&state, &e, &request_id),
+    };
+    let same_type = a.mime_type().essence_eq(b.mime_type());
+    (
+        StatusCode::OK,
+        Json(CompareResponse {
+            request_id: request_id.as_str().to_string(),
+            a: MagicResponse::from(a).result,
+            b: MagicResponse::from(b).result,
+            same_type,
+        }),
+    )
+        .into_response()
+}
+
+/// `POST /v1/magic/is-text`: whether the body is text, without the rest of
+/// the analysis result.
+pub async fn is_text_content(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<IsTextQuery>,
+    Extension(request_id): Extension<RequestId>,
+    body: Body,
+) -> axum::response::Response {
+    let filename = match WindowsCompatibleFilename::new(query.filename.as_deref().unwrap_or("upload")) {
+        Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+    let (force_to_file, _memory) = match content_strategy(&state, &headers) {
+        Ok(strategy) => strategy,
+        Err(e) => return analysis_error_response(&state, &e, &request_id),
+    };
+    let use_case = &state.analyze_content_use_case;
+    let stream = body.into_data_stream();
+    let options = AnalysisOptions::default();
+    let result = if force_to_file {
+        use_case
+            .analyze_to_temp_file(request_id.clone(), filename, stream, options)
+            .await
+    } else {
+        use_case
+            .analyze_in_memory(request_id.clone(), filename, stream, options)
+            .await
+    };
+    match result {
+        Ok(result) => is_text_response(&state, &result),
+        Err(e) => analysis_error_response(&state, &e, &request_id),
+    }
+}
+
+/// `POST /v1/magic/data-uri`: analyze the content of the `data:` URI sent
+/// as the body. The URI's media type is reported as `verdict.expected`
+/// beside the detected type, and is the declared type for
+/// `analysis.fallback_to_declared_type`.
+#[tracing::instrument(
+    name = "handler.analyze_data_uri",
+    fields(error.kind = tracing::field::Empty),
+    skip(state, request_id, body),
+)]
+pub async fn analyze_data_uri(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DataUriQuery>,
+    Extension(request_id): Extension<RequestId>,
+    body: axum::body::Bytes,
+) -> axum::response::Response {
+    let filename = match WindowsCompatibleFilename::new(query.filename.as_deref().unwrap_or("upload")) {
+        Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+    let uri = match std::str::from_utf8(&body)
+        .map_err(|_| ValidationError::InvalidCharacter)
+        .and_then(DataUri::parse)
+    {
+        Ok(uri) => uri,
         Err(e) => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: format!("Invalid path: {}", e),
+                    error: format!("Invalid data URI: {}", e),
+                    code: Some("invalid_data_uri".to_string()),
+                    request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
+                }),
+            )
+                .into_response();
+        }
+    };
+    let (declared_type, data) = uri.into_parts();
+    let options = AnalysisOptions {
+        declared_type: Some(declared_type.clone()),
+        ..AnalysisOptions::default()
+    };
+    let stream = futures_util::stream::iter([Ok::<_, std::convert::Infallible>(
+        axum::body::Bytes::from(data),
+    )]);
+
+    let start = Instant::now();
+    let result = state
+        .analyze_content_use_case
+        .analyze_in_memory(request_id.clone(), filename, stream, options)
+        .await;
+    match result {
+        Ok(res) => {
+            state.metrics.analysis_duration.record(
+                start.elapsed().as_secs_f64() * 1000.0,
+                &[KeyValue::new("analysis.type", "data_uri")],
+            );
+            state.result_sink.publish(&res);
+            success_response(&state, res, None, None, Some(&declared_type), false, None)
+        }
+        Err(e) => analysis_error_response(&state, &e, &request_id),
+    }
+}
+
+/// `GET /v1/magic/is-text?path=`: whether a sandbox file is text.
+pub async fn is_text_path(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<IsTextQuery>,
+    Extension(request_id): Extension<RequestId>,
+) -> axum::response::Response {
+    let Some(raw_path) = query.path.as_deref() else {
+        let e = ApplicationError::BadRequest("Missing path".to_string());
+        return analysis_error_response(&state, &e, &request_id);
+    };
+    match analyze_batch_entry(&state, &request_id, query.filename.as_deref(), raw_path).await {
+        Ok(result) => is_text_response(&state, &result),
+        Err(e) => analysis_error_response(&state, &e, &request_id),
+    }
+}
+
+/// `text`/`binary` per `analysis.text.textual_subtypes`, judged on the
+/// detected type even when `analysis.unknown_type_alias` replaced it.
+fn is_text_response(state: &AppState, result: &MagicResult) -> axum::response::Response {
+    let is_text = result
+        .textual()
+        .unwrap_or_else(|| state.config.analysis.text.is_textual(result.mime_type()));
+    (
+        StatusCode::OK,
+        Json(IsTextResponse {
+            request_id: result.request_id().as_str().to_string(),
+            is_text,
+            mime_type: result.mime_type().as_str(),
+        }),
+    )
+        .into_response()
+}
+
+/// Filename for a path analysis, lowercased when the sandbox is on a
+/// case-insensitive filesystem.
+fn sandbox_filename(
+    state: &AppState,
+    filename: &str,
+) -> Result<WindowsCompatibleFilename, ValidationError> {
+    if state.config.sandbox.case_insensitive {
+        WindowsCompatibleFilename::new_normalized(filename)
+    } else {
+        WindowsCompatibleFilename::new(filename)
+    }
+}
+
+async fn analyze_batch_entry(
+    state: &AppState,
+    request_id: &RequestId,
+    filename_default: Option<&str>,
+    raw_path: &str,
+) -> Result<MagicResult, ApplicationError> {
+    let path = crate::domain::value_objects::path::RelativePath::new(raw_path)
+        .map_err(|e| ApplicationError::BadRequest(format!("Invalid path: {}", e)))?;
+    let name = filename_default
+        .unwrap_or_else(|| raw_path.rsplit('/').next().unwrap_or(raw_path));
+    let filename = sandbox_filename(state, name)
+        .map_err(|e| ApplicationError::BadRequest(format!("Invalid filename: {}", e)))?;
+    state
+        .analyze_path_use_case
+        .execute(request_id.clone(), filename, path, AnalysisOptions::default())
+        .await
+}
+
+/// Run a user-supplied magic rule against base64 content. Disabled unless
+/// `magic.allow_custom_rules` is set.
+#[tracing::instrument(
+    name = "handler.test_rule",
+    fields(error.kind = tracing::field::Empty),
+    skip(state, request_id, body),
+)]
+pub async fn test_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    Json(body): Json<TestRuleRequest>,
+) -> impl IntoResponse {
+    if !state.config.magic.allow_custom_rules {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: "Custom magic rules are disabled".to_string(),
+                code: Some("custom_rules_disabled".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    }
+
+    let content = match general_purpose::STANDARD.decode(&body.content_base64) {
+        Ok(c) => c,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Invalid content_base64: {}", e),
+                    code: None,
                     request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
                 }),
             )
                 .into_response()
@@ -189,26 +1319,450 @@ pub async fn analyze_path(
     };
 
     match state
-        .analyze_path_use_case
-        .execute(request_id.clone(), filename, path)
+        .test_magic_rule_use_case
+        .execute(&body.rule, &content)
         .await
     {
-        Ok(result) => (StatusCode::OK, Json(MagicResponse::from(result))).into_response(),
+        Ok((mime_type, description)) => (
+            StatusCode::OK,
+            Json(RuleTestResponse {
+                request_id: request_id.as_str().to_string(),
+                result: MagicAnalysisResult {
+                    mime_type: mime_type.as_str(),
+                    description,
+                    encoding: None,
+                    requires_transcoding: None,
+                    category: None,
+                    polyglot_types: None,
+                    is_polyglot: None,
+                    archive_first_entry: None,
+                    nested: None,
+                    hash: None,
+                    hashes: None,
+                    verified: None,
+                    aliased: None,
+                    description_truncated: None,
+                    raw: None,
+                    trace: None,
+                    hexdump: None,
+                    cached: None,
+                    stale: None,
+                    database: None,
+                    range: None,
+                },
+            }),
+        )
+            .into_response(),
         Err(e) => {
             let kind = error_kind(&e);
             tracing::Span::current().record("error.kind", kind);
-            state
-                .metrics
-                .analysis_errors
-                .add(1, &[KeyValue::new("error.kind", kind)]);
             (
-                e.status_code(),
+                error_status(&state, &e),
+                Json(ErrorResponse {
+                    error: format!("Rule test failed: {}", e),
+                    code: error_code(&e),
+                    request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Return a result stored by `POST /v1/magic/content?store=true`, as
+/// originally answered.
+#[tracing::instrument(name = "handler.get_result", skip(state, request_id))]
+pub async fn get_result(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    Path(content_id): Path<String>,
+) -> impl IntoResponse {
+    match state.result_store.get(&content_id) {
+        Some(result) => {
+            let signing_key = state.config.integrations.result_signing_key.as_deref();
+            (StatusCode::OK, Json(MagicResponse::from(result).signed(signing_key))).into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("No stored result for {}", content_id),
+                code: Some("result_not_found".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// `GET /v1/results/by-path`: the analyses of a sandbox path stored with
+/// `POST /v1/magic/path?store=true`, oldest first, for spotting a file whose
+/// type has changed.
+#[tracing::instrument(name = "handler.get_path_history", skip(state, request_id, query))]
+pub async fn get_path_history(
+    State(state): State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    Query(query): Query<PathHistoryQuery>,
+) -> impl IntoResponse {
+    let path = match crate::domain::value_objects::path::RelativePath::new(&query.path) {
+        Ok(p) => p,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: format!("Analysis failed: {}", e),
+                    error: format!("Invalid path: {}", e),
+                    code: None,
                     request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
                 }),
             )
                 .into_response()
         }
+    };
+    let history = state
+        .result_store
+        .path_history(path.as_str())
+        .into_iter()
+        .map(|entry| PathHistoryItem {
+            stored_at: entry.stored_at.to_rfc3339(),
+            request_id: entry.result.request_id().as_str().to_string(),
+            mime_type: entry.result.mime_type().as_str().to_string(),
+            description: entry.result.description().to_string(),
+            content_id: entry.result.hash().map(str::to_string),
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        Json(PathHistoryResponse {
+            request_id: request_id.as_str().to_string(),
+            path: path.as_str().to_string(),
+            history,
+        }),
+    )
+        .into_response()
+}
+
+/// `POST /v1/upload`: keep the body in a temp file and answer with a token
+/// for analyzing it, possibly several times, with
+/// `POST /v1/magic/token/{token}`.
+#[tracing::instrument(name = "handler.upload_content", skip(state, request_id, body))]
+pub async fn upload_content(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UploadQuery>,
+    Extension(request_id): Extension<RequestId>,
+    body: Body,
+) -> impl IntoResponse {
+    let filename = match WindowsCompatibleFilename::new(&query.filename) {
+        Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+    let stored = state
+        .analyze_content_use_case
+        .store_upload(body.into_data_stream())
+        .await;
+    let (file, handle) = match stored {
+        Ok(stored) => stored,
+        Err(e) => {
+            return (
+                e.status_code(),
+                Json(ErrorResponse {
+                    error: format!("Upload failed: {}", e),
+                    code: None,
+                    request_id: Some(request_id.as_str().to_string()),
+                    detail: None,
+                }),
+            )
+                .into_response();
+        }
+    };
+    let size = handle.metadata().map(|m| m.len()).unwrap_or(0);
+    let token = state.upload_store.put(Upload {
+        filename,
+        file,
+        handle,
+    });
+    (
+        StatusCode::CREATED,
+        Json(UploadResponse {
+            request_id: request_id.as_str().to_string(),
+            token,
+            size,
+            expires_in_secs: state.config.uploads.ttl_secs,
+        }),
+    )
+        .into_response()
+}
+
+/// `POST /v1/magic/token/{token}`: analyze content kept by `POST /v1/upload`
+/// with the same query flags as `POST /v1/magic/content/{filename}`.
+#[tracing::instrument(
+    name = "handler.analyze_upload",
+    fields(error.kind = tracing::field::Empty),
+    skip(state, request_id, principal, query),
+)]
+pub async fn analyze_upload(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(query): Query<AnalyzeNamedQuery>,
+    Extension(request_id): Extension<RequestId>,
+    principal: Option<Extension<Principal>>,
+) -> impl IntoResponse {
+    let Some((filename, file)) = state.upload_store.get(&token) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("No upload for token {}", token),
+                code: Some("upload_not_found".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    };
+    analyze_kept_content(&state, request_id, principal.as_deref(), filename, file, &query.options).await
+}
+
+/// `POST /v1/session`: start assembling content from several requests.
+/// Chunks are appended with `PATCH /v1/session/{id}` and the result analyzed
+/// with `POST /v1/session/{id}/analyze`.
+#[tracing::instrument(name = "handler.start_session", skip(state, request_id))]
+pub async fn start_session(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UploadQuery>,
+    Extension(request_id): Extension<RequestId>,
+) -> impl IntoResponse {
+    let filename = match WindowsCompatibleFilename::new(&query.filename) {
+        Ok(f) => f,
+        Err(e) => return invalid_filename_response(&e, &request_id),
+    };
+    let file = match state.analyze_content_use_case.start_session().await {
+        Ok(file) => file,
+        Err(e) => return session_error_response(&e, &request_id),
+    };
+    let session = UploadSession {
+        filename,
+        file,
+        size: 0,
+    };
+    let Some(session_id) = state.session_store.create(session) else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "Too many open upload sessions".to_string(),
+                code: Some("too_many_sessions".to_string()),
+                request_id: Some(request_id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    };
+    (
+        StatusCode::CREATED,
+        Json(UploadSessionResponse {
+            request_id: request_id.as_str().to_string(),
+            session_id,
+            size: 0,
+            expires_in_secs: state.config.sessions.ttl_secs,
+        }),
+    )
+        .into_response()
+}
+
+/// `PATCH /v1/session/{id}`: append the body to the session's content.
+///
+/// A body that would take the session past `sessions.max_size_mb` is
+/// rejected with 413. When its `Content-Length` gives that away up front the
+/// session is left as it was; otherwise the session is discarded, since part
+/// of the body may already have been appended.
+#[tracing::instrument(name = "handler.append_session", skip(state, request_id, headers, body))]
+pub async fn append_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Extension(request_id): Extension<RequestId>,
+    headers: HeaderMap,
+    body: Body,
+) -> impl IntoResponse {
+    let Some(session) = state.session_store.get(&id) else {
+        return session_not_found_response(&id, &request_id);
+    };
+    let mut session = session.lock().await;
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    let max_size_mb = state.config.sessions.max_size_mb;
+    if declared.is_some_and(|len| session.size + len > max_size_mb * 1024 * 1024) {
+        let e = ApplicationError::PayloadTooLarge(format!(
+            "Session content exceeds {}MB",
+            max_size_mb
+        ));
+        return session_error_response(&e, &request_id);
+    }
+    let appended = state
+        .analyze_content_use_case
+        .append_to_session(&mut session, body.into_data_stream())
+        .await;
+    if let Err(e) = appended {
+        if matches!(e, ApplicationError::PayloadTooLarge(_)) {
+            state.session_store.remove(&id);
+        }
+        return session_error_response(&e, &request_id);
+    }
+    (
+        StatusCode::OK,
+        Json(UploadSessionResponse {
+            request_id: request_id.as_str().to_string(),
+            session_id: id,
+            size: session.size,
+            expires_in_secs: state.config.sessions.ttl_secs,
+        }),
+    )
+        .into_response()
+}
+
+/// `POST /v1/session/{id}/analyze`: analyze everything appended so far with
+/// the same query flags as `POST /v1/magic/content/{filename}`. The session
+/// stays open until it expires.
+#[tracing::instrument(
+    name = "handler.analyze_session",
+    fields(error.kind = tracing::field::Empty),
+    skip(state, request_id, principal, query),
+)]
+pub async fn analyze_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<AnalyzeNamedQuery>,
+    Extension(request_id): Extension<RequestId>,
+    principal: Option<Extension<Principal>>,
+) -> impl IntoResponse {
+    let Some(session) = state.session_store.get(&id) else {
+        return session_not_found_response(&id, &request_id);
+    };
+    let (filename, file) = {
+        let mut session = session.lock().await;
+        match state
+            .analyze_content_use_case
+            .session_content(&mut session)
+            .await
+        {
+            Ok(file) => (session.filename.clone(), file),
+            Err(e) => return analysis_error_response(&state, &e, &request_id),
+        }
+    };
+    analyze_kept_content(&state, request_id, principal.as_deref(), filename, file, &query.options).await
+}
+
+fn session_not_found_response(id: &str, request_id: &RequestId) -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: format!("No upload session {}", id),
+            code: Some("session_not_found".to_string()),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+fn session_error_response(e: &ApplicationError, request_id: &RequestId) -> axum::response::Response {
+    (
+        e.status_code(),
+        Json(ErrorResponse {
+            error: format!("Upload failed: {}", e),
+            code: error_code(e),
+            request_id: Some(request_id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Analyze content kept on the server, by upload token or upload session,
+/// with the query flags of `POST /v1/magic/content/{filename}`.
+async fn analyze_kept_content(
+    state: &AppState,
+    request_id: RequestId,
+    principal: Option<&Principal>,
+    filename: WindowsCompatibleFilename,
+    file: std::fs::File,
+    query: &AnalyzeOptionsQuery,
+) -> axum::response::Response {
+    if query.trace && !has_admin_scope(principal) {
+        return admin_option_forbidden_response("trace", &request_id);
+    }
+    if query.hexdump && !has_admin_scope(principal) {
+        return admin_option_forbidden_response("hexdump", &request_id);
+    }
+    if query.echo_content {
+        return echo_too_large_response(state.config.analysis.max_echo_bytes, &request_id);
+    }
+    let reject_unknown = state.config.response.reject_unknown_fields;
+    let fields = match parse_fields(query.fields.as_deref(), reject_unknown) {
+        Ok(f) => f,
+        Err(field) => return unknown_field_response(field, &request_id),
+    };
+    let hashes = match parse_hash(query.hash.as_deref()) {
+        Ok(h) => h,
+        Err(algorithm) => return unsupported_hash_response(algorithm, &request_id),
+    };
+    let output = match parse_output(query.output.as_deref()) {
+        Ok(o) => o,
+        Err(output) => return unsupported_output_response(output, &request_id),
+    };
+    let fields = restrict_to_output(fields, output);
+    let expected = match parse_expected(query.expected.as_deref()) {
+        Ok(e) => e,
+        Err(value) => return invalid_expected_response(value, &request_id),
+    };
+    let options = AnalysisOptions {
+        nested: query.nested,
+        polyglot: query.polyglot,
+        hash: query.store || hashes.contains(&HashAlgorithm::Sha256),
+        hashes,
+        output,
+        raw: query.raw_magic,
+        trace: query.trace,
+        hexdump: query.hexdump,
+        ..AnalysisOptions::default()
+    };
+
+    let start = Instant::now();
+    let result = state
+        .analyze_content_use_case
+        .analyze_upload(request_id.clone(), filename, file, options)
+        .await;
+    match result {
+        Ok(res) => {
+            if let Some(response) = expected_mismatch(state, &res, expected.as_ref(), &request_id) {
+                return response;
+            }
+            state.metrics.analysis_duration.record(
+                start.elapsed().as_secs_f64() * 1000.0,
+                &[KeyValue::new("analysis.type", "upload")],
+            );
+            state.result_sink.publish(&res);
+            let result_url = match res.hash() {
+                Some(content_id) if query.store => {
+                    state.result_store.put(content_id, res.clone());
+                    Some(result_url(state, content_id))
+                }
+                _ => None,
+            };
+            let timing = server_timing(&res, start.elapsed());
+            let mut response = success_response(
+                state,
+                res,
+                result_url,
+                None,
+                expected.as_ref(),
+                query.echo_content_type,
+                fields.as_deref(),
+            );
+            insert_server_timing(&mut response, &timing);
+            response
+        }
+        Err(e) => analysis_error_response(state, &e, &request_id),
     }
 }
@@ -1,2 +1,3 @@
+pub mod admin_handlers;
 pub mod health_handlers;
 pub mod magic_handlers;
@@ -1,17 +1,27 @@
-use crate::domain::value_objects::auth::BasicAuthCredentials;
+use crate::domain::errors::AuthenticationError;
+use crate::domain::value_objects::auth::{ALL_SCOPES, BasicAuthCredentials, Principal};
+use crate::domain::value_objects::request_id::RequestId;
+use crate::presentation::http::responses::error_response::ErrorResponse;
 use crate::presentation::state::app_state::AppState;
 use axum::{
     extract::{Request, State},
     http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
 use base64::{engine::general_purpose, Engine as _};
 use std::sync::Arc;
 
+/// Authenticate the request with Basic credentials or a Bearer token and
+/// attach the resulting [`Principal`] to the request extensions.
+///
+/// Rejected credentials get `401`. When the authentication backend itself
+/// fails (e.g. the introspection endpoint is unreachable) the answer is
+/// `503` with code `auth_unavailable`, so clients keep their credentials.
 pub async fn require_auth(
     State(state): State<Arc<AppState>>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let auth_header = request
@@ -20,29 +30,86 @@ pub async fn require_auth(
         .and_then(|h| h.to_str().ok())
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    if !auth_header.starts_with("Basic ") {
+    let outcome = if let Some(token) = auth_header.strip_prefix("Bearer ") {
+        state.auth_service.authorize(token.trim()).await
+    } else if let Some(credentials) = auth_header.strip_prefix("Basic ") {
+        authenticate_basic(&state, credentials).await
+    } else {
         return Err(StatusCode::UNAUTHORIZED);
-    }
+    };
+    let principal = match outcome {
+        Ok(principal) => principal,
+        Err(AuthenticationError::InvalidCredentials) => return Err(StatusCode::UNAUTHORIZED),
+        Err(AuthenticationError::InternalError(msg)) => {
+            tracing::error!(error = %msg, "Authentication backend failed");
+            return Ok(auth_unavailable_response(&request));
+        }
+    };
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
 
-    let credentials = auth_header.trim_start_matches("Basic ");
+async fn authenticate_basic(
+    state: &AppState,
+    credentials: &str,
+) -> Result<Principal, AuthenticationError> {
     let decoded = general_purpose::STANDARD
         .decode(credentials)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
-    let decoded_str = String::from_utf8(decoded).map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(|_| AuthenticationError::InvalidCredentials)?;
+    let decoded_str =
+        String::from_utf8(decoded).map_err(|_| AuthenticationError::InvalidCredentials)?;
 
     let parts: Vec<&str> = decoded_str.splitn(2, ':').collect();
     if parts.len() != 2 {
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(AuthenticationError::InvalidCredentials);
     }
 
-    let credentials =
-        BasicAuthCredentials::new(parts[0], parts[1]).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let credentials = BasicAuthCredentials::new(parts[0], parts[1])
+        .map_err(|_| AuthenticationError::InvalidCredentials)?;
 
-    state
-        .auth_service
-        .verify_credentials(&credentials)
-        .await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    state.auth_service.verify_credentials(&credentials).await?;
 
-    Ok(next.run(request).await)
+    // The single configured Basic user predates scopes and keeps full access.
+    let scopes = ALL_SCOPES.iter().map(|s| s.to_string()).collect();
+    Ok(Principal::new(credentials.username(), scopes))
+}
+
+fn auth_unavailable_response(request: &Request) -> Response {
+    let request_id = request.extensions().get::<RequestId>();
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse {
+            error: "Authentication service unavailable".to_string(),
+            code: Some("auth_unavailable".to_string()),
+            request_id: request_id.map(|id| id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
+}
+
+/// Reject requests whose authenticated [`Principal`] lacks `scope` with
+/// `403 Forbidden` and code `insufficient_scope`. Must run after
+/// [`require_auth`].
+pub async fn require_scope(scope: &'static str, request: Request, next: Next) -> Response {
+    let allowed = request
+        .extensions()
+        .get::<Principal>()
+        .is_some_and(|p| p.has_scope(scope));
+    if allowed {
+        return next.run(request).await;
+    }
+
+    let request_id = request.extensions().get::<RequestId>();
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse {
+            error: format!("Missing required scope: {}", scope),
+            code: Some("insufficient_scope".to_string()),
+            request_id: request_id.map(|id| id.as_str().to_string()),
+            detail: None,
+        }),
+    )
+        .into_response()
 }
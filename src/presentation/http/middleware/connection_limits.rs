@@ -0,0 +1,82 @@
+use crate::presentation::http::middleware::idle_connections::{
+    ConnectionActivity, TrackedListener,
+};
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    extract::{ConnectInfo, Request, State, connect_info::Connected},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+    serve::IncomingStream,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::TcpListener;
+
+/// Per-connection state, created once when a connection is accepted and
+/// shared by every request served on it.
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    pub remote_addr: SocketAddr,
+    served: Arc<AtomicU64>,
+    activity: Option<Arc<ConnectionActivity>>,
+}
+
+impl ConnectionInfo {
+    pub fn new(remote_addr: SocketAddr) -> Self {
+        Self {
+            remote_addr,
+            served: Arc::new(AtomicU64::new(0)),
+            activity: None,
+        }
+    }
+
+    /// Track this connection's requests for the idle reaper.
+    pub fn with_activity(mut self, activity: Arc<ConnectionActivity>) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
+    pub fn activity(&self) -> Option<&Arc<ConnectionActivity>> {
+        self.activity.as_ref()
+    }
+}
+
+impl Connected<IncomingStream<'_, TcpListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self::new(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, TrackedListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, TrackedListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+/// Enforce `server.max_requests_per_connection` by answering the last
+/// allowed request with `Connection: close`, so the client reconnects (and
+/// re-authenticates, possibly to another instance behind a load balancer).
+///
+/// Requests without [`ConnectionInfo`] (e.g. routers driven directly in
+/// tests) are not counted.
+pub async fn limit_requests_per_connection(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let served = request
+        .extensions()
+        .get::<ConnectInfo<ConnectionInfo>>()
+        .map(|ConnectInfo(c)| c.served.fetch_add(1, Ordering::Relaxed) + 1);
+    let mut response = next.run(request).await;
+    if let (Some(max), Some(served)) = (state.config.server.max_requests_per_connection, served)
+        && served >= max
+    {
+        response
+            .headers_mut()
+            .insert(header::CONNECTION, HeaderValue::from_static("close"));
+    }
+    response
+}
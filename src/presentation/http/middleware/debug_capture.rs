@@ -0,0 +1,89 @@
+use crate::domain::value_objects::request_id::RequestId;
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Response bytes logged per captured request; the rest is elided.
+const MAX_LOGGED_BODY: usize = 64 * 1024;
+
+/// Headers whose values are never logged.
+const REDACTED_HEADERS: &[header::HeaderName] = &[
+    header::AUTHORIZATION,
+    header::PROXY_AUTHORIZATION,
+    header::COOKIE,
+    header::SET_COOKIE,
+];
+
+/// Claim one of the captures armed by `POST /admin/debug-capture`, if any
+/// are left.
+pub fn take_capture(remaining: &AtomicU64) -> bool {
+    remaining
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// Log the request headers and response body of the next requests armed by
+/// `POST /admin/debug-capture` at info level, then stop by itself.
+/// Credentials and cookies are redacted.
+pub async fn capture_for_debugging(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !take_capture(&state.debug_captures_remaining) {
+        return next.run(request).await;
+    }
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.as_str().to_string())
+        .unwrap_or_default();
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    let request_headers = loggable_headers(request.headers());
+
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(request_id, error = %e, "Debug capture could not read the response body");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let logged = &bytes[..bytes.len().min(MAX_LOGGED_BODY)];
+    tracing::info!(
+        request_id,
+        http.method = method,
+        url.full = uri,
+        http.request.headers = request_headers,
+        http.response.status_code = parts.status.as_u16(),
+        http.response.headers = loggable_headers(&parts.headers),
+        http.response.body = %String::from_utf8_lossy(logged),
+        http.response.body_truncated = logged.len() < bytes.len(),
+        "Debug capture"
+    );
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn loggable_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED_HEADERS.contains(name) {
+                "[redacted]"
+            } else {
+                value.to_str().unwrap_or("[binary]")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -1,21 +1,29 @@
 use crate::domain::value_objects::request_id::RequestId;
 use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::http::responses::problem_details::ProblemDetails;
 use axum::{
+    body::Body,
     extract::Request,
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
 
+/// Give every error response a JSON body: an [`ErrorResponse`], or RFC 7807
+/// [`ProblemDetails`] when the client accepts `application/problem+json`.
 pub async fn handle_error(request: Request, next: Next) -> Response {
     let request_id = request.extensions().get::<RequestId>().cloned();
+    let problem_json = ProblemDetails::accepted_by(request.headers());
     let response = next.run(request).await;
 
-    if response.status().is_success() {
+    // 2xx and 3xx (e.g. 304 Not Modified) are not errors and must keep their
+    // empty bodies.
+    if response.status().is_success() || response.status().is_redirection() {
         return response;
     }
 
     let status = response.status();
+    let request_id = request_id.map(|id| id.as_str().to_string());
 
     // If the response is already JSON, we assume it's already formatted correctly.
     // Otherwise, we wrap it in our standard ErrorResponse.
@@ -25,7 +33,22 @@ pub async fn handle_error(request: Request, next: Next) -> Response {
     });
 
     if is_json {
-        return response;
+        if !problem_json {
+            return response;
+        }
+        // Only `ErrorResponse` bodies are rewritten; other JSON errors (such
+        // as a failed readiness report) keep their shape.
+        let (parts, body) = response.into_parts();
+        let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+            return Response::from_parts(parts, Body::empty());
+        };
+        return match serde_json::from_slice::<ErrorResponse>(&bytes) {
+            Ok(mut error) => {
+                error.request_id = error.request_id.or(request_id);
+                ProblemDetails::from_error(status, error).into_response()
+            }
+            Err(_) => Response::from_parts(parts, Body::from(bytes)),
+        };
     }
 
     // Convert non-JSON error response to ErrorResponse
@@ -33,13 +56,16 @@ pub async fn handle_error(request: Request, next: Next) -> Response {
         .canonical_reason()
         .unwrap_or("Unknown error")
         .to_string();
+    let error = ErrorResponse {
+        error: error_message,
+        code: ErrorResponse::code_for_status(status),
+        request_id,
+        detail: None,
+    };
 
-    (
-        status,
-        Json(ErrorResponse {
-            error: error_message,
-            request_id: request_id.map(|id| id.as_str().to_string()),
-        }),
-    )
-        .into_response()
+    if problem_json {
+        ProblemDetails::from_error(status, error).into_response()
+    } else {
+        (status, Json(error)).into_response()
+    }
 }
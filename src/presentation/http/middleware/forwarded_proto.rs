@@ -0,0 +1,68 @@
+use crate::domain::value_objects::request_id::RequestId;
+use crate::presentation::http::middleware::connection_limits::ConnectionInfo;
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::http::responses::problem_details::ProblemDetails;
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    Json,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// Enforce `server.require_forwarded_proto_https`: requests must come from
+/// a trusted proxy that saw them over HTTPS, so credentials are not accepted
+/// from a client that reached the server in plaintext.
+///
+/// A peer is trusted when `server.trusted_proxies` lists it or is empty.
+/// Requests without [`ConnectionInfo`] (e.g. routers driven directly in
+/// tests) only pass with an empty list.
+pub async fn require_https_forwarding(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let server = &state.config.server;
+    if !server.require_forwarded_proto_https {
+        return next.run(request).await;
+    }
+    let trusted_peer = server.trusted_proxies.is_empty()
+        || request
+            .extensions()
+            .get::<ConnectInfo<ConnectionInfo>>()
+            .is_some_and(|ConnectInfo(c)| server.trusted_proxies.contains(&c.remote_addr.ip()));
+    // Of a comma-separated chain, the first entry is the client's hop.
+    let forwarded_https = request
+        .headers()
+        .get(X_FORWARDED_PROTO)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"));
+    if trusted_peer && forwarded_https {
+        return next.run(request).await;
+    }
+
+    let error = ErrorResponse {
+        error: "HTTPS is required".to_string(),
+        code: Some("https_required".to_string()),
+        request_id: request
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.as_str().to_string()),
+        detail: None,
+    };
+    let mut response = if ProblemDetails::accepted_by(request.headers()) {
+        ProblemDetails::from_error(StatusCode::UPGRADE_REQUIRED, error).into_response()
+    } else {
+        (StatusCode::UPGRADE_REQUIRED, Json(error)).into_response()
+    };
+    response.headers_mut().insert(
+        header::UPGRADE,
+        HeaderValue::from_static("TLS/1.2, HTTP/1.1"),
+    );
+    response
+}
@@ -0,0 +1,205 @@
+use crate::presentation::http::middleware::connection_limits::ConnectionInfo;
+use axum::{
+    extract::{ConnectInfo, Request},
+    middleware::Next,
+    response::Response,
+    serve::Listener,
+};
+use socket2::SockRef;
+use std::net::Shutdown;
+use std::os::fd::{AsFd, OwnedFd};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Activity of one accepted connection, shared between its requests (which
+/// record it) and the [`ConnectionTracker`] (which reaps it when idle).
+#[derive(Debug)]
+pub struct ConnectionActivity {
+    /// Duplicate of the connection's socket, used only to shut it down.
+    socket: OwnedFd,
+    epoch: Instant,
+    last_active_ms: AtomicU64,
+    in_flight: AtomicUsize,
+}
+
+impl ConnectionActivity {
+    fn new(socket: OwnedFd) -> Self {
+        Self {
+            socket,
+            epoch: Instant::now(),
+            last_active_ms: AtomicU64::new(0),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    fn touch(&self) {
+        let now = self.epoch.elapsed().as_millis() as u64;
+        self.last_active_ms.store(now, Ordering::Relaxed);
+    }
+
+    /// Time since the last request started or finished on this connection.
+    pub fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_active_ms.load(Ordering::Relaxed));
+        self.epoch.elapsed().saturating_sub(last)
+    }
+
+    /// Whether a request on this connection is still being handled.
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.load(Ordering::Relaxed) > 0
+    }
+
+    /// Mark a request as started until the returned guard is dropped.
+    fn begin_request(self: &Arc<Self>) -> RequestGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.touch();
+        RequestGuard(Arc::clone(self))
+    }
+
+    /// Shut the socket down in both directions. The connection's reader sees
+    /// end-of-stream and hyper drops the connection.
+    fn close(&self) {
+        let _ = SockRef::from(&self.socket).shutdown(Shutdown::Both);
+    }
+}
+
+struct RequestGuard(Arc<ConnectionActivity>);
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Every open connection accepted through a [`TrackedListener`].
+///
+/// Connections are held weakly: once hyper drops a connection its
+/// [`ConnectionInfo`] goes with it and the entry is pruned on the next reap.
+#[derive(Debug, Default)]
+pub struct ConnectionTracker {
+    connections: Mutex<Vec<Weak<ConnectionActivity>>>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn register(&self, stream: &TcpStream) -> Option<Arc<ConnectionActivity>> {
+        let socket = stream.as_fd().try_clone_to_owned().ok()?;
+        let activity = Arc::new(ConnectionActivity::new(socket));
+        activity.touch();
+        self.connections
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&activity));
+        Some(activity)
+    }
+
+    /// Number of connections still open.
+    pub fn open_connections(&self) -> usize {
+        let mut connections = self.connections.lock().unwrap();
+        connections.retain(|c| c.strong_count() > 0);
+        connections.len()
+    }
+
+    /// Close every connection with no request in flight that has been idle
+    /// longer than `max_idle`, returning how many were closed.
+    pub fn reap_idle(&self, max_idle: Duration) -> usize {
+        let mut connections = self.connections.lock().unwrap();
+        let mut reaped = 0;
+        connections.retain(|weak| {
+            let Some(activity) = weak.upgrade() else {
+                return false;
+            };
+            if activity.is_busy() || activity.idle_for() <= max_idle {
+                return true;
+            }
+            activity.close();
+            reaped += 1;
+            false
+        });
+        reaped
+    }
+
+    /// Reap connections idle longer than `max_idle` in the background,
+    /// checking twice per `max_idle`.
+    pub fn spawn_reaper(self: Arc<Self>, max_idle: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(max_idle / 2);
+            loop {
+                interval.tick().await;
+                let reaped = self.reap_idle(max_idle);
+                if reaped > 0 {
+                    tracing::debug!(
+                        connections.reaped = reaped,
+                        "Closed idle connections"
+                    );
+                }
+            }
+        })
+    }
+}
+
+/// [`TcpListener`] that registers every accepted connection with a
+/// [`ConnectionTracker`] and hands its activity to [`ConnectionInfo`].
+pub struct TrackedListener {
+    inner: TcpListener,
+    tracker: Arc<ConnectionTracker>,
+    tcp_nodelay: bool,
+}
+
+impl TrackedListener {
+    pub fn new(inner: TcpListener, tracker: Arc<ConnectionTracker>) -> Self {
+        Self {
+            inner,
+            tracker,
+            tcp_nodelay: false,
+        }
+    }
+
+    /// Set `TCP_NODELAY` on every accepted connection.
+    pub fn with_tcp_nodelay(mut self, tcp_nodelay: bool) -> Self {
+        self.tcp_nodelay = tcp_nodelay;
+        self
+    }
+}
+
+impl Listener for TrackedListener {
+    type Io = TcpStream;
+    type Addr = ConnectionInfo;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, remote_addr) = Listener::accept(&mut self.inner).await;
+        if self.tcp_nodelay
+            && let Err(e) = stream.set_nodelay(true)
+        {
+            tracing::warn!(error = %e, "Failed to set TCP_NODELAY");
+        }
+        let info = match self.tracker.register(&stream) {
+            Some(activity) => ConnectionInfo::new(remote_addr).with_activity(activity),
+            None => ConnectionInfo::new(remote_addr),
+        };
+        (stream, info)
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        self.inner.local_addr().map(ConnectionInfo::new)
+    }
+}
+
+/// Record request activity on the connection, so the idle reaper neither
+/// closes a connection mid-request nor one that was just used.
+///
+/// Requests on connections not accepted through a [`TrackedListener`] are
+/// not tracked.
+pub async fn track_connection_activity(request: Request, next: Next) -> Response {
+    let _guard = request
+        .extensions()
+        .get::<ConnectInfo<ConnectionInfo>>()
+        .and_then(|ConnectInfo(c)| c.activity())
+        .map(|activity| activity.begin_request());
+    next.run(request).await
+}
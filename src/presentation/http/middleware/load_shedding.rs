@@ -0,0 +1,50 @@
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::http::responses::problem_details::ProblemDetails;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Seconds clients are asked to wait before retrying a shed request.
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// `server.overload_policy = "shed"`: serve at most as many requests at once
+/// as `limit` has permits, answering the rest immediately with `503` and
+/// code `overloaded` instead of queueing them.
+///
+/// Installed outside the request id layer, like
+/// [`enforce_request_timeout`](super::request_timeout::enforce_request_timeout),
+/// so the response carries a request id only if the client sent one.
+pub async fn shed_overload(
+    State(limit): State<Arc<Semaphore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Ok(_permit) = limit.try_acquire() else {
+        let error = ErrorResponse {
+            error: "Server is at capacity; retry later".to_string(),
+            code: Some("overloaded".to_string()),
+            request_id: request
+                .headers()
+                .get("x-request-id")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            detail: None,
+        };
+        let mut response = if ProblemDetails::accepted_by(request.headers()) {
+            ProblemDetails::from_error(StatusCode::SERVICE_UNAVAILABLE, error).into_response()
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response()
+        };
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+        return response;
+    };
+    next.run(request).await
+}
@@ -1,3 +1,12 @@
 pub mod auth;
+pub mod connection_limits;
+pub mod debug_capture;
 pub mod error_handler;
+pub mod forwarded_proto;
+pub mod idle_connections;
+pub mod load_shedding;
+pub mod quota;
 pub mod request_id;
+pub mod request_limits;
+pub mod request_timeout;
+pub mod response_envelope;
@@ -0,0 +1,57 @@
+use crate::domain::value_objects::auth::Principal;
+use crate::domain::value_objects::request_id::RequestId;
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Enforce `[auth.quota] daily_limit` for the authenticated [`Principal`].
+///
+/// A user who has used up the day's analyses gets `429 Too Many Requests`
+/// with code `quota_exceeded` and a `Retry-After` of the seconds until the
+/// reset; otherwise every successful (2xx) response counts once. Must run
+/// after [`require_auth`](super::auth::require_auth).
+pub async fn enforce_daily_quota(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let quota = &state.daily_quota;
+    let username = match request.extensions().get::<Principal>() {
+        Some(principal) if quota.is_enabled() => principal.username().to_string(),
+        _ => return next.run(request).await,
+    };
+
+    let now = Utc::now();
+    if quota.is_exhausted(&username, now) {
+        let retry_after = (quota.resets_at(now) - now).num_seconds().max(1);
+        let request_id = request.extensions().get::<RequestId>();
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(ErrorResponse {
+                error: format!(
+                    "Daily quota of {} analyses exceeded",
+                    state.config.auth.quota.daily_limit
+                ),
+                code: Some("quota_exceeded".to_string()),
+                request_id: request_id.map(|id| id.as_str().to_string()),
+                detail: None,
+            }),
+        )
+            .into_response();
+    }
+
+    let response = next.run(request).await;
+    if response.status().is_success() {
+        quota.record(&username, Utc::now());
+    }
+    response
+}
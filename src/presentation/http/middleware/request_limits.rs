@@ -0,0 +1,54 @@
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+/// Enforce `server.limits.max_uri_length`, `server.limits.max_header_size`
+/// and, for a declared `Content-Length`, `server.limits.max_body_size_mb`.
+///
+/// The header size is the sum of all header names and values, which is what
+/// clients control; hyper's read buffer, sized by
+/// [`max_head_size`](crate::presentation::http::server::max_head_size),
+/// remains the hard upper bound.
+/// JSON bodies without a `Content-Length` are bounded by the
+/// `DefaultBodyLimit` layer while they are read.
+pub async fn enforce_request_limits(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let limits = &state.config.server.limits;
+
+    let uri_length = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str().len())
+        .unwrap_or(0);
+    if uri_length > limits.max_uri_length {
+        return Err(StatusCode::URI_TOO_LONG);
+    }
+
+    let header_size: usize = request
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len())
+        .sum();
+    if header_size > limits.max_header_size {
+        return Err(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+    }
+
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > limits.max_body_size_mb * 1024 * 1024) {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    Ok(next.run(request).await)
+}
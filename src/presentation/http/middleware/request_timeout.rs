@@ -0,0 +1,47 @@
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::http::responses::problem_details::ProblemDetails;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::time::Duration;
+
+/// Bound the whole request (authentication, body read, detection and
+/// serialization) by `server.timeouts.request_timeout_secs`, answering `504`
+/// with code `request_timeout_exceeded`.
+///
+/// Installed as the outermost layer, so the request id is only known if the
+/// client sent one. The read and analysis timeouts are shorter and normally
+/// fire first with a more specific error.
+pub async fn enforce_request_timeout(
+    State(limit): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let problem_json = ProblemDetails::accepted_by(request.headers());
+
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            let error = ErrorResponse {
+                error: format!("Request did not complete within {}s", limit.as_secs()),
+                code: Some("request_timeout_exceeded".to_string()),
+                request_id,
+                detail: None,
+            };
+            if problem_json {
+                ProblemDetails::from_error(StatusCode::GATEWAY_TIMEOUT, error).into_response()
+            } else {
+                (StatusCode::GATEWAY_TIMEOUT, Json(error)).into_response()
+            }
+        }
+    }
+}
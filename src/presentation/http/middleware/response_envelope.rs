@@ -0,0 +1,73 @@
+use crate::domain::value_objects::request_id::RequestId;
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use crate::presentation::http::responses::problem_details::ProblemDetails;
+use crate::presentation::state::app_state::AppState;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Wrap JSON bodies as `{ok: true, data}` or `{ok: false, error}` when
+/// `server.response_envelope` is enabled.
+///
+/// Non-JSON error bodies (e.g. the router's plain 404) are replaced by an
+/// [`ErrorResponse`] first so every error shares the envelope. Successful
+/// non-JSON bodies and bodiless responses such as `304` pass through, as do
+/// errors for clients that asked for `application/problem+json`.
+pub async fn wrap_in_envelope(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.config.server.response_envelope {
+        return next.run(request).await;
+    }
+    let request_id = request.extensions().get::<RequestId>().cloned();
+    let problem_json = ProblemDetails::accepted_by(request.headers());
+    let response = next.run(request).await;
+    let status = response.status();
+    let ok = status.is_success();
+    if !ok && (problem_json || (!status.is_client_error() && !status.is_server_error())) {
+        return response;
+    }
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("application/json"));
+    let (mut parts, body) = response.into_parts();
+    let payload = match (is_json, ok) {
+        (true, _) => match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) => serde_json::from_slice::<Value>(&bytes).unwrap_or(Value::Null),
+            Err(_) => Value::Null,
+        },
+        (false, true) => return Response::from_parts(parts, body),
+        (false, false) => json!(ErrorResponse {
+            error: status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string(),
+            code: ErrorResponse::code_for_status(status),
+            request_id: request_id.map(|id| id.as_str().to_string()),
+            detail: None,
+        }),
+    };
+    let envelope = if ok {
+        json!({"ok": true, "data": payload})
+    } else {
+        json!({"ok": false, "error": payload})
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(envelope.to_string()))
+}
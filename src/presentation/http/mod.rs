@@ -2,3 +2,4 @@ pub mod handlers;
 pub mod middleware;
 pub mod responses;
 pub mod router;
+pub mod server;
@@ -1,8 +1,28 @@
-use serde::Serialize;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
+    /// Stable machine-readable error code (e.g. `filename_too_long`), letting
+    /// clients branch on the failure without parsing `error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub request_id: Option<String>,
+    /// What libmagic reported for a failed analysis, verbatim (e.g. an
+    /// offset or rule line), when it reported anything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Code for an error produced outside our handlers (a middleware or an
+    /// axum rejection) that only carries a status.
+    pub fn code_for_status(status: StatusCode) -> Option<String> {
+        match status {
+            StatusCode::PAYLOAD_TOO_LARGE => Some("payload_too_large".to_string()),
+            _ => None,
+        }
+    }
 }
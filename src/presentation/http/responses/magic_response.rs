@@ -1,17 +1,156 @@
 use crate::domain::entities::magic_result::MagicResult;
+use crate::domain::value_objects::mime_type::MimeType;
+use crate::infrastructure::integrations::result_signature::sign_payload;
 use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// `result` fields that can be selected with `?fields=`.
+pub const RESULT_FIELDS: &[&str] = &[
+    "mime_type",
+    "description",
+    "encoding",
+    "requires_transcoding",
+    "category",
+    "polyglot_types",
+    "is_polyglot",
+    "archive_first_entry",
+    "nested",
+    "hash",
+    "hashes",
+    "verified",
+    "aliased",
+    "description_truncated",
+    "raw",
+    "trace",
+    "hexdump",
+    "cached",
+    "stale",
+    "database",
+    "range",
+];
 
 #[derive(Serialize)]
 pub struct MagicResponse {
     pub request_id: String,
     pub filename: String,
+    /// The requested sandbox-relative path, for `/v1/magic/path` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Where the stored result can be fetched, with `?store=true` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_url: Option<String>,
+    /// The analyzed body, with `?echo_content=true` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<EchoedContent>,
+    /// How the detected type compares to `?expected=`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<TypeVerdict>,
     pub result: MagicAnalysisResult,
+    /// HMAC-SHA256 of the rest of the response in canonical form, when
+    /// `integrations.result_signing_key` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TypeVerdict {
+    pub detected: String,
+    pub expected: String,
+    pub matches: bool,
+}
+
+/// Base64 of the analyzed body, gzip-compressed first when `encoding` is
+/// `gzip`.
+#[derive(Serialize)]
+pub struct EchoedContent {
+    pub encoding: &'static str,
+    pub data: String,
 }
 
 #[derive(Serialize)]
 pub struct MagicAnalysisResult {
     pub mime_type: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Present with `encoding`: `true` when the charset is neither UTF-8
+    /// nor ASCII.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires_transcoding: Option<bool>,
+    /// `text` or `binary`, per `analysis.text.textual_subtypes`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Every distinct type the content matches (`?polyglot=true` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polyglot_types: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_polyglot: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_first_entry: Option<ArchiveFirstEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nested: Option<NestedTypeResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Every digest requested with `?hash=`, keyed by algorithm.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<BTreeMap<String, String>>,
+    /// Only present (as `false`) when `mime_type` is the client's declared
+    /// type rather than a detection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// Only present (as `true`) when `mime_type` is
+    /// `analysis.unknown_type_alias` standing in for an inconclusive type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliased: Option<bool>,
+    /// Only present (as `true`) when `description` was cut to
+    /// `analysis.max_description_length`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_truncated: Option<bool>,
+    /// libmagic's unparsed output, with `?raw_magic=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    /// libmagic's debug output while matching, with `?trace=true`. Empty
+    /// when the repository cannot trace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
+    /// `hexdump -C` style dump of the first `analysis.hexdump_bytes` of the
+    /// content, with `?hexdump=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hexdump: Option<String>,
+    /// Only present (as `true`) when the detection came from the content
+    /// hash cache after a matching `If-Content-Hash`, or as a stale fallback.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached: Option<bool>,
+    /// Only present (as `true`) when libmagic was unavailable and a cached
+    /// detection of identical content was served in its place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale: Option<bool>,
+    /// `control` or `candidate` while `[magic.experiment]` is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Bytes analyzed when a `Range` was honoured on `/v1/magic/path`, or
+    /// when only the head of a large upload was analyzed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<AnalyzedRange>,
+}
+
+#[derive(Serialize)]
+pub struct AnalyzedRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Serialize)]
+pub struct NestedTypeResult {
+    pub container: String,
+    pub inner: String,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveFirstEntry {
+    pub name: String,
+    pub mime_type: String,
 }
 
 impl From<MagicResult> for MagicResponse {
@@ -19,10 +158,172 @@ impl From<MagicResult> for MagicResponse {
         Self {
             request_id: result.request_id().as_str().to_string(),
             filename: result.filename().as_str().to_string(),
+            path: result.source_path().map(str::to_string),
+            result_url: None,
+            content: None,
+            verdict: None,
+            signature: None,
             result: MagicAnalysisResult {
                 mime_type: result.mime_type().as_str().to_string(),
                 description: result.description().to_string(),
+                encoding: result.encoding().map(str::to_string),
+                requires_transcoding: result.requires_transcoding(),
+                category: result
+                    .textual()
+                    .map(|textual| if textual { "text" } else { "binary" }.to_string()),
+                polyglot_types: result
+                    .polyglot_types()
+                    .map(|types| types.iter().map(MimeType::as_str).collect()),
+                is_polyglot: result.is_polyglot(),
+                archive_first_entry: result.archive_first_entry().map(|entry| ArchiveFirstEntry {
+                    name: entry.name().to_string(),
+                    mime_type: entry.mime_type().as_str().to_string(),
+                }),
+                nested: result.nested().map(|nested| NestedTypeResult {
+                    container: nested.container().as_str().to_string(),
+                    inner: nested.inner().as_str().to_string(),
+                }),
+                hash: result.hash().map(str::to_string),
+                hashes: result.hashes().cloned(),
+                verified: (!result.is_verified()).then_some(false),
+                aliased: result.is_aliased().then_some(true),
+                description_truncated: result.is_description_truncated().then_some(true),
+                raw: result.raw().map(str::to_string),
+                trace: result.trace().map(str::to_string),
+                hexdump: result.hexdump().map(str::to_string),
+                cached: result.is_cached().then_some(true),
+                stale: result.is_stale().then_some(true),
+                database: result.database_variant().map(str::to_string),
+                range: result
+                    .analyzed_range()
+                    .map(|(start, end)| AnalyzedRange { start, end }),
             },
         }
     }
 }
+
+#[derive(Serialize)]
+pub struct BatchPathResponse {
+    pub request_id: String,
+    pub results: Vec<BatchPathItem>,
+}
+
+/// Outcome for one entry of a batch: either `result` or `error`/`status`.
+#[derive(Serialize)]
+pub struct BatchPathItem {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<MagicAnalysisResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl BatchPathItem {
+    pub fn success(path: String, response: MagicResponse) -> Self {
+        Self {
+            path,
+            filename: Some(response.filename),
+            result: Some(response.result),
+            error: None,
+            status: None,
+        }
+    }
+
+    pub fn failure(path: String, status: u16, error: String) -> Self {
+        Self {
+            path,
+            filename: None,
+            result: None,
+            error: Some(error),
+            status: Some(status),
+        }
+    }
+}
+
+/// Body of `/v1/magic/compare`.
+#[derive(Serialize)]
+pub struct CompareResponse {
+    pub request_id: String,
+    pub a: MagicAnalysisResult,
+    pub b: MagicAnalysisResult,
+    /// Whether both MIME types have the same essence, ignoring parameters.
+    pub same_type: bool,
+}
+
+/// Body of `GET /v1/results/by-path`.
+#[derive(Serialize)]
+pub struct PathHistoryResponse {
+    pub request_id: String,
+    pub path: String,
+    /// Oldest first.
+    pub history: Vec<PathHistoryItem>,
+}
+
+/// One stored analysis of a path.
+#[derive(Serialize)]
+pub struct PathHistoryItem {
+    /// RFC 3339 time the analysis was stored.
+    pub stored_at: String,
+    pub request_id: String,
+    pub mime_type: String,
+    pub description: String,
+    /// Lowercase hex SHA-256 of the content, under which
+    /// `GET /v1/results/{content_id}` serves the full result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+}
+
+/// Body of `/v1/magic/is-text`.
+#[derive(Serialize)]
+pub struct IsTextResponse {
+    pub request_id: String,
+    pub is_text: bool,
+    pub mime_type: String,
+}
+
+#[derive(Serialize)]
+pub struct UploadResponse {
+    pub request_id: String,
+    pub token: String,
+    pub size: u64,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Serialize)]
+pub struct UploadSessionResponse {
+    pub request_id: String,
+    pub session_id: String,
+    pub size: u64,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Serialize)]
+pub struct RuleTestResponse {
+    pub request_id: String,
+    pub result: MagicAnalysisResult,
+}
+
+impl MagicResponse {
+    /// Serialize, keeping only the selected `result` fields. `request_id`
+    /// and `filename` are always included.
+    pub fn to_json_with_fields(&self, fields: &[&str]) -> Value {
+        let mut json = serde_json::to_value(self).unwrap_or(Value::Null);
+        if let Some(Value::Object(result)) = json.get_mut("result") {
+            result.retain(|key, _| fields.contains(&key.as_str()));
+        }
+        json
+    }
+
+    /// Sign the response as it serializes, when `key` is set.
+    pub fn signed(mut self, key: Option<&str>) -> Self {
+        self.signature = None;
+        self.signature = key.map(|key| {
+            sign_payload(key, &serde_json::to_value(&self).unwrap_or(Value::Null))
+        });
+        self
+    }
+}
@@ -1,2 +1,3 @@
 pub mod error_response;
 pub mod magic_response;
+pub mod problem_details;
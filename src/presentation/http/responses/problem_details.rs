@@ -0,0 +1,67 @@
+use crate::presentation::http::responses::error_response::ErrorResponse;
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An error as RFC 7807 `application/problem+json`, for clients that ask for
+/// it with `Accept`.
+#[derive(Serialize)]
+pub struct ProblemDetails {
+    /// `urn:magicer:error:<code>` for errors with a code, else `about:blank`.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// The request id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension member carrying [`ErrorResponse::code`] unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl ProblemDetails {
+    pub fn from_error(status: StatusCode, error: ErrorResponse) -> Self {
+        Self {
+            problem_type: error.code.as_ref().map_or_else(
+                || "about:blank".to_string(),
+                |c| format!("urn:magicer:error:{}", c),
+            ),
+            title: status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string(),
+            status: status.as_u16(),
+            detail: error.error,
+            instance: error.request_id,
+            code: error.code,
+        }
+    }
+
+    /// Whether the client listed `application/problem+json` in `Accept`.
+    pub fn accepted_by(headers: &HeaderMap) -> bool {
+        headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .any(|v| v.contains(PROBLEM_JSON))
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = serde_json::to_string(&self).unwrap_or_default();
+        (
+            status,
+            [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            body,
+        )
+            .into_response()
+    }
+}
@@ -1,27 +1,193 @@
-use crate::presentation::http::handlers::{health_handlers, magic_handlers};
-use crate::presentation::http::middleware::auth;
+use crate::domain::value_objects::auth::{SCOPE_ADMIN, SCOPE_ANALYZE, SCOPE_READ_SANDBOX};
+use crate::presentation::http::handlers::{admin_handlers, health_handlers, magic_handlers};
+use crate::presentation::http::middleware::{
+    auth, connection_limits, debug_capture, forwarded_proto, idle_connections, quota,
+    request_limits, response_envelope,
+};
 use crate::presentation::state::app_state::AppState;
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{get, patch, post},
     Router,
 };
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
 pub fn create_router(state: Arc<AppState>) -> Router {
-    let api_routes = Router::new()
-        .route("/content", post(magic_handlers::analyze_content))
-        .route("/path", post(magic_handlers::analyze_path))
+    let endpoints = &state.config.endpoints;
+    let mut api_routes = Router::new();
+    if endpoints.content_enabled {
+        api_routes = api_routes
+            .route(
+                "/content",
+                post(magic_handlers::analyze_content).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+                )),
+            )
+            .route(
+                "/content/{filename}",
+                post(magic_handlers::analyze_named_content).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+                )),
+            )
+            .route(
+                "/token/{token}",
+                post(magic_handlers::analyze_upload).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+                )),
+            )
+            .route(
+                "/is-text",
+                post(magic_handlers::is_text_content).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+                )),
+            )
+            .route(
+                "/data-uri",
+                post(magic_handlers::analyze_data_uri).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+                )),
+            );
+    }
+    if endpoints.path_enabled {
+        api_routes = api_routes
+            .route(
+                "/path",
+                post(magic_handlers::analyze_path).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_READ_SANDBOX, request, next),
+                )),
+            )
+            .route(
+                "/is-text",
+                get(magic_handlers::is_text_path).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_READ_SANDBOX, request, next),
+                )),
+            )
+            .route(
+                "/compare",
+                post(magic_handlers::compare_paths).route_layer(middleware::from_fn(
+                    |request, next| auth::require_scope(SCOPE_READ_SANDBOX, request, next),
+                )),
+            );
+    }
+    if endpoints.batch_enabled {
+        api_routes = api_routes.route(
+            "/paths",
+            post(magic_handlers::analyze_paths).route_layer(middleware::from_fn(
+                |request, next| auth::require_scope(SCOPE_READ_SANDBOX, request, next),
+            )),
+        );
+    }
+    let api_routes = api_routes
+        .route(
+            "/test",
+            post(magic_handlers::test_rule).route_layer(middleware::from_fn(
+                |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+            )),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            quota::enforce_daily_quota,
+        ))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth::require_auth,
         ))
         .with_state(state.clone());
 
-    Router::new()
-        .route("/v1/ping", get(health_handlers::ping))
+    let result_routes = Router::new()
+        .route(
+            "/by-path",
+            get(magic_handlers::get_path_history).route_layer(middleware::from_fn(
+                |request, next| auth::require_scope(SCOPE_READ_SANDBOX, request, next),
+            )),
+        )
+        .route(
+            "/{content_id}",
+            get(magic_handlers::get_result).route_layer(middleware::from_fn(
+                |request, next| auth::require_scope(SCOPE_ANALYZE, request, next),
+            )),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
+        .with_state(state.clone());
+
+    let upload_routes = Router::new()
+        .route("/v1/upload", post(magic_handlers::upload_content))
+        .route("/v1/session", post(magic_handlers::start_session))
+        .route("/v1/session/{id}", patch(magic_handlers::append_session))
+        .route(
+            "/v1/session/{id}/analyze",
+            post(magic_handlers::analyze_session).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                quota::enforce_daily_quota,
+            )),
+        )
+        .route_layer(middleware::from_fn(|request, next| {
+            auth::require_scope(SCOPE_ANALYZE, request, next)
+        }))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
+        .with_state(state.clone());
+
+    let admin_routes = Router::new()
+        .route("/reload-magic", post(admin_handlers::reload_magic))
+        .route("/check-magic", post(admin_handlers::check_magic))
+        .route("/debug-capture", post(admin_handlers::arm_debug_capture))
+        .route_layer(middleware::from_fn(|request, next| {
+            auth::require_scope(SCOPE_ADMIN, request, next)
+        }))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
+        .with_state(state.clone());
+
+    let mut routes = Router::new()
         .nest("/v1/magic", api_routes)
+        .nest("/v1/results", result_routes);
+    if endpoints.content_enabled {
+        routes = routes.merge(upload_routes);
+    }
+    // Probes and discovery stay reachable without a forwarding proxy.
+    let routes = routes
+        .nest("/admin", admin_routes)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            forwarded_proto::require_https_forwarding,
+        ))
+        .route("/v1/ping", get(health_handlers::ping))
+        .route("/v1/ready", get(health_handlers::ready))
+        .route("/v1/capabilities", get(health_handlers::capabilities));
+    let routes = match &state.config.server.route_prefix {
+        Some(prefix) => Router::new().nest(prefix, routes),
+        None => routes,
+    };
+
+    routes
         .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            request_limits::enforce_request_limits,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            connection_limits::limit_requests_per_connection,
+        ))
+        .layer(middleware::from_fn(
+            idle_connections::track_connection_activity,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            response_envelope::wrap_in_envelope,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            debug_capture::capture_for_debugging,
+        ))
         .with_state(state)
 }
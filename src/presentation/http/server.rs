@@ -0,0 +1,85 @@
+use crate::infrastructure::config::server_config::LimitConfig;
+use crate::presentation::http::middleware::connection_limits::ConnectionInfo;
+use axum::{
+    Router,
+    extract::{ConnectInfo, Request},
+    serve::Listener,
+};
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use std::pin::pin;
+use tower::ServiceExt;
+
+/// Room for the request line, header separators and HTTP/2's 32-byte
+/// per-field overhead on top of the configured limits.
+const HEAD_OVERHEAD_BYTES: usize = 4096;
+
+/// Smallest read buffer hyper's HTTP/1 parser accepts.
+const MIN_HTTP1_BUF_SIZE: usize = 8192;
+
+/// Bytes of request head (URI plus headers) hyper buffers before
+/// rejecting a request with `431 Request Header Fields Too Large`.
+///
+/// The URI is included so `server.limits.max_uri_length` is still answered
+/// with `414 URI Too Long` by `enforce_request_limits`.
+pub fn max_head_size(limits: &LimitConfig) -> usize {
+    limits
+        .max_header_size
+        .saturating_add(limits.max_uri_length)
+        .saturating_add(HEAD_OVERHEAD_BYTES)
+}
+
+/// Serve `app` on `listener` until `shutdown` completes, then wait for open
+/// connections to finish their in-flight requests.
+///
+/// Connections are served by hyper directly rather than `axum::serve`, so
+/// the HTTP/1 read buffer and the HTTP/2 header list size are bounded by
+/// [`max_head_size`] while the request head is parsed.
+pub async fn serve<L>(
+    mut listener: L,
+    app: Router,
+    limits: &LimitConfig,
+    shutdown: impl Future<Output = ()>,
+) where
+    L: Listener<Addr = ConnectionInfo>,
+{
+    let head_size = max_head_size(limits);
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .max_buf_size(head_size.max(MIN_HTTP1_BUF_SIZE));
+    builder
+        .http2()
+        .max_header_list_size(u32::try_from(head_size).unwrap_or(u32::MAX));
+
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = pin!(shutdown);
+    loop {
+        let (io, info) = tokio::select! {
+            conn = listener.accept() => conn,
+            _ = &mut shutdown => break,
+        };
+        let service = app
+            .clone()
+            .map_request(move |mut request: Request<Incoming>| {
+                request.extensions_mut().insert(ConnectInfo(info.clone()));
+                request
+            });
+        let conn = builder
+            .serve_connection(TokioIo::new(io), TowerToHyperService::new(service))
+            .into_owned();
+        let conn = graceful.watch(conn);
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::debug!(error = %e, "Connection closed with error");
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+}
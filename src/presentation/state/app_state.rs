@@ -1,22 +1,58 @@
+use crate::application::analysis_sampler::AnalysisSampler;
+use crate::application::daily_quota::DailyQuota;
+use crate::application::memory_budget::MemoryBudget;
 use crate::application::use_cases::analyze_content::AnalyzeContentUseCase;
 use crate::application::use_cases::analyze_path::AnalyzePathUseCase;
 use crate::application::use_cases::health_check::HealthCheckUseCase;
+use crate::application::use_cases::readiness_check::ReadinessCheckUseCase;
+use crate::application::use_cases::test_magic_rule::TestMagicRuleUseCase;
 use crate::domain::repositories::magic_repository::MagicRepository;
 use crate::domain::services::authentication_service::AuthenticationService;
+use crate::domain::services::magic_reloader::MagicReloader;
+use crate::domain::services::result_sink::{NoopResultSink, ResultSink};
+use crate::domain::services::result_store::ResultStore;
 use crate::domain::services::sandbox_service::SandboxService;
 use crate::domain::services::temp_storage::TempStorageService;
+use crate::domain::services::upload_session_store::UploadSessionStore;
+use crate::domain::services::upload_store::UploadStore;
+use crate::domain::value_objects::mime_type::MimeType;
 use crate::infrastructure::config::server_config::ServerConfig;
+use crate::infrastructure::results::in_memory_result_store::InMemoryResultStore;
 use crate::infrastructure::telemetry::metrics::AppMetrics;
+use crate::infrastructure::uploads::in_memory_session_store::InMemoryUploadSessionStore;
+use crate::infrastructure::uploads::in_memory_upload_store::InMemoryUploadStore;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::time::Duration;
 
 pub struct AppState {
     pub analyze_content_use_case: AnalyzeContentUseCase,
     pub analyze_path_use_case: AnalyzePathUseCase,
     pub health_check_use_case: HealthCheckUseCase,
+    pub readiness_check_use_case: ReadinessCheckUseCase,
+    pub test_magic_rule_use_case: TestMagicRuleUseCase,
     pub auth_service: Arc<dyn AuthenticationService>,
+    /// Receives every successful analysis result.
+    pub result_sink: Arc<dyn ResultSink>,
+    /// Results kept with `?store=true`, served by `GET /v1/results/{id}`.
+    pub result_store: Arc<dyn ResultStore>,
+    /// Content kept by `POST /v1/upload`, analyzed by token.
+    pub upload_store: Arc<dyn UploadStore>,
+    /// Content assembled chunk by chunk under `/v1/session`.
+    pub session_store: Arc<dyn UploadSessionStore>,
+    /// Bytes buffered by in-memory content analyses in flight.
+    pub memory_budget: Arc<MemoryBudget>,
+    /// Swaps in a freshly loaded magic database; `None` when the repository
+    /// cannot be reloaded.
+    pub magic_reloader: Option<Arc<dyn MagicReloader>>,
     pub config: Arc<ServerConfig>,
     /// Shared OTel metric instruments for all request handlers.
     pub metrics: Arc<AppMetrics>,
+    /// Requests still to be logged in full, as armed by
+    /// `POST /admin/debug-capture`.
+    pub debug_captures_remaining: AtomicU64,
+    /// Successful analyses per user today, against `[auth.quota]`.
+    pub daily_quota: DailyQuota,
 }
 
 impl AppState {
@@ -29,17 +65,79 @@ impl AppState {
         metrics: Arc<AppMetrics>,
     ) -> Self {
         let timeout = config.server.timeouts.analysis_timeout_secs;
+        let sampler = Arc::new(AnalysisSampler::new(config.logging.sample_rate));
         Self {
             analyze_content_use_case: AnalyzeContentUseCase::new(
                 magic_repo.clone(),
-                temp_storage,
+                temp_storage.clone(),
                 config.clone(),
+            )
+            .with_sampler(sampler.clone()),
+            test_magic_rule_use_case: TestMagicRuleUseCase::new(
+                magic_repo.clone(),
+                temp_storage.clone(),
             ),
-            analyze_path_use_case: AnalyzePathUseCase::new(magic_repo, sandbox, timeout),
+            analyze_path_use_case: AnalyzePathUseCase::new(magic_repo, sandbox, timeout)
+                .with_allow_empty(config.analysis.allow_empty)
+                .with_max_description_length(config.analysis.max_description_length)
+                .with_description_redaction(config.analysis.redact_description_metadata)
+                .with_mmap_fallback(config.analysis.mmap_fallback_enabled)
+                .with_use_mmap(config.analysis.use_mmap)
+                .with_retry_on_truncation(config.analysis.retry_on_truncation)
+                .with_text_config(config.analysis.text.clone())
+                .with_reject_descriptions(config.magic.reject_descriptions.clone())
+                .with_unknown_type_alias(
+                    config
+                        .analysis
+                        .unknown_type_alias
+                        .as_deref()
+                        .and_then(|alias| MimeType::try_from(alias).ok()),
+                )
+                .with_sampler(sampler),
             health_check_use_case: HealthCheckUseCase::new(),
+            readiness_check_use_case: ReadinessCheckUseCase::new(temp_storage)
+                .with_disk_check(config.clone()),
             auth_service,
+            result_sink: Arc::new(NoopResultSink),
+            result_store: Arc::new(
+                InMemoryResultStore::new(
+                    config.results.max_entries,
+                    Duration::from_secs(config.results.ttl_secs),
+                )
+                .with_max_history_per_path(config.results.max_history_per_path),
+            ),
+            upload_store: Arc::new(InMemoryUploadStore::new(
+                config.uploads.max_entries,
+                Duration::from_secs(config.uploads.ttl_secs),
+            )),
+            session_store: Arc::new(InMemoryUploadSessionStore::new(
+                config.sessions.max_entries,
+                Duration::from_secs(config.sessions.ttl_secs),
+            )),
+            memory_budget: Arc::new(MemoryBudget::new(config.analysis.max_total_memory_bytes)),
+            daily_quota: DailyQuota::new(
+                config.auth.quota.daily_limit,
+                config.auth.quota.reset_hour_utc,
+            ),
+            magic_reloader: None,
             config,
             metrics,
+            debug_captures_remaining: AtomicU64::new(0),
         }
     }
+
+    pub fn with_result_sink(mut self, result_sink: Arc<dyn ResultSink>) -> Self {
+        self.result_sink = result_sink;
+        self
+    }
+
+    pub fn with_result_store(mut self, result_store: Arc<dyn ResultStore>) -> Self {
+        self.result_store = result_store;
+        self
+    }
+
+    pub fn with_magic_reloader(mut self, reloader: Arc<dyn MagicReloader>) -> Self {
+        self.magic_reloader = Some(reloader);
+        self
+    }
 }
@@ -3,3 +3,9 @@ pub mod fake_temp_storage;
 
 #[path = "e2e/full_workflow_tests.rs"]
 pub mod full_workflow_tests;
+
+#[path = "e2e/connection_limit_tests.rs"]
+pub mod connection_limit_tests;
+
+#[path = "e2e/cli_tests.rs"]
+pub mod cli_tests;
@@ -0,0 +1,85 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn magicer() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_magicer"));
+    // Keep a config file in the environment from changing the detection.
+    command.env_remove("MAGICER_CONFIG_PATH");
+    command
+}
+
+#[test]
+fn test_analyze_file_prints_mime_type() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.pdf");
+    std::fs::write(&path, b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n").unwrap();
+
+    let output = magicer()
+        .args(["analyze", "--file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("application/pdf\t"), "{}", stdout);
+    assert!(stdout.contains("PDF document"), "{}", stdout);
+}
+
+#[test]
+fn test_analyze_reads_stdin() {
+    let mut child = magicer()
+        .arg("analyze")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"%PDF-1.4\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("application/pdf\t"));
+}
+
+#[test]
+fn test_analyze_missing_file_fails() {
+    let output = magicer()
+        .args(["analyze", "--file", "/nonexistent/magicer-cli-test"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_check_database_reports_valid_and_broken() {
+    let dir = tempfile::tempdir().unwrap();
+    let valid = dir.path().join("hello.magic");
+    std::fs::write(&valid, "0\tstring\tHELLO\tHello file\n!:mime\ttext/x-hello\n").unwrap();
+    let broken = dir.path().join("broken.magic");
+    std::fs::write(&broken, "0\tbogustype\tHELLO\tbroken\n").unwrap();
+
+    let output = magicer()
+        .args(["check-database", "--database"])
+        .arg(&valid)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "ok");
+
+    let output = magicer()
+        .args(["check-database", "--database"])
+        .arg(&broken)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("bogustype"), "{}", stderr);
+}
@@ -0,0 +1,274 @@
+use axum::middleware;
+use magicer::infrastructure::auth::basic_auth_service::BasicAuthService;
+use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::filesystem::sandbox::PathSandbox;
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::presentation::http::middleware::connection_limits::ConnectionInfo;
+use magicer::presentation::http::middleware::idle_connections::{
+    ConnectionTracker, TrackedListener,
+};
+use magicer::presentation::http::middleware::{error_handler, request_id};
+use magicer::presentation::http::router::create_router;
+use magicer::presentation::http::server;
+use magicer::presentation::state::app_state::AppState;
+use crate::fake_temp_storage::FakeTempStorageService;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+fn test_app(config: ServerConfig) -> axum::Router {
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(BasicAuthService::new("admin", "secret")),
+        Arc::new(config),
+        Arc::new(magicer::infrastructure::telemetry::metrics::AppMetrics::new(
+            &opentelemetry::global::meter("test"),
+        )),
+    ));
+    create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+/// Serve the router on an ephemeral port with per-connection info.
+async fn spawn_server(config: ServerConfig) -> std::net::SocketAddr {
+    let app = test_app(config);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<ConnectionInfo>(),
+        )
+        .await
+        .unwrap();
+    });
+    addr
+}
+
+/// Serve the router the way `main` does, tracking connections so idle ones
+/// can be reaped.
+async fn spawn_tracked_server(config: ServerConfig) -> (std::net::SocketAddr, Arc<ConnectionTracker>) {
+    let limits = config.server.limits.clone();
+    let app = test_app(config);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tracker = Arc::new(ConnectionTracker::new());
+    let listener = TrackedListener::new(listener, Arc::clone(&tracker));
+    tokio::spawn(async move {
+        server::serve(listener, app, &limits, std::future::pending()).await;
+    });
+    (addr, tracker)
+}
+
+/// Read one HTTP/1.1 response, returning its lowercased header lines.
+async fn read_response(reader: &mut BufReader<TcpStream>) -> Vec<String> {
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        let line = line.trim_end().to_ascii_lowercase();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("content-length:") {
+            content_length = len.trim().parse().unwrap();
+        }
+        headers.push(line);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.unwrap();
+    headers
+}
+
+#[tokio::test]
+async fn test_connection_closed_after_max_requests() {
+    let mut config = ServerConfig::default();
+    config.server.max_requests_per_connection = Some(3);
+    let addr = spawn_server(config).await;
+
+    let mut reader = BufReader::new(TcpStream::connect(addr).await.unwrap());
+    for i in 1..=3 {
+        reader
+            .get_mut()
+            .write_all(b"GET /v1/ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let headers = read_response(&mut reader).await;
+        assert!(headers[0].starts_with("http/1.1 200"), "{:?}", headers);
+        let closes = headers.iter().any(|h| h == "connection: close");
+        assert_eq!(closes, i == 3, "request {}: {:?}", i, headers);
+    }
+
+    // The server hangs up after the last allowed response.
+    reader
+        .get_mut()
+        .write_all(b"GET /v1/ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .ok();
+    let mut rest = Vec::new();
+    let read = reader.read_to_end(&mut rest).await.unwrap_or(0);
+    assert_eq!(read, 0);
+}
+
+#[tokio::test]
+async fn test_connection_kept_alive_without_limit() {
+    let addr = spawn_server(ServerConfig::default()).await;
+
+    let mut reader = BufReader::new(TcpStream::connect(addr).await.unwrap());
+    for _ in 0..5 {
+        reader
+            .get_mut()
+            .write_all(b"GET /v1/ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let headers = read_response(&mut reader).await;
+        assert!(!headers.iter().any(|h| h == "connection: close"));
+    }
+}
+
+#[tokio::test]
+async fn test_idle_connection_reaped() {
+    let (addr, tracker) = spawn_tracked_server(ServerConfig::default()).await;
+    tracker.clone().spawn_reaper(Duration::from_millis(200));
+
+    let mut reader = BufReader::new(TcpStream::connect(addr).await.unwrap());
+    reader
+        .get_mut()
+        .write_all(b"GET /v1/ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let headers = read_response(&mut reader).await;
+    assert!(headers[0].starts_with("http/1.1 200"), "{:?}", headers);
+    assert_eq!(tracker.open_connections(), 1);
+
+    // Stay silent past the idle limit: the server hangs up on its own.
+    let mut rest = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(5), reader.read_to_end(&mut rest))
+        .await
+        .expect("idle connection was not reaped")
+        .unwrap_or(0);
+    assert_eq!(read, 0);
+    assert_eq!(tracker.open_connections(), 0);
+}
+
+#[tokio::test]
+async fn test_active_connection_not_reaped() {
+    let (addr, tracker) = spawn_tracked_server(ServerConfig::default()).await;
+
+    let mut reader = BufReader::new(TcpStream::connect(addr).await.unwrap());
+    for _ in 0..3 {
+        reader
+            .get_mut()
+            .write_all(b"GET /v1/ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let headers = read_response(&mut reader).await;
+        assert!(headers[0].starts_with("http/1.1 200"), "{:?}", headers);
+        assert_eq!(tracker.reap_idle(Duration::from_secs(60)), 0);
+    }
+    assert_eq!(tracker.open_connections(), 1);
+}
+
+#[tokio::test]
+async fn test_oversized_header_rejected_by_hyper() {
+    let mut config = ServerConfig::default();
+    config.server.limits.max_header_size = 1024;
+    config.server.limits.max_uri_length = 1024;
+    let (addr, _tracker) = spawn_tracked_server(config).await;
+
+    let request = format!(
+        "GET /v1/ping HTTP/1.1\r\nHost: localhost\r\nX-Filler: {}\r\n\r\n",
+        "a".repeat(64 * 1024)
+    );
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.ok();
+    let mut reader = BufReader::new(stream);
+    let headers = read_response(&mut reader).await;
+
+    // Rejected while parsing, before the router assigns a request ID.
+    assert!(headers[0].starts_with("http/1.1 431"), "{:?}", headers);
+    assert!(!headers.iter().any(|h| h.starts_with("x-request-id:")));
+}
+
+#[tokio::test]
+async fn test_tracked_listener_sets_tcp_nodelay() {
+    use axum::serve::Listener;
+
+    for tcp_nodelay in [false, true] {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut listener = TrackedListener::new(listener, Arc::new(ConnectionTracker::new()))
+            .with_tcp_nodelay(tcp_nodelay);
+
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await;
+        assert_eq!(stream.nodelay().unwrap(), tcp_nodelay);
+    }
+}
+
+#[tokio::test]
+async fn test_overload_shed_rejects_requests_beyond_capacity() {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use magicer::presentation::http::middleware::load_shedding::shed_overload;
+    use tokio::sync::Semaphore;
+    use tower::ServiceExt;
+
+    let state = Arc::new(AppState::new(
+        Arc::new(
+            FakeMagicRepository::builder()
+                .with_defaults()
+                .with_delay(Duration::from_millis(800))
+                .build(),
+        ),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(BasicAuthService::new("admin", "secret")),
+        Arc::new(ServerConfig::default()),
+        Arc::new(magicer::infrastructure::telemetry::metrics::AppMetrics::new(
+            &opentelemetry::global::meter("test"),
+        )),
+    ));
+    let app = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(Semaphore::new(2)),
+            shed_overload,
+        ));
+    let analyze = |app: axum::Router| async move {
+        app.oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    };
+
+    let slow: Vec<_> = (0..2).map(|_| tokio::spawn(analyze(app.clone()))).collect();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let started = std::time::Instant::now();
+    let shed = analyze(app.clone()).await;
+    assert!(started.elapsed() < Duration::from_millis(300), "request was queued");
+    assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(shed.headers()["retry-after"], "1");
+    let body = axum::body::to_bytes(shed.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "overloaded");
+
+    for handle in slow {
+        assert_eq!(handle.await.unwrap().status(), StatusCode::OK);
+    }
+    assert_eq!(analyze(app).await.status(), StatusCode::OK);
+}
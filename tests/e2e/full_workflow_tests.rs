@@ -16,7 +16,9 @@ const TEST_SANDBOX_DIR: &str = "/tmp/magicer_e2e";
 
 use uuid::Uuid;
 
-fn setup_test_server(config_override: Option<Box<dyn FnOnce(&mut ServerConfig)>>) -> (TestServer, PathBuf) {
+type ConfigOverride = Box<dyn FnOnce(&mut ServerConfig)>;
+
+fn setup_test_server(config_override: Option<ConfigOverride>) -> (TestServer, PathBuf) {
     let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
     let unique_id = Uuid::new_v4();
     let test_dir = PathBuf::from(format!("{}/{}", TEST_SANDBOX_DIR, unique_id));
@@ -158,3 +160,54 @@ async fn test_path_traversal_rejection() {
     // RelativePath::new rejects '..' so this will be a 400 Bad Request
     response.assert_status_bad_request();
 }
+
+#[tokio::test]
+async fn test_uri_over_limit_rejected() {
+    let (server, _) = setup_test_server(Some(Box::new(|config| {
+        config.server.limits.max_uri_length = 64;
+    })));
+    let response = server
+        .post("/v1/magic/path")
+        .add_query_param("filename", "a".repeat(100))
+        .add_query_param("path", "test.pdf")
+        .add_header(header::AUTHORIZATION, HeaderValue::from_static("Basic YWRtaW46c2VjcmV0"))
+        .await;
+
+    response.assert_status(axum::http::StatusCode::URI_TOO_LONG);
+    let json = response.json::<serde_json::Value>();
+    assert!(json.get("request_id").is_some());
+}
+
+#[tokio::test]
+async fn test_body_over_limit_rejected_as_json() {
+    let (server, _) = setup_test_server(Some(Box::new(|config| {
+        config.server.limits.max_body_size_mb = 1;
+    })));
+    let body = vec![0u8; 1024 * 1024 + 1];
+    let response = server
+        .post("/v1/magic/content")
+        .add_query_param("filename", "big.bin")
+        .add_header(header::AUTHORIZATION, HeaderValue::from_static("Basic YWRtaW46c2VjcmV0"))
+        .add_header(header::CONTENT_LENGTH, HeaderValue::from(body.len()))
+        .bytes(body.into())
+        .await;
+
+    response.assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+    let json = response.json::<serde_json::Value>();
+    assert_eq!(json["code"], "payload_too_large");
+    assert!(json["error"].is_string());
+    assert!(json.get("request_id").is_some());
+}
+
+#[tokio::test]
+async fn test_headers_over_limit_rejected() {
+    let (server, _) = setup_test_server(Some(Box::new(|config| {
+        config.server.limits.max_header_size = 1024;
+    })));
+    let response = server
+        .get("/v1/ping")
+        .add_header("x-padding", HeaderValue::from_str(&"a".repeat(2048)).unwrap())
+        .await;
+
+    response.assert_status(axum::http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use std::path::Path;
 use futures_util::future::BoxFuture;
-use magicer::application::use_cases::analyze_content::AnalyzeContentUseCase;
+use magicer::application::use_cases::analyze_content::{AnalysisOptions, AnalyzeContentUseCase};
 use magicer::domain::repositories::magic_repository::MagicRepository;
 use magicer::domain::value_objects::request_id::RequestId;
 use magicer::domain::value_objects::filename::WindowsCompatibleFilename;
@@ -68,11 +68,183 @@ async fn test_analyze_to_temp_file_success() {
     let data = b"%PDF-1.4";
     let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
     
-    let result = use_case.analyze_to_temp_file(request_id, filename, stream).await.unwrap();
+    let result = use_case.analyze_to_temp_file(request_id, filename, stream, AnalysisOptions::default()).await.unwrap();
     
     assert_eq!(result.mime_type().as_str(), "application/pdf");
 }
 
+/// Temp file without a usable path: analysis only succeeds if it reads
+/// through the descriptor from `read_handle` instead of reopening `path()`.
+struct DescriptorOnlyTempFile {
+    file: std::fs::File,
+    read_handles: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait]
+impl TemporaryFile for DescriptorOnlyTempFile {
+    async fn write(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        use std::io::Write;
+        self.file.write_all(data)
+    }
+
+    async fn sync(&mut self) -> Result<(), std::io::Error> {
+        self.file.sync_all()
+    }
+
+    async fn read_handle(&mut self) -> Result<std::fs::File, std::io::Error> {
+        self.read_handles.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.file.try_clone()
+    }
+
+    fn path(&self) -> &Path {
+        Path::new("/nonexistent/magicer-temp-file")
+    }
+}
+
+struct DescriptorOnlyTempStorage {
+    read_handles: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait]
+impl TempStorageService for DescriptorOnlyTempStorage {
+    async fn create_temp_file(&self) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        Ok(Box::new(DescriptorOnlyTempFile {
+            file: tempfile::tempfile()?,
+            read_handles: self.read_handles.clone(),
+        }))
+    }
+}
+
+/// Counts how content reaches the repository: as a buffer, or as the open
+/// file that libmagic reads itself.
+#[derive(Default)]
+struct FileSpyRepo {
+    buffers: std::sync::atomic::AtomicUsize,
+    files: std::sync::atomic::AtomicUsize,
+}
+
+impl MagicRepository for FileSpyRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.buffers.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Box::pin(async {
+            Ok((MimeType::try_from("application/pdf").unwrap(), "PDF document".to_string()))
+        })
+    }
+
+    fn analyze_file<'a>(&'a self, _file: &'a std::fs::File, _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.files.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Box::pin(async {
+            Ok((MimeType::try_from("application/pdf").unwrap(), "PDF document".to_string()))
+        })
+    }
+}
+
+fn failing_mmap(_file: &std::fs::File) -> Result<magicer::infrastructure::filesystem::mmap::MmapHandler, magicer::infrastructure::errors::InfrastructureError> {
+    Err(magicer::infrastructure::errors::InfrastructureError::Io(std::io::Error::other("mmap not expected")))
+}
+
+#[tokio::test]
+async fn test_analyze_to_temp_file_reads_through_open_descriptor() {
+    let read_handles = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let spy = Arc::new(FileSpyRepo::default());
+    let repo: Arc<dyn MagicRepository> = spy.clone();
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(DescriptorOnlyTempStorage {
+        read_handles: read_handles.clone(),
+    });
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config).with_mmap(failing_mmap);
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"%PDF-1.4"))]);
+
+    let result = use_case
+        .analyze_to_temp_file(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("test.pdf").unwrap(),
+            stream,
+            AnalysisOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    assert_eq!(read_handles.load(std::sync::atomic::Ordering::SeqCst), 1);
+    // libmagic got the descriptor; the content was neither mapped nor read
+    // back into a buffer.
+    assert_eq!(spy.files.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(spy.buffers.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_analyze_to_temp_file_maps_only_for_buffer_options() {
+    let spy = Arc::new(FileSpyRepo::default());
+    let repo: Arc<dyn MagicRepository> = spy.clone();
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, Arc::new(FakeTempStorage), config);
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"%PDF-1.4"))]);
+
+    let result = use_case
+        .analyze_to_temp_file(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("test.pdf").unwrap(),
+            stream,
+            AnalysisOptions {
+                polyglot: true,
+                ..AnalysisOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    assert_eq!(spy.files.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+/// Records the size hint each temp file was requested with.
+struct SizeHintSpyStorage {
+    hints: std::sync::Mutex<Vec<Option<u64>>>,
+}
+
+#[async_trait]
+impl TempStorageService for SizeHintSpyStorage {
+    async fn create_temp_file(&self) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.create_temp_file_sized(None).await
+    }
+
+    async fn create_temp_file_sized(
+        &self,
+        size_hint: Option<u64>,
+    ) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.hints.lock().unwrap().push(size_hint);
+        FakeTempStorage.create_temp_file().await
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_to_temp_file_passes_declared_size_to_storage() {
+    let spy = Arc::new(SizeHintSpyStorage {
+        hints: std::sync::Mutex::new(Vec::new()),
+    });
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, spy.clone(), config);
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"%PDF-1.4"))]);
+    let options = AnalysisOptions {
+        size_hint: Some(8),
+        ..AnalysisOptions::default()
+    };
+
+    use_case
+        .analyze_to_temp_file(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("test.pdf").unwrap(),
+            stream,
+            options,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(*spy.hints.lock().unwrap(), vec![Some(8)]);
+}
+
 #[tokio::test]
 async fn test_analyze_in_memory_success() {
     let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
@@ -85,7 +257,7 @@ async fn test_analyze_in_memory_success() {
     let data = b"%PDF-1.4";
     let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
     
-    let result = use_case.analyze_in_memory(request_id, filename, stream).await.unwrap();
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await.unwrap();
     
     assert_eq!(result.mime_type().as_str(), "application/pdf");
 }
@@ -101,7 +273,7 @@ async fn test_analyze_content_success() {
     let data = b"%PDF-1.4";
     let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
     
-    let result = use_case.analyze_in_memory(request_id, filename, stream).await.unwrap();
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await.unwrap();
     
     assert_eq!(result.mime_type().as_str(), "application/pdf");
     assert_eq!(result.description(), "PDF document");
@@ -117,7 +289,7 @@ async fn test_analyze_content_empty_rejected() {
     let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
     let stream = futures_util::stream::iter(std::iter::empty::<Result<bytes::Bytes, std::io::Error>>());
     
-    let result = use_case.analyze_in_memory(request_id, filename, stream).await;
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     assert!(matches!(err, ApplicationError::BadRequest(_)));
@@ -141,7 +313,7 @@ async fn test_analyze_content_repository_failure() {
     let data = b"some data";
     let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
     
-    let result = use_case.analyze_in_memory(request_id, filename, stream).await;
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     assert!(err.to_string().contains("Analysis failed: forced failure"));
@@ -170,8 +342,599 @@ async fn test_analyze_content_timeout() {
     let data = b"some data";
     let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
     
-    let result = use_case.analyze_in_memory(request_id, filename, stream).await;
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     assert_eq!(err.status_code(), axum::http::StatusCode::GATEWAY_TIMEOUT);
 }
+
+#[tokio::test]
+async fn test_analyze_content_slow_stream_exceeds_pipeline_deadline() {
+    use futures_util::StreamExt;
+
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.server.timeouts.analysis_timeout_secs = 1;
+    let config = Arc::new(config_val);
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config);
+    let request_id = RequestId::generate();
+    let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
+
+    // Each chunk arrives well within the deadline, but the whole body does not.
+    let stream = Box::pin(
+        futures_util::stream::iter(vec![&b"%PDF"[..], &b"-1.4"[..]]).then(|chunk| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(700)).await;
+            Ok::<_, std::io::Error>(bytes::Bytes::from_static(chunk))
+        }),
+    );
+
+    let result = use_case.analyze_to_temp_file(request_id, filename, stream, AnalysisOptions::default()).await;
+    let err = result.unwrap_err();
+    assert!(matches!(err, ApplicationError::Timeout(1)));
+    assert_eq!(err.status_code(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+}
+
+#[tokio::test]
+async fn test_analyze_content_stalled_stream_hits_idle_timeout() {
+    use futures_util::StreamExt;
+
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.server.timeouts.stream_idle_secs = 1;
+    let config = Arc::new(config_val);
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config);
+    let request_id = RequestId::generate();
+    let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
+
+    // First chunk arrives immediately, then the client goes quiet.
+    let stream = Box::pin(
+        futures_util::stream::iter(vec![(0, &b"%PDF"[..]), (1500, &b"-1.4"[..])]).then(|(delay_ms, chunk)| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+            Ok::<_, std::io::Error>(bytes::Bytes::from_static(chunk))
+        }),
+    );
+
+    let result = use_case.analyze_in_memory(request_id, filename, stream, AnalysisOptions::default()).await;
+    let err = result.unwrap_err();
+    assert!(matches!(err, ApplicationError::RequestTimeout));
+    assert_eq!(err.status_code(), axum::http::StatusCode::REQUEST_TIMEOUT);
+}
+
+fn peek_enabled_use_case() -> AnalyzeContentUseCase {
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+    );
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.analysis.peek_archives = true;
+    AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config_val))
+}
+
+#[tokio::test]
+async fn test_analyze_content_peeks_first_zip_entry() {
+    let use_case = peek_enabled_use_case();
+    let data = crate::infrastructure::magic::archive_peek_tests::build_zip("report.pdf", 0, b"%PDF-1.4\n");
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from(data))]);
+
+    let result = use_case
+        .analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("a.zip").unwrap(), stream, AnalysisOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/zip");
+    let entry = result.archive_first_entry().unwrap();
+    assert_eq!(entry.name(), "report.pdf");
+    assert_eq!(entry.mime_type().as_str(), "application/pdf");
+}
+
+#[tokio::test]
+async fn test_analyze_content_peeks_first_tar_entry() {
+    let use_case = peek_enabled_use_case();
+    let data = crate::infrastructure::magic::archive_peek_tests::build_tar("logo.png", &[0x89, 0x50, 0x4E, 0x47]);
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from(data))]);
+
+    let result = use_case
+        .analyze_to_temp_file(RequestId::generate(), WindowsCompatibleFilename::new("a.tar").unwrap(), stream, AnalysisOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/x-tar");
+    let entry = result.archive_first_entry().unwrap();
+    assert_eq!(entry.name(), "logo.png");
+    assert_eq!(entry.mime_type().as_str(), "image/png");
+}
+
+#[tokio::test]
+async fn test_analyze_content_archive_peek_disabled_by_default() {
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+    );
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config);
+    let data = crate::infrastructure::magic::archive_peek_tests::build_zip("report.pdf", 0, b"%PDF-1.4\n");
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from(data))]);
+
+    let result = use_case
+        .analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("a.zip").unwrap(), stream, AnalysisOptions::default())
+        .await
+        .unwrap();
+
+    assert!(result.archive_first_entry().is_none());
+}
+
+#[tokio::test]
+async fn test_analyze_content_nested_reports_container_and_inner() {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+    );
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"%PDF-1.4\n").unwrap();
+    let data = encoder.finish().unwrap();
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from(data))]);
+
+    let result = use_case
+        .analyze_in_memory(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("doc.pdf.gz").unwrap(),
+            stream,
+            AnalysisOptions { nested: true, ..AnalysisOptions::default() },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/gzip");
+    let nested = result.nested().unwrap();
+    assert_eq!(nested.container().as_str(), "application/gzip");
+    assert_eq!(nested.inner().as_str(), "application/pdf");
+}
+
+#[tokio::test]
+async fn test_analyze_content_nested_uses_container_timeout_override() {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"%PDF-1.4\n").unwrap();
+    let data = encoder.finish().unwrap();
+
+    let analyze = |override_secs: Option<u64>| {
+        let data = data.clone();
+        async move {
+            let repo: Arc<dyn MagicRepository> = Arc::new(
+                magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::builder()
+                    .with_defaults()
+                    .with_delay_for(b"%PDF", std::time::Duration::from_millis(1500))
+                    .build(),
+            );
+            let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+            let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+            config_val.server.timeouts.analysis_timeout_secs = 1;
+            if let Some(secs) = override_secs {
+                config_val.magic.timeouts.insert("application/gzip".to_string(), secs);
+            }
+            let use_case = AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config_val));
+            let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from(data))]);
+            use_case
+                .analyze_in_memory(
+                    RequestId::generate(),
+                    WindowsCompatibleFilename::new("doc.pdf.gz").unwrap(),
+                    stream,
+                    AnalysisOptions { nested: true, ..AnalysisOptions::default() },
+                )
+                .await
+        }
+    };
+
+    assert!(matches!(analyze(None).await, Err(ApplicationError::Timeout(1))));
+    let result = analyze(Some(3)).await.unwrap();
+    assert_eq!(result.nested().unwrap().inner().as_str(), "application/pdf");
+}
+
+#[tokio::test]
+async fn test_analyze_content_reports_polyglot_types() {
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::builder()
+            .with_mapping(b"%PDF", "application/pdf", "PDF document")
+            .with_mapping(b"%PDF-1.4\nPK", "application/zip", "Zip archive data")
+            .build(),
+    );
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config));
+    let analyze = |data: &'static [u8], polyglot: bool| {
+        let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+        use_case.analyze_in_memory(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("upload.pdf").unwrap(),
+            stream,
+            AnalysisOptions { polyglot, ..AnalysisOptions::default() },
+        )
+    };
+
+    let result = analyze(b"%PDF-1.4\nPK\x03\x04", true).await.unwrap();
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    let types: Vec<String> = result.polyglot_types().unwrap().iter().map(MimeType::as_str).collect();
+    assert_eq!(types, ["application/pdf", "application/zip"]);
+    assert_eq!(result.is_polyglot(), Some(true));
+
+    let single = analyze(b"%PDF-1.7\n", true).await.unwrap();
+    assert_eq!(single.polyglot_types().unwrap().len(), 1);
+    assert_eq!(single.is_polyglot(), Some(false));
+
+    let skipped = analyze(b"%PDF-1.4\nPK\x03\x04", false).await.unwrap();
+    assert!(skipped.polyglot_types().is_none());
+    assert_eq!(skipped.is_polyglot(), None);
+}
+
+async fn analyze_with_declared_type(data: &'static [u8], declared: &str) -> magicer::domain::entities::magic_result::MagicResult {
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+    );
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.fallback_to_declared_type = true;
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config));
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+
+    use_case
+        .analyze_in_memory(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("upload.bin").unwrap(),
+            stream,
+            AnalysisOptions {
+                declared_type: Some(MimeType::try_from(declared).unwrap()),
+                ..AnalysisOptions::default()
+            },
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_analyze_content_falls_back_to_declared_type_on_octet_stream() {
+    let result = analyze_with_declared_type(b"\x00\x01\x02unknown", "application/x-custom").await;
+
+    assert_eq!(result.mime_type().as_str(), "application/x-custom");
+    assert!(!result.is_verified());
+}
+
+#[tokio::test]
+async fn test_analyze_content_declared_type_never_overrides_detection() {
+    let result = analyze_with_declared_type(b"\x89PNG\r\n\x1a\n", "application/x-custom").await;
+
+    assert_eq!(result.mime_type().as_str(), "image/png");
+    assert!(result.is_verified());
+}
+
+struct CountingMagicRepo {
+    calls: std::sync::atomic::AtomicUsize,
+}
+
+impl MagicRepository for CountingMagicRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Box::pin(async {
+            Ok((MimeType::try_from("application/pdf").unwrap(), "PDF document".to_string()))
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_if_content_hash_served_from_cache() {
+    let repo = Arc::new(CountingMagicRepo { calls: std::sync::atomic::AtomicUsize::new(0) });
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.analysis.content_hash_cache_entries = 16;
+    let use_case = AnalyzeContentUseCase::new(repo.clone(), temp_storage, Arc::new(config_val));
+    let data = b"%PDF-1.4";
+    let hash = magicer::application::use_cases::analyze_content::sha256_hex(data);
+    let analyze = |options: AnalysisOptions| {
+        let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+        use_case.analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("a.pdf").unwrap(), stream, options)
+    };
+
+    let primed = analyze(AnalysisOptions::default()).await.unwrap();
+    let cached = analyze(AnalysisOptions { if_content_hash: Some(hash.to_uppercase()), ..AnalysisOptions::default() })
+        .await
+        .unwrap();
+    let mismatched = analyze(AnalysisOptions { if_content_hash: Some("0".repeat(64)), ..AnalysisOptions::default() })
+        .await
+        .unwrap();
+
+    assert!(!primed.is_cached());
+    assert!(cached.is_cached());
+    assert_eq!(cached.mime_type().as_str(), "application/pdf");
+    assert!(!mismatched.is_cached());
+    assert_eq!(repo.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+/// Detects PDF until `failing` is set, then fails like a broken database.
+struct ToggleMagicRepo {
+    failing: std::sync::atomic::AtomicBool,
+}
+
+impl MagicRepository for ToggleMagicRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let failing = self.failing.load(std::sync::atomic::Ordering::SeqCst);
+        Box::pin(async move {
+            if failing {
+                Err(MagicError::AnalysisFailed("corrupt database".to_string()))
+            } else {
+                Ok((MimeType::try_from("application/pdf").unwrap(), "PDF document".to_string()))
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_open_breaker_serves_stale_cached_detection() {
+    use magicer::infrastructure::magic::circuit_breaker_magic_repository::{BreakerState, CircuitBreakerMagicRepository};
+    let inner = Arc::new(ToggleMagicRepo { failing: std::sync::atomic::AtomicBool::new(false) });
+    let breaker = Arc::new(CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        1,
+        std::time::Duration::from_secs(30),
+        std::time::Duration::from_secs(60),
+    ));
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.analysis.content_hash_cache_entries = 16;
+    let use_case = AnalyzeContentUseCase::new(breaker.clone(), temp_storage, Arc::new(config_val));
+    let analyze = |data: &'static [u8]| {
+        let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+        use_case.analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("a.pdf").unwrap(), stream, AnalysisOptions::default())
+    };
+
+    let primed = analyze(b"%PDF-1.4 seen").await.unwrap();
+    inner.failing.store(true, std::sync::atomic::Ordering::SeqCst);
+    let tripped = analyze(b"%PDF-1.4 trips").await.unwrap_err();
+    assert_eq!(breaker.state(), BreakerState::Open);
+    let stale = analyze(b"%PDF-1.4 seen").await.unwrap();
+    let unseen = analyze(b"%PDF-1.4 unseen").await.unwrap_err();
+
+    assert!(!primed.is_stale());
+    assert!(matches!(tripped, ApplicationError::AnalysisFailed(_)));
+    assert!(stale.is_stale());
+    assert!(stale.is_cached());
+    assert_eq!(stale.mime_type().as_str(), "application/pdf");
+    assert_eq!(stale.description(), "PDF document");
+    assert!(matches!(unseen, ApplicationError::ServiceUnavailable(_)));
+}
+
+async fn analyze_with_min_bytes(min_content_bytes: usize, data: &'static [u8]) -> Result<magicer::domain::entities::magic_result::MagicResult, ApplicationError> {
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.analysis.min_content_bytes = min_content_bytes;
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config_val));
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+    use_case
+        .analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("a.bin").unwrap(), stream, AnalysisOptions::default())
+        .await
+}
+
+#[tokio::test]
+async fn test_min_content_bytes_boundary() {
+    let below = analyze_with_min_bytes(8, b"abcdefg").await;
+    let at = analyze_with_min_bytes(8, b"abcdefgh").await;
+
+    let err = below.unwrap_err();
+    assert!(matches!(err, ApplicationError::ContentTooShort(_)));
+    assert_eq!(err.status_code(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(at.is_ok());
+}
+
+#[tokio::test]
+async fn test_min_content_bytes_allows_short_known_signature() {
+    let result = analyze_with_min_bytes(64, b"%PDF-1.4").await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_min_content_bytes_disabled_by_default() {
+    let result = analyze_with_min_bytes(0, b"a").await;
+    assert!(result.is_ok());
+}
+
+struct PngMagicRepo;
+impl MagicRepository for PngMagicRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async {
+            Ok((MimeType::try_from("image/png").unwrap(), "PNG image data, 800 x 600, 8-bit/color RGBA".to_string()))
+        })
+    }
+}
+
+async fn analyze_png(redact: bool) -> String {
+    let repo: Arc<dyn MagicRepository> = Arc::new(PngMagicRepo);
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let mut config_val = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config_val.analysis.redact_description_metadata = redact;
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, Arc::new(config_val));
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"\x89PNG"))]);
+    let result = use_case
+        .analyze_in_memory(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("photo.png").unwrap(),
+            stream,
+            AnalysisOptions::default(),
+        )
+        .await
+        .unwrap();
+    result.description().to_string()
+}
+
+#[tokio::test]
+async fn test_description_metadata_redacted_when_enabled() {
+    assert_eq!(analyze_png(true).await, "PNG image data, [redacted], 8-bit/color RGBA");
+    assert_eq!(analyze_png(false).await, "PNG image data, 800 x 600, 8-bit/color RGBA");
+}
+
+#[tokio::test]
+async fn test_result_tagged_with_experiment_database() {
+    use magicer::infrastructure::magic::experiment_magic_repository::ExperimentMagicRepository;
+
+    let repo: Arc<dyn MagicRepository> = Arc::new(ExperimentMagicRepository::new(
+        Arc::new(FakeMagicRepo),
+        Arc::new(PngMagicRepo),
+        1.0,
+    ));
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FakeTempStorage);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = AnalyzeContentUseCase::new(repo, temp_storage, config);
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"\x89PNG"))]);
+
+    let result = use_case
+        .analyze_in_memory(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("photo.png").unwrap(),
+            stream,
+            AnalysisOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "image/png");
+    assert_eq!(result.database_variant(), Some("candidate"));
+}
+
+/// Log output captured by a thread-local subscriber.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn sampled_log_lines(sample_rate: u64, analyses: usize) -> usize {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.logging.sample_rate = sample_rate;
+    let use_case = AnalyzeContentUseCase::new(Arc::new(FakeMagicRepo), Arc::new(FakeTempStorage), Arc::new(config));
+    for _ in 0..analyses {
+        let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"%PDF-1.4"))]);
+        use_case
+            .analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("test.pdf").unwrap(), stream, AnalysisOptions::default())
+            .await
+            .unwrap();
+    }
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    output.lines().filter(|line| line.contains("Sampled analysis")).count()
+}
+
+#[tokio::test]
+async fn test_sample_rate_one_logs_every_analysis() {
+    assert_eq!(sampled_log_lines(1, 4).await, 4);
+}
+
+#[tokio::test]
+async fn test_higher_sample_rate_logs_a_fraction_of_analyses() {
+    assert_eq!(sampled_log_lines(3, 6).await, 2);
+}
+
+#[tokio::test]
+async fn test_sampling_is_off_by_default() {
+    assert_eq!(sampled_log_lines(0, 3).await, 0);
+}
+
+async fn analyze_with_alias(data: &'static [u8], alias: Option<&str>) -> magicer::domain::entities::magic_result::MagicResult {
+    let repo: Arc<dyn MagicRepository> = Arc::new(
+        magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+    );
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.unknown_type_alias = alias.map(str::to_string);
+    let use_case = AnalyzeContentUseCase::new(repo, Arc::new(FakeTempStorage), Arc::new(config));
+    let stream = futures_util::stream::iter(vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(data))]);
+
+    use_case
+        .analyze_in_memory(RequestId::generate(), WindowsCompatibleFilename::new("upload.bin").unwrap(), stream, AnalysisOptions::default())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_unknown_type_alias_replaces_octet_stream() {
+    let result = analyze_with_alias(b"\x00\x01\x02unknown", Some("application/x-unknown")).await;
+
+    assert_eq!(result.mime_type().as_str(), "application/x-unknown");
+    assert!(result.is_aliased());
+}
+
+#[tokio::test]
+async fn test_octet_stream_kept_without_unknown_type_alias() {
+    let result = analyze_with_alias(b"\x00\x01\x02unknown", None).await;
+
+    assert_eq!(result.mime_type().as_str(), "application/octet-stream");
+    assert!(!result.is_aliased());
+}
+
+#[tokio::test]
+async fn test_unknown_type_alias_leaves_detections_alone() {
+    let result = analyze_with_alias(b"\x89PNG\r\n\x1a\n", Some("application/x-unknown")).await;
+
+    assert_eq!(result.mime_type().as_str(), "image/png");
+    assert!(!result.is_aliased());
+}
+
+#[tokio::test]
+async fn test_cancelled_upload_removes_temp_file() {
+    use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FsTempStorageService::new(temp_dir.path().to_path_buf()));
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let use_case = Arc::new(AnalyzeContentUseCase::new(repo, temp_storage, config));
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<bytes::Bytes, std::io::Error>>(1);
+    tx.send(Ok(bytes::Bytes::from_static(b"%PDF-1.4"))).await.unwrap();
+    let stream = futures_util::stream::unfold(rx, |mut rx| async { rx.recv().await.map(|chunk| (chunk, rx)) });
+
+    let task = tokio::spawn({
+        let use_case = use_case.clone();
+        async move {
+            use_case
+                .analyze_to_temp_file(RequestId::generate(), WindowsCompatibleFilename::new("a.pdf").unwrap(), Box::pin(stream), AnalysisOptions::default())
+                .await
+        }
+    });
+    let files = || std::fs::read_dir(temp_dir.path()).unwrap().count();
+    // The first chunk is written; the upload then waits for more.
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while files() == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    task.abort();
+    assert!(task.await.unwrap_err().is_cancelled());
+    drop(tx);
+
+    assert_eq!(files(), 0);
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 use std::path::PathBuf;
 use futures_util::future::BoxFuture;
+use magicer::application::use_cases::analyze_content::AnalysisOptions;
 use magicer::application::use_cases::analyze_path::AnalyzePathUseCase;
 use magicer::domain::repositories::magic_repository::MagicRepository;
 use magicer::domain::services::sandbox_service::SandboxService;
@@ -49,12 +50,42 @@ async fn test_analyze_path_success() {
     let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
     let path = RelativePath::new("uploads/test.pdf").unwrap();
     
-    let result = use_case.execute(request_id, filename, path).await.unwrap();
+    let result = use_case.execute(request_id, filename, path, AnalysisOptions::default()).await.unwrap();
     
     assert_eq!(result.mime_type().as_str(), "application/pdf");
     assert_eq!(result.description(), "PDF document");
 }
 
+#[tokio::test]
+async fn test_analyze_path_sha256_hash() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path().to_path_buf();
+    std::fs::write(root.join("hello.txt"), b"hello world\n").unwrap();
+
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root });
+    let use_case = AnalyzePathUseCase::new(repo, sandbox, 30);
+    let options = AnalysisOptions {
+        hash: true,
+        ..AnalysisOptions::default()
+    };
+
+    let result = use_case
+        .execute(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("hello.txt").unwrap(),
+            RelativePath::new("hello.txt").unwrap(),
+            options,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result.hash(),
+        Some("a948904f2f0f479b8f8197694b30184b0d2ed1c1cd2a1ec0fb85d299a192a447")
+    );
+}
+
 struct BoundaryViolatingSandbox;
 impl SandboxService for BoundaryViolatingSandbox {
     fn resolve_path(&self, _path: &RelativePath) -> Result<PathBuf, ValidationError> {
@@ -73,7 +104,7 @@ async fn test_analyze_path_outside_sandbox_rejected() {
     let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
     let path = RelativePath::new("test.pdf").unwrap();
     
-    let result = use_case.execute(request_id, filename, path).await;
+    let result = use_case.execute(request_id, filename, path, AnalysisOptions::default()).await;
     assert!(result.is_err());
 }
 
@@ -98,7 +129,7 @@ async fn test_analyze_path_not_found() {
     let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
     let path = RelativePath::new("missing.pdf").unwrap();
     
-    let result = use_case.execute(request_id, filename, path).await;
+    let result = use_case.execute(request_id, filename, path, AnalysisOptions::default()).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     assert!(matches!(err, ApplicationError::NotFound(_)));
@@ -129,7 +160,7 @@ async fn test_analyze_path_timeout() {
     let filename = WindowsCompatibleFilename::new("test.pdf").unwrap();
     let path = RelativePath::new("test.pdf").unwrap();
     
-    let result = use_case.execute(request_id, filename, path).await;
+    let result = use_case.execute(request_id, filename, path, AnalysisOptions::default()).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     assert_eq!(err.status_code(), axum::http::StatusCode::GATEWAY_TIMEOUT);
@@ -141,3 +172,352 @@ impl MagicRepository for FailingMagicRepo {
         Box::pin(async { Err(MagicError::AnalysisFailed("fail".to_string())) })
     }
 }
+
+fn enomem_mmap(_file: &std::fs::File) -> Result<magicer::infrastructure::filesystem::mmap::MmapHandler, magicer::infrastructure::errors::InfrastructureError> {
+    Err(magicer::infrastructure::errors::InfrastructureError::Io(std::io::Error::from_raw_os_error(libc::ENOMEM)))
+}
+
+fn enomem_use_case(root: PathBuf, fallback: bool) -> AnalyzePathUseCase {
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root });
+    AnalyzePathUseCase::new(repo, sandbox, 5)
+        .with_mmap(enomem_mmap)
+        .with_mmap_fallback(fallback)
+}
+
+#[tokio::test]
+async fn test_analyze_path_mmap_enomem_falls_back_to_file_mode() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("big.pdf"), b"%PDF-1.4").unwrap();
+    let use_case = enomem_use_case(temp_dir.path().to_path_buf(), true);
+
+    let options = AnalysisOptions { hash: true, ..AnalysisOptions::default() };
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("big.pdf").unwrap(),
+        RelativePath::new("big.pdf").unwrap(),
+        options,
+    ).await.unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    assert_eq!(
+        result.hash(),
+        Some(magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4").as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_analyze_path_mmap_enomem_without_fallback_fails() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("big.pdf"), b"%PDF-1.4").unwrap();
+    let use_case = enomem_use_case(temp_dir.path().to_path_buf(), false);
+
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("big.pdf").unwrap(),
+        RelativePath::new("big.pdf").unwrap(),
+        AnalysisOptions::default(),
+    ).await;
+
+    assert!(matches!(result, Err(ApplicationError::InternalError(_))));
+}
+
+#[tokio::test]
+async fn test_analyze_path_fifo_rejected_without_blocking() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let fifo = temp_dir.path().join("pipe");
+    let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root: temp_dir.path().to_path_buf() });
+    let use_case = AnalyzePathUseCase::new(repo, sandbox, 5);
+
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        use_case.execute(
+            RequestId::generate(),
+            WindowsCompatibleFilename::new("pipe").unwrap(),
+            RelativePath::new("pipe").unwrap(),
+            AnalysisOptions::default(),
+        ),
+    )
+    .await
+    .expect("analysis of a FIFO must not block");
+
+    let err = result.unwrap_err();
+    assert!(matches!(err, ApplicationError::UnsupportedFileType(_)));
+    assert_eq!(err.status_code(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_analyze_path_directory_rejected() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp_dir.path().join("dir")).unwrap();
+    let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+    let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root: temp_dir.path().to_path_buf() });
+    let use_case = AnalyzePathUseCase::new(repo, sandbox, 5);
+
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("dir").unwrap(),
+        RelativePath::new("dir").unwrap(),
+        AnalysisOptions::default(),
+    ).await;
+
+    assert!(matches!(result, Err(ApplicationError::UnsupportedFileType(_))));
+}
+
+/// Truncates the analyzed file to its first four bytes during the first
+/// analysis, as a concurrent writer would, and reports what it saw.
+struct TruncatingMagicRepo {
+    path: PathBuf,
+    truncated: std::sync::atomic::AtomicBool,
+}
+impl MagicRepository for TruncatingMagicRepo {
+    fn analyze_buffer<'a>(&'a self, data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        let seen = format!("{} bytes", data.len());
+        if !self.truncated.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            std::fs::OpenOptions::new().write(true).open(&self.path).unwrap().set_len(4).unwrap();
+        }
+        Box::pin(async move { Ok((MimeType::try_from("application/pdf").unwrap(), seen)) })
+    }
+}
+
+fn truncating_use_case(root: PathBuf, retry: bool) -> AnalyzePathUseCase {
+    std::fs::write(root.join("growing.pdf"), vec![b'%'; 8192]).unwrap();
+    let repo: Arc<dyn MagicRepository> = Arc::new(TruncatingMagicRepo {
+        path: root.join("growing.pdf"),
+        truncated: std::sync::atomic::AtomicBool::new(false),
+    });
+    let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root });
+    AnalyzePathUseCase::new(repo, sandbox, 5).with_retry_on_truncation(retry)
+}
+
+#[tokio::test]
+async fn test_analyze_path_retries_in_file_mode_after_truncation() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let use_case = truncating_use_case(temp_dir.path().to_path_buf(), true);
+
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("growing.pdf").unwrap(),
+        RelativePath::new("growing.pdf").unwrap(),
+        AnalysisOptions::default(),
+    ).await.unwrap();
+
+    assert_eq!(result.description(), "4 bytes");
+}
+
+#[tokio::test]
+async fn test_analyze_path_truncation_retry_describes_the_current_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let use_case = truncating_use_case(temp_dir.path().to_path_buf(), true);
+    let analyze = || use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("growing.pdf").unwrap(),
+        RelativePath::new("growing.pdf").unwrap(),
+        AnalysisOptions { hash: true, ..AnalysisOptions::default() },
+    );
+
+    let retried = analyze().await.unwrap();
+    // The file is no longer truncated by this second run.
+    let current = analyze().await.unwrap();
+
+    assert_eq!(
+        retried.hash(),
+        Some(magicer::application::use_cases::analyze_content::sha256_hex(b"%%%%").as_str())
+    );
+    assert_eq!(retried.entity_tag(), current.entity_tag());
+    assert_eq!(retried.modified_at(), current.modified_at());
+}
+
+#[tokio::test]
+async fn test_analyze_path_keeps_mapped_result_without_truncation_retry() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let use_case = truncating_use_case(temp_dir.path().to_path_buf(), false);
+
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("growing.pdf").unwrap(),
+        RelativePath::new("growing.pdf").unwrap(),
+        AnalysisOptions::default(),
+    ).await.unwrap();
+
+    assert_eq!(result.description(), "8192 bytes");
+}
+
+#[tokio::test]
+async fn test_analyze_path_without_mmap_uses_file_mode() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("nfs.pdf"), b"%PDF-1.4").unwrap();
+    // Mapping would fail without a fallback, so success means it was never tried.
+    let use_case = enomem_use_case(temp_dir.path().to_path_buf(), false).with_use_mmap(false);
+
+    let options = AnalysisOptions { hash: true, ..AnalysisOptions::default() };
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("nfs.pdf").unwrap(),
+        RelativePath::new("nfs.pdf").unwrap(),
+        options,
+    ).await.unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    assert_eq!(
+        result.hash(),
+        Some(magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4").as_str())
+    );
+}
+
+/// Answers the description cookie and the database variant differently
+/// from plain detection, so it shows which of them ran.
+struct DescribingRepo;
+impl MagicRepository for DescribingRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async { Ok((MimeType::try_from("application/pdf").unwrap(), "application/pdf".to_string())) })
+    }
+    fn describe_buffer<'a>(&'a self, data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<String, MagicError>> {
+        let description = format!("PDF document, {} bytes", data.len());
+        Box::pin(async move { Ok(description) })
+    }
+    fn database_variant(&self, _data: &[u8]) -> Option<&'static str> {
+        Some("candidate")
+    }
+    fn database_variant_for_file(&self, _file: &std::fs::File) -> Option<&'static str> {
+        Some("candidate")
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_path_output_and_variant_match_with_and_without_mmap() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("doc.pdf"), b"garbage-%PDF-1.4").unwrap();
+
+    for range in [None, Some("bytes=8-")] {
+        let mut seen = Vec::new();
+        for use_mmap in [true, false] {
+            let repo: Arc<dyn MagicRepository> = Arc::new(DescribingRepo);
+            let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root: temp_dir.path().to_path_buf() });
+            let use_case = AnalyzePathUseCase::new(repo, sandbox, 5).with_use_mmap(use_mmap);
+            let options = AnalysisOptions {
+                output: magicer::application::use_cases::analyze_content::OutputMode::parse("description"),
+                range: range.and_then(magicer::domain::value_objects::byte_range::ByteRange::parse),
+                ..AnalysisOptions::default()
+            };
+            let result = use_case.execute(
+                RequestId::generate(),
+                WindowsCompatibleFilename::new("doc.pdf").unwrap(),
+                RelativePath::new("doc.pdf").unwrap(),
+                options,
+            ).await.unwrap();
+            seen.push((result.description().to_string(), result.database_variant().map(str::to_string)));
+        }
+
+        assert_eq!(seen[0], seen[1], "range {:?}", range);
+        assert!(seen[0].0.starts_with("PDF document"), "{:?}", seen[0]);
+        assert_eq!(seen[0].1.as_deref(), Some("candidate"));
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_path_without_mmap_analyzes_only_the_range() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut content = b"garbage-".to_vec();
+    content.extend_from_slice(b"%PDF-1.4");
+    std::fs::write(temp_dir.path().join("embedded.bin"), &content).unwrap();
+    let use_case = enomem_use_case(temp_dir.path().to_path_buf(), false).with_use_mmap(false);
+
+    let options = AnalysisOptions {
+        hash: true,
+        range: magicer::domain::value_objects::byte_range::ByteRange::parse("bytes=8-"),
+        ..AnalysisOptions::default()
+    };
+    let result = use_case.execute(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("embedded.bin").unwrap(),
+        RelativePath::new("embedded.bin").unwrap(),
+        options,
+    ).await.unwrap();
+
+    assert_eq!(result.mime_type().as_str(), "application/pdf");
+    assert_eq!(result.analyzed_range(), Some((8, 15)));
+    assert_eq!(
+        result.hash(),
+        Some(magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4").as_str())
+    );
+}
+
+static SWAPPING_OPENS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Opens the file and then replaces its path with a PNG, as a writer
+/// renaming a new file into place would.
+fn open_then_swap(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    SWAPPING_OPENS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    let file = std::fs::File::open(path)?;
+    let replacement = path.with_extension("swap");
+    std::fs::write(&replacement, b"\x89PNG\r\n\x1a\n and a longer body")?;
+    std::fs::rename(&replacement, path)?;
+    Ok(file)
+}
+
+#[tokio::test]
+async fn test_analyze_path_opens_once_and_analyzes_the_opened_file() {
+    for use_mmap in [true, false] {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("doc.pdf"), b"%PDF-1.4").unwrap();
+        let repo: Arc<dyn MagicRepository> = Arc::new(
+            magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository::new().unwrap(),
+        );
+        let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root: temp_dir.path().to_path_buf() });
+        let use_case = AnalyzePathUseCase::new(repo, sandbox, 5)
+            .with_open(open_then_swap)
+            .with_use_mmap(use_mmap);
+        let opens_before = SWAPPING_OPENS.load(std::sync::atomic::Ordering::SeqCst);
+
+        let result = use_case
+            .execute(
+                RequestId::generate(),
+                WindowsCompatibleFilename::new("doc.pdf").unwrap(),
+                RelativePath::new("doc.pdf").unwrap(),
+                AnalysisOptions { hash: true, ..AnalysisOptions::default() },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(SWAPPING_OPENS.load(std::sync::atomic::Ordering::SeqCst) - opens_before, 1);
+        assert_eq!(result.mime_type().as_str(), "application/pdf", "use_mmap = {use_mmap}");
+        assert_eq!(
+            result.hash(),
+            Some(magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4").as_str())
+        );
+        // The ETag encodes the size of the opened file, not the replacement.
+        assert!(result.entity_tag().unwrap().starts_with("\"8-"), "use_mmap = {use_mmap}");
+    }
+}
+
+#[tokio::test]
+async fn test_analyze_path_rejects_matching_description() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path().to_path_buf();
+    std::fs::write(root.join("test.pdf"), b"%PDF-1.4").unwrap();
+    let analyze = |patterns: Vec<String>| {
+        let repo: Arc<dyn MagicRepository> = Arc::new(FakeMagicRepo);
+        let sandbox: Arc<dyn SandboxService> = Arc::new(FakeSandbox { root: root.clone() });
+        let use_case = AnalyzePathUseCase::new(repo, sandbox, 5).with_reject_descriptions(patterns);
+        async move {
+            use_case
+                .execute(
+                    RequestId::generate(),
+                    WindowsCompatibleFilename::new("test.pdf").unwrap(),
+                    RelativePath::new("test.pdf").unwrap(),
+                    AnalysisOptions::default(),
+                )
+                .await
+        }
+    };
+
+    let rejected = analyze(vec!["encrypted".to_string(), "pdf DOC".to_string()]).await;
+    let accepted = analyze(vec!["encrypted".to_string()]).await.unwrap();
+
+    assert!(matches!(rejected, Err(ApplicationError::RejectedContent(ref p)) if p == "pdf DOC"));
+    assert_eq!(accepted.mime_type().as_str(), "application/pdf");
+}
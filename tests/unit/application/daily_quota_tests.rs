@@ -0,0 +1,43 @@
+use chrono::{TimeZone, Utc};
+use magicer::application::daily_quota::DailyQuota;
+
+#[test]
+fn test_quota_exhausted_at_limit() {
+    let quota = DailyQuota::new(3, 0);
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+
+    for _ in 0..3 {
+        assert!(!quota.is_exhausted("alice", now));
+        quota.record("alice", now);
+    }
+
+    assert!(quota.is_exhausted("alice", now));
+    assert!(!quota.is_exhausted("bob", now));
+}
+
+#[test]
+fn test_quota_resets_at_configured_utc_hour() {
+    let quota = DailyQuota::new(1, 6);
+    let before_reset = Utc.with_ymd_and_hms(2026, 3, 2, 5, 59, 59).unwrap();
+    let at_reset = Utc.with_ymd_and_hms(2026, 3, 2, 6, 0, 0).unwrap();
+
+    // 07:00 the previous day is in the same quota day as 05:59 today.
+    quota.record("alice", Utc.with_ymd_and_hms(2026, 3, 1, 7, 0, 0).unwrap());
+
+    assert!(quota.is_exhausted("alice", before_reset));
+    assert_eq!(quota.resets_at(before_reset), at_reset);
+    assert!(!quota.is_exhausted("alice", at_reset));
+    assert_eq!(quota.used("alice", at_reset), 0);
+}
+
+#[test]
+fn test_zero_limit_is_unlimited() {
+    let quota = DailyQuota::new(0, 0);
+    let now = Utc::now();
+
+    quota.record("alice", now);
+
+    assert!(!quota.is_enabled());
+    assert!(!quota.is_exhausted("alice", now));
+    assert_eq!(quota.used("alice", now), 0);
+}
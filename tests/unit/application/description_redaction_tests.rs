@@ -0,0 +1,35 @@
+use magicer::application::description_redaction::redact_metadata;
+
+#[test]
+fn test_dimensions_redacted() {
+    assert_eq!(
+        redact_metadata("PNG image data, 800 x 600, 8-bit/color RGBA, non-interlaced"),
+        "PNG image data, [redacted], 8-bit/color RGBA, non-interlaced"
+    );
+    assert_eq!(
+        redact_metadata("JPEG image data, baseline, precision 8, 1920x1080, components 3"),
+        "JPEG image data, baseline, precision 8, [redacted], components 3"
+    );
+}
+
+#[test]
+fn test_author_and_software_strings_redacted() {
+    assert_eq!(
+        redact_metadata("Composite Document File V2 Document, Author: Jane Doe, Name of Creating Application: Microsoft Office Word"),
+        "Composite Document File V2 Document, Author: [redacted], Name of Creating Application: [redacted]"
+    );
+    assert_eq!(
+        redact_metadata("JPEG image data, Exif Standard: [TIFF image data, big-endian, direntries=10, manufacturer=Canon, model=EOS 5D, software=GIMP 2.10]"),
+        "JPEG image data, Exif Standard: [TIFF image data, big-endian, direntries=10, manufacturer=[redacted], model=[redacted], software=[redacted]]"
+    );
+    assert_eq!(
+        redact_metadata("JPEG image data, comment: \"taken at home, 2024\", baseline"),
+        "JPEG image data, comment: [redacted], baseline"
+    );
+}
+
+#[test]
+fn test_description_without_metadata_unchanged() {
+    assert_eq!(redact_metadata("PDF document, version 1.4"), "PDF document, version 1.4");
+    assert_eq!(redact_metadata("ASCII text"), "ASCII text");
+}
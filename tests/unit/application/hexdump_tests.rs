@@ -0,0 +1,18 @@
+use magicer::application::hexdump::hexdump;
+
+#[test]
+fn test_hexdump_matches_canonical_format() {
+    let dump = hexdump(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n1 0 obj");
+
+    assert_eq!(
+        dump,
+        "00000000  25 50 44 46 2d 31 2e 34  0a 25 e2 e3 cf d3 0a 31  |%PDF-1.4.%.....1|\n\
+         00000010  20 30 20 6f 62 6a                                 | 0 obj|\n\
+         00000016\n"
+    );
+}
+
+#[test]
+fn test_hexdump_of_empty_input_is_just_the_length() {
+    assert_eq!(hexdump(b""), "00000000\n");
+}
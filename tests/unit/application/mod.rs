@@ -1,3 +1,8 @@
 pub mod analyze_content_tests;
 pub mod analyze_path_tests;
+pub mod daily_quota_tests;
+pub mod description_redaction_tests;
 pub mod health_check_tests;
+pub mod hexdump_tests;
+pub mod readiness_check_tests;
+pub mod test_magic_rule_tests;
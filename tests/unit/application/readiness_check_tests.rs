@@ -0,0 +1,56 @@
+use magicer::application::use_cases::readiness_check::{ReadinessCheckUseCase, ReadinessError};
+use magicer::domain::services::temp_storage::TempStorageService;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_readiness_round_trip_success() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_storage: Arc<dyn TempStorageService> =
+        Arc::new(FsTempStorageService::new(temp_dir.path().to_path_buf()));
+    let use_case = ReadinessCheckUseCase::new(temp_storage);
+
+    assert!(use_case.execute().await.is_ok());
+    // The probe file must not be left behind
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_readiness_fails_on_read_only_temp_dir() {
+    // procfs rejects file creation regardless of the caller's privileges
+    let temp_storage: Arc<dyn TempStorageService> = Arc::new(FsTempStorageService::new(
+        PathBuf::from("/proc/magicer_readiness_probe"),
+    ));
+    let use_case = ReadinessCheckUseCase::new(temp_storage);
+
+    let err = use_case.execute().await.unwrap_err();
+    assert!(matches!(err, ReadinessError::TempDirNotWritable(_)));
+    assert_eq!(err.reason(), "temp_dir_not_writable");
+}
+
+#[tokio::test]
+async fn test_readiness_reports_free_space_and_enforces_minimum() {
+    use magicer::infrastructure::config::server_config::ServerConfig;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_storage: Arc<dyn TempStorageService> =
+        Arc::new(FsTempStorageService::new(temp_dir.path().to_path_buf()));
+    let mut config = ServerConfig::default();
+    config.analysis.temp_dir = temp_dir.path().to_string_lossy().into_owned();
+    let free_space_mb = config.get_free_space_mb(&config.analysis.temp_dir);
+
+    let use_case = ReadinessCheckUseCase::new(temp_storage.clone())
+        .with_disk_check(Arc::new(config.clone()));
+    let report = use_case.execute().await.unwrap();
+    let reported = report.free_space_mb.unwrap();
+    assert!(reported > 0);
+    // Other tests share the filesystem, so allow for some drift.
+    assert!(reported.abs_diff(free_space_mb) < 64);
+
+    config.analysis.min_free_space_mb = u64::MAX;
+    let use_case = ReadinessCheckUseCase::new(temp_storage).with_disk_check(Arc::new(config));
+    let err = use_case.execute().await.unwrap_err();
+    assert_eq!(err.reason(), "insufficient_disk_space");
+    assert!(err.free_space_mb().is_some_and(|mb| mb > 0));
+}
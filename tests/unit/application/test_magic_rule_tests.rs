@@ -0,0 +1,41 @@
+use magicer::application::errors::ApplicationError;
+use magicer::application::use_cases::test_magic_rule::TestMagicRuleUseCase;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use magicer::infrastructure::magic::libmagic_repository::LibmagicRepository;
+use std::sync::Arc;
+
+const TRIVIAL_RULE: &str =
+    "0\tstring\tMAGICERTEST\tMagicer test format\n!:mime\tapplication/x-magicer-test\n";
+
+fn use_case(temp_dir: &std::path::Path) -> TestMagicRuleUseCase {
+    TestMagicRuleUseCase::new(
+        Arc::new(LibmagicRepository::new(true).unwrap()),
+        Arc::new(FsTempStorageService::new(temp_dir.to_path_buf())),
+    )
+}
+
+#[tokio::test]
+async fn test_custom_rule_detects_content() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (mime, description) = use_case(dir.path())
+        .execute(TRIVIAL_RULE, b"MAGICERTEST payload")
+        .await
+        .unwrap();
+
+    assert_eq!(mime.as_str(), "application/x-magicer-test");
+    assert_eq!(description, "Magicer test format");
+    // The staged rule file is removed afterwards.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_invalid_rule_is_bad_request() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let result = use_case(dir.path())
+        .execute("0\tnot_a_type\tX\n", b"X")
+        .await;
+
+    assert!(matches!(result, Err(ApplicationError::BadRequest(_))));
+}
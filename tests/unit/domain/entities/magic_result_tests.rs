@@ -47,3 +47,26 @@ fn test_magic_result_identity() {
     assert_ne!(result1.id(), result2.id());
     assert_ne!(result1, result2);
 }
+
+#[test]
+fn test_long_description_truncated_at_char_boundary() {
+    let filename = WindowsCompatibleFilename::new("test.txt").unwrap();
+    let mime_type = MimeType::try_from("text/plain").unwrap();
+    // "é" is two bytes, so a 5-byte cut would split the third one.
+    let result = MagicResult::new(RequestId::generate(), filename, mime_type, "ééééé".to_string())
+        .with_description_limit(Some(5));
+
+    assert_eq!(result.description(), "éé…");
+    assert!(result.is_description_truncated());
+}
+
+#[test]
+fn test_short_description_not_truncated() {
+    let filename = WindowsCompatibleFilename::new("test.txt").unwrap();
+    let mime_type = MimeType::try_from("text/plain").unwrap();
+    let result = MagicResult::new(RequestId::generate(), filename, mime_type, "ASCII text".to_string())
+        .with_description_limit(Some(10));
+
+    assert_eq!(result.description(), "ASCII text");
+    assert!(!result.is_description_truncated());
+}
@@ -0,0 +1,32 @@
+use magicer::domain::value_objects::byte_range::ByteRange;
+
+#[test]
+fn test_parse_single_ranges() {
+    assert_eq!(ByteRange::parse("bytes=0-99"), Some(ByteRange::From { start: 0, end: Some(99) }));
+    assert_eq!(ByteRange::parse("bytes=512-"), Some(ByteRange::From { start: 512, end: None }));
+    assert_eq!(ByteRange::parse("bytes=-16"), Some(ByteRange::Suffix(16)));
+}
+
+#[test]
+fn test_parse_rejects_unsupported_ranges() {
+    for value in ["items=0-1", "bytes=0-1,4-5", "bytes=9-3", "bytes=a-b", "bytes=-", "0-10"] {
+        assert_eq!(ByteRange::parse(value), None, "{}", value);
+    }
+}
+
+#[test]
+fn test_resolve_against_file_size() {
+    let size = 100;
+    assert_eq!(ByteRange::parse("bytes=10-19").unwrap().resolve(size), Some((10, 19)));
+    assert_eq!(ByteRange::parse("bytes=90-500").unwrap().resolve(size), Some((90, 99)));
+    assert_eq!(ByteRange::parse("bytes=50-").unwrap().resolve(size), Some((50, 99)));
+    assert_eq!(ByteRange::parse("bytes=-10").unwrap().resolve(size), Some((90, 99)));
+    assert_eq!(ByteRange::parse("bytes=-500").unwrap().resolve(size), Some((0, 99)));
+}
+
+#[test]
+fn test_resolve_out_of_bounds() {
+    assert_eq!(ByteRange::parse("bytes=100-").unwrap().resolve(100), None);
+    assert_eq!(ByteRange::parse("bytes=-0").unwrap().resolve(100), None);
+    assert_eq!(ByteRange::parse("bytes=0-0").unwrap().resolve(0), None);
+}
@@ -0,0 +1,36 @@
+use magicer::domain::errors::ValidationError;
+use magicer::domain::value_objects::data_uri::DataUri;
+
+#[test]
+fn test_parse_base64_data_uri() {
+    let uri = DataUri::parse("data:application/pdf;base64,JVBERi0xLjQ=").unwrap();
+    assert_eq!(uri.media_type().as_str(), "application/pdf");
+    assert_eq!(uri.data(), b"%PDF-1.4");
+}
+
+#[test]
+fn test_parse_drops_parameters_and_wrapped_lines() {
+    let uri = DataUri::parse("DATA:Text/HTML;charset=utf-8;base64,PGh0\r\nbWw+").unwrap();
+    assert_eq!(uri.media_type().as_str(), "text/html");
+    assert_eq!(uri.data(), b"<html>");
+}
+
+#[test]
+fn test_parse_percent_encoded_data_uri_defaults_to_text_plain() {
+    let uri = DataUri::parse("data:,hello%20world").unwrap();
+    assert_eq!(uri.media_type().as_str(), "text/plain");
+    assert_eq!(uri.data(), b"hello world");
+}
+
+#[test]
+fn test_parse_rejects_malformed_data_uris() {
+    assert_eq!(DataUri::parse("http://example.com/a.pdf"), Err(ValidationError::UnsupportedValue));
+    for value in [
+        "data:application/pdf;base64",
+        "data:application/pdf;base64,not base64!",
+        "data:pdf;base64,JVBERi0xLjQ=",
+        "data:,100%",
+    ] {
+        assert!(DataUri::parse(value).is_err(), "{}", value);
+    }
+}
@@ -61,3 +61,18 @@ fn test_new_with_windows_reserved_characters_returns_success() {
         assert!(filename.is_ok(), "Should accept character: {}", c);
     }
 }
+
+#[test]
+fn test_new_normalized_lowercases_name() {
+    let filename = WindowsCompatibleFilename::new_normalized("Report.PDF").unwrap();
+    assert_eq!(filename.as_str(), "report.pdf");
+    assert_eq!(filename, WindowsCompatibleFilename::new_normalized("REPORT.pdf").unwrap());
+}
+
+#[test]
+fn test_new_normalized_still_validates() {
+    assert_eq!(
+        WindowsCompatibleFilename::new_normalized("Dir/File.txt").unwrap_err(),
+        ValidationError::InvalidCharacter
+    );
+}
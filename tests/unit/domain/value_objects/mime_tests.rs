@@ -37,3 +37,33 @@ fn test_try_from_with_empty_string_returns_error() {
     let mime = MimeType::try_from("");
     assert!(mime.is_err());
 }
+
+fn textual_subtypes() -> Vec<String> {
+    vec!["json".to_string(), "xml".to_string()]
+}
+
+#[test]
+fn test_is_textual_includes_listed_application_subtypes() {
+    assert!(MimeType::new("application/json").unwrap().is_textual(&textual_subtypes()));
+    assert!(MimeType::new("text/plain").unwrap().is_textual(&[]));
+    assert!(!MimeType::new("application/json").unwrap().is_textual(&[]));
+}
+
+#[test]
+fn test_is_textual_includes_structured_suffixes() {
+    assert!(MimeType::new("image/svg+xml").unwrap().is_textual(&[]));
+    assert!(MimeType::new("application/ld+json").unwrap().is_textual(&[]));
+}
+
+#[test]
+fn test_is_textual_excludes_binary_types() {
+    assert!(!MimeType::new("application/pdf").unwrap().is_textual(&textual_subtypes()));
+    assert!(!MimeType::new("image/png").unwrap().is_textual(&textual_subtypes()));
+}
+
+#[test]
+fn test_essence_eq_ignores_case_only() {
+    let pdf = MimeType::new("application/pdf").unwrap();
+    assert!(pdf.essence_eq(&MimeType::new("Application/PDF").unwrap()));
+    assert!(!pdf.essence_eq(&MimeType::new("application/x-pdf").unwrap()));
+}
@@ -3,3 +3,5 @@ mod path_tests;
 mod request_id_tests;
 mod mime_tests;
 mod auth_tests;
+mod byte_range_tests;
+mod data_uri_tests;
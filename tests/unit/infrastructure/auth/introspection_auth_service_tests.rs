@@ -0,0 +1,87 @@
+use axum::{Form, Json, Router, routing::post};
+use magicer::domain::errors::AuthenticationError;
+use magicer::domain::services::authentication_service::AuthenticationService;
+use magicer::domain::value_objects::auth::BasicAuthCredentials;
+use magicer::infrastructure::auth::basic_auth_service::BasicAuthService;
+use magicer::infrastructure::auth::introspection_auth_service::IntrospectionAuthService;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Spawn a fake RFC 7662 endpoint: `good-token` is active, anything else is
+/// inactive. Returns the URL and a counter of introspection calls.
+async fn spawn_introspection_backend() -> (String, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let app = Router::new().route(
+        "/introspect",
+        post(move |Form(form): Form<HashMap<String, String>>| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                if form.get("token").map(String::as_str) == Some("good-token") {
+                    Json(serde_json::json!({
+                        "active": true,
+                        "username": "svc-client",
+                        "scope": "analyze read_sandbox"
+                    }))
+                } else {
+                    Json(serde_json::json!({ "active": false }))
+                }
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}/introspect", addr), calls)
+}
+
+fn service(url: &str) -> IntrospectionAuthService {
+    let basic = Arc::new(BasicAuthService::new("admin", "secret"));
+    IntrospectionAuthService::new(basic, url, Duration::from_secs(60)).unwrap()
+}
+
+#[tokio::test]
+async fn test_authorize_active_token_returns_principal() {
+    let (url, calls) = spawn_introspection_backend().await;
+    let service = service(&url);
+
+    let principal = service.authorize("good-token").await.unwrap();
+    assert_eq!(principal.username(), "svc-client");
+    assert!(principal.has_scope("analyze"));
+    assert!(principal.has_scope("read_sandbox"));
+    assert!(!principal.has_scope("admin"));
+
+    // Second lookup is served from the cache.
+    service.authorize("good-token").await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_authorize_inactive_token_rejected() {
+    let (url, _) = spawn_introspection_backend().await;
+    let service = service(&url);
+
+    let result = service.authorize("revoked-token").await;
+    assert_eq!(result, Err(AuthenticationError::InvalidCredentials));
+}
+
+#[tokio::test]
+async fn test_authorize_unreachable_backend_is_internal_error() {
+    let service = service("http://127.0.0.1:1/introspect");
+
+    let result = service.authorize("good-token").await;
+    assert!(matches!(result, Err(AuthenticationError::InternalError(_))));
+}
+
+#[tokio::test]
+async fn test_basic_credentials_delegate_to_inner_service() {
+    let service = service("http://127.0.0.1:1/introspect");
+
+    let valid = BasicAuthCredentials::new("admin", "secret").unwrap();
+    let invalid = BasicAuthCredentials::new("admin", "wrong").unwrap();
+    assert!(service.verify_credentials(&valid).await.is_ok());
+    assert!(service.verify_credentials(&invalid).await.is_err());
+}
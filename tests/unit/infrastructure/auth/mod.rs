@@ -1 +1,2 @@
-mod basic_auth_service_tests;
+pub mod basic_auth_service_tests;
+pub mod introspection_auth_service_tests;
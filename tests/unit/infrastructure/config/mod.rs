@@ -1,2 +1,3 @@
 pub mod server_config_tests;
 pub mod operational_integrity_tests;
+pub mod startup_banner_tests;
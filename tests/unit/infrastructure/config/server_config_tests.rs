@@ -23,6 +23,8 @@ fn test_load_from_toml() {
 [server]
 host = "192.168.1.1"
 port = 9090
+tcp_nodelay = true
+reuse_port = true
 
 [analysis]
 large_file_threshold_mb = 5
@@ -33,6 +35,8 @@ large_file_threshold_mb = 5
     
     assert_eq!(config.server.host, "192.168.1.1");
     assert_eq!(config.server.port, 9090);
+    assert!(config.server.tcp_nodelay);
+    assert!(config.server.reuse_port);
     assert_eq!(config.analysis.large_file_threshold_mb, 5);
     // Missing fields should be default
     assert_eq!(config.analysis.write_buffer_size_kb, 64);
@@ -103,6 +107,60 @@ fn test_validate_invalid_host_returns_error() {
     assert!(matches!(result.unwrap_err(), ValidationError::EmptyValue));
 }
 
+#[test]
+fn test_bind_addr_ipv4() {
+    let mut config = ServerConfig::default();
+    config.server.host = "0.0.0.0".to_string();
+    config.server.port = 9000;
+
+    let addr = config.bind_addr().unwrap();
+    assert!(addr.is_ipv4());
+    assert_eq!(addr.to_string(), "0.0.0.0:9000");
+}
+
+#[test]
+fn test_bind_addr_ipv6() {
+    let mut config = ServerConfig::default();
+    config.server.host = "::".to_string();
+    config.server.port = 9000;
+    assert_eq!(config.bind_addr().unwrap().to_string(), "[::]:9000");
+
+    config.server.host = "[::1]".to_string();
+    let addr = config.bind_addr().unwrap();
+    assert!(addr.is_ipv6());
+    assert_eq!(addr.to_string(), "[::1]:9000");
+}
+
+#[test]
+#[serial]
+fn test_validate_malformed_host_returns_invalid_address() {
+    let mut config = ServerConfig::default();
+    config.server.host = "not a host!".to_string();
+
+    assert_eq!(config.bind_addr(), Err(ValidationError::InvalidAddress));
+    assert_eq!(config.validate(), Err(ValidationError::InvalidAddress));
+}
+
+#[test]
+#[serial]
+fn test_validate_route_prefix() {
+    let mut config = ServerConfig::default();
+
+    config.server.route_prefix = Some("/magicer".to_string());
+    assert_eq!(config.validate(), Ok(()));
+
+    for (prefix, expected) in [
+        ("magicer", ValidationError::InvalidPath),
+        ("/", ValidationError::InvalidPath),
+        ("/magicer/", ValidationError::InvalidPath),
+        ("/magicer?x=1", ValidationError::InvalidCharacter),
+        ("/magicer#top", ValidationError::InvalidCharacter),
+    ] {
+        config.server.route_prefix = Some(prefix.to_string());
+        assert_eq!(config.validate(), Err(expected), "{}", prefix);
+    }
+}
+
 #[test]
 fn test_get_free_space_mb() {
     let config = ServerConfig::default();
@@ -110,3 +168,126 @@ fn test_get_free_space_mb() {
     // We expect some free space on /tmp in the sandbox
     assert!(free_space > 0);
 }
+
+#[test]
+fn test_magic_timeout_for_prefers_longest_pattern() {
+    use magicer::domain::value_objects::mime_type::MimeType;
+
+    let mut config = ServerConfig::default();
+    config.magic.timeouts.insert("application/*".to_string(), 10);
+    config.magic.timeouts.insert("application/x-*".to_string(), 20);
+    config.magic.timeouts.insert("application/x-xz".to_string(), 30);
+
+    let timeout_for = |mime: &str| config.magic.timeout_for(&MimeType::try_from(mime).unwrap());
+    assert_eq!(timeout_for("application/gzip"), Some(10));
+    assert_eq!(timeout_for("application/x-bzip2"), Some(20));
+    assert_eq!(timeout_for("application/x-xz"), Some(30));
+    assert_eq!(timeout_for("text/plain"), None);
+    assert_eq!(config.magic.max_timeout_override(), 30);
+}
+
+#[test]
+#[serial]
+fn test_validate_all_reports_every_error() {
+    let mut config = ServerConfig::default();
+    config.server.port = 0;
+    config.server.host = "".to_string();
+    config.logging.format = "xml".to_string();
+    config.sandbox.base_dir = "/proc/magicer_missing_sandbox".to_string();
+    config.auth.username = "admin".to_string();
+
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(
+        errors.0,
+        vec![
+            ("server.port", ValidationError::InvalidPath),
+            ("server.host", ValidationError::EmptyValue),
+            ("sandbox.base_dir", ValidationError::FileNotFound),
+            ("logging.format", ValidationError::UnsupportedValue),
+            ("auth.password", ValidationError::EmptyValue),
+        ]
+    );
+    assert!(errors.to_string().starts_with("5 invalid setting(s): server.port:"));
+    assert_eq!(config.validate(), Err(ValidationError::InvalidPath));
+}
+
+#[test]
+fn test_request_timeout_must_exceed_analysis_timeout() {
+    let mut config = ServerConfig::default();
+    assert_eq!(config.server.timeouts.request_timeout_secs(), 120);
+    config.server.timeouts.request_timeout_secs = Some(config.server.timeouts.analysis_timeout_secs);
+
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(
+        errors.0,
+        vec![("server.timeouts.request_timeout_secs", ValidationError::UnsupportedValue)]
+    );
+}
+
+#[test]
+fn test_unset_request_timeout_follows_long_analysis_timeout() {
+    // A configuration written before `request_timeout_secs` existed.
+    let mut config = ServerConfig::default();
+    config.server.timeouts = toml::from_str("analysis_timeout_secs = 300").unwrap();
+
+    assert!(config.validate_all().is_ok());
+    assert_eq!(config.server.timeouts.request_timeout_secs(), 600);
+
+    config.server.timeouts.request_timeout_secs = Some(400);
+    assert!(config.validate_all().is_ok());
+    assert_eq!(config.server.timeouts.request_timeout_secs(), 400);
+}
+
+#[test]
+fn test_overload_policy_must_be_queue_or_shed() {
+    let mut config = ServerConfig::default();
+    assert_eq!(config.server.overload_policy, "queue");
+    config.server.overload_policy = "shed".to_string();
+    assert!(config.validate_all().is_ok());
+
+    config.server.overload_policy = "drop".to_string();
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(errors.0, vec![("server.overload_policy", ValidationError::UnsupportedValue)]);
+}
+
+#[test]
+fn test_timeout_status_code_must_be_a_timeout_status() {
+    let mut config = ServerConfig::default();
+    assert_eq!(config.server.timeouts.timeout_status_code, 504);
+    config.server.timeouts.timeout_status_code = 408;
+    assert!(config.validate_all().is_ok());
+
+    config.server.timeouts.timeout_status_code = 500;
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(
+        errors.0,
+        vec![("server.timeouts.timeout_status_code", ValidationError::UnsupportedValue)]
+    );
+}
+
+#[test]
+fn test_unknown_type_alias_must_be_a_mime_type() {
+    let mut config = ServerConfig::default();
+    config.analysis.unknown_type_alias = Some("application/x-unknown".to_string());
+    assert!(config.validate_all().is_ok());
+
+    config.analysis.unknown_type_alias = Some("unknown".to_string());
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(errors.0.len(), 1);
+    assert_eq!(errors.0[0].0, "analysis.unknown_type_alias");
+}
+
+#[test]
+fn test_webhook_max_retries_is_bounded() {
+    let mut config = ServerConfig::default();
+    config.integrations.webhook = Some(
+        toml::from_str("url = \"http://127.0.0.1:9/hook\"\nmax_retries = 10").unwrap(),
+    );
+    assert!(config.validate_all().is_ok());
+
+    config.integrations.webhook = Some(
+        toml::from_str("url = \"http://127.0.0.1:9/hook\"\nmax_retries = 4294967295").unwrap(),
+    );
+    let errors = config.validate_all().unwrap_err();
+    assert_eq!(errors.0, vec![("integrations.webhook.max_retries", ValidationError::UnsupportedValue)]);
+}
@@ -0,0 +1,32 @@
+use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::config::startup_banner::StartupBanner;
+
+#[test]
+fn test_banner_redacts_password_and_reports_settings() {
+    let mut config = ServerConfig::default();
+    config.auth.username = "operator".to_string();
+    config.auth.password = "hunter2-secret".to_string();
+    config.analysis.large_file_threshold_mb = 42;
+    config.magic.database_path = Some("/opt/magic.mgc".to_string());
+
+    let banner = StartupBanner::new(&config, "127.0.0.1:8080".parse().unwrap());
+    let rendered = format!("{:?}", banner);
+
+    assert!(!rendered.contains("hunter2-secret"));
+    assert_eq!(banner.bind_addr.to_string(), "127.0.0.1:8080");
+    assert_eq!(banner.auth_scheme, "basic");
+    assert_eq!(banner.auth_username, "operator");
+    assert_eq!(banner.large_file_threshold_mb, 42);
+    assert_eq!(banner.temp_dir, config.analysis.temp_dir);
+    assert_eq!(banner.magic_database, "/opt/magic.mgc");
+    assert_eq!(banner.max_connections, config.server.max_connections);
+}
+
+#[test]
+fn test_banner_reports_bearer_when_introspection_enabled() {
+    let mut config = ServerConfig::default();
+    config.auth.introspection_url = Some("https://idp.example/introspect".to_string());
+
+    let banner = StartupBanner::new(&config, "127.0.0.1:8080".parse().unwrap());
+    assert_eq!(banner.auth_scheme, "basic+bearer");
+}
@@ -1,3 +1,5 @@
 pub mod path_sandbox_tests;
 pub mod temp_file_handler_tests;
 pub mod mmap_tests;
+pub mod temp_storage_service_tests;
+pub mod tiered_temp_storage_tests;
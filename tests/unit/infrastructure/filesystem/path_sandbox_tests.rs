@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use magicer::domain::errors::ValidationError;
 use magicer::domain::services::sandbox_service::SandboxService;
 use magicer::infrastructure::filesystem::sandbox::PathSandbox;
 use magicer::domain::value_objects::path::RelativePath;
@@ -24,3 +25,80 @@ fn test_sandbox_within_boundary() {
     let result = sandbox.resolve_path(&relative_path).unwrap();
     assert!(result.starts_with(&base_dir));
 }
+
+#[test]
+fn test_sandbox_denied_subpath_rejected() {
+    let sandbox = PathSandbox::new(PathBuf::from("/tmp/sandbox"))
+        .with_denied_subpaths(&["private", "/keys/"]);
+
+    for path in ["private/report.pdf", "private/a/b.txt", "private", "keys/id.pem"] {
+        let relative_path = RelativePath::new(path).unwrap();
+        assert_eq!(
+            sandbox.resolve_path(&relative_path),
+            Err(ValidationError::PermissionDenied),
+            "{path} should be denied"
+        );
+    }
+}
+
+#[test]
+fn test_sandbox_denied_subpath_allows_sibling() {
+    let base_dir = PathBuf::from("/tmp/sandbox");
+    let sandbox = PathSandbox::new(base_dir.clone()).with_denied_subpaths(&["private"]);
+
+    for path in ["private-notes/report.pdf", "public/private/report.pdf"] {
+        let relative_path = RelativePath::new(path).unwrap();
+        assert_eq!(sandbox.resolve_path(&relative_path), Ok(base_dir.join(path)));
+    }
+}
+
+#[test]
+fn test_sandbox_deny_symlinks_rejects_symlinked_path() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("real.txt"), b"hello").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real.txt"), dir.path().join("link.txt")).unwrap();
+    let sandbox = PathSandbox::new(dir.path().to_path_buf()).with_deny_symlinks(true);
+
+    let link = RelativePath::new("link.txt").unwrap();
+    let real = RelativePath::new("real.txt").unwrap();
+    assert_eq!(sandbox.resolve_path(&link), Err(ValidationError::PermissionDenied));
+    assert_eq!(sandbox.resolve_path(&real), Ok(dir.path().join("real.txt")));
+    assert_eq!(
+        sandbox.resolve_path_following_symlinks(&link),
+        Ok(dir.path().join("link.txt"))
+    );
+}
+
+#[test]
+fn test_sandbox_follows_symlinks_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("real")).unwrap();
+    std::fs::write(dir.path().join("real/file.txt"), b"hello").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("alias")).unwrap();
+    let sandbox = PathSandbox::new(dir.path().to_path_buf());
+
+    let relative_path = RelativePath::new("alias/file.txt").unwrap();
+    assert_eq!(
+        sandbox.resolve_path(&relative_path),
+        Ok(dir.path().join("alias/file.txt"))
+    );
+}
+
+#[test]
+fn test_sandbox_denied_subpath_ignores_case_when_case_insensitive() {
+    let base_dir = PathBuf::from("/tmp/sandbox");
+    let sensitive = PathSandbox::new(base_dir.clone()).with_denied_subpaths(&["Private"]);
+    let insensitive = PathSandbox::new(base_dir.clone())
+        .with_denied_subpaths(&["Private"])
+        .with_case_insensitive(true);
+
+    let relative_path = RelativePath::new("PRIVATE/report.pdf").unwrap();
+    assert_eq!(
+        sensitive.resolve_path(&relative_path),
+        Ok(base_dir.join("PRIVATE/report.pdf"))
+    );
+    assert_eq!(
+        insensitive.resolve_path(&relative_path),
+        Err(ValidationError::PermissionDenied)
+    );
+}
@@ -0,0 +1,19 @@
+use magicer::domain::services::temp_storage::TempStorageService;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use std::io::Read;
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_read_handle_reuses_open_descriptor() {
+    let dir = tempdir().unwrap();
+    let storage = FsTempStorageService::new(dir.path().to_path_buf());
+    let mut tf = storage.create_temp_file().await.unwrap();
+    tf.write(b"%PDF-1.4").await.unwrap();
+    tf.sync().await.unwrap();
+
+    // Opening by path would fail now; the descriptor still reaches the data.
+    std::fs::remove_file(tf.path()).unwrap();
+    let mut content = Vec::new();
+    tf.read_handle().await.unwrap().read_to_end(&mut content).unwrap();
+    assert_eq!(content, b"%PDF-1.4");
+}
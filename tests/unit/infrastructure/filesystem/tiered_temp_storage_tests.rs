@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use magicer::domain::services::temp_storage::{TempStorageService, TemporaryFile};
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use magicer::infrastructure::filesystem::tiered_temp_storage::TieredTempStorageService;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::{TempDir, tempdir};
+
+/// Counts the files it hands out, delegating to a real directory.
+struct SpyStorage {
+    inner: FsTempStorageService,
+    created: AtomicUsize,
+}
+
+impl SpyStorage {
+    fn new(dir: &TempDir) -> Arc<Self> {
+        Arc::new(Self {
+            inner: FsTempStorageService::new(dir.path().to_path_buf()),
+            created: AtomicUsize::new(0),
+        })
+    }
+
+    fn created(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl TempStorageService for SpyStorage {
+    async fn create_temp_file(&self) -> Result<Box<dyn TemporaryFile>, std::io::Error> {
+        self.created.fetch_add(1, Ordering::SeqCst);
+        self.inner.create_temp_file().await
+    }
+}
+
+fn tiered(
+    threshold: u64,
+) -> (
+    TieredTempStorageService,
+    Arc<SpyStorage>,
+    Arc<SpyStorage>,
+    [TempDir; 2],
+) {
+    let dirs = [tempdir().unwrap(), tempdir().unwrap()];
+    let small = SpyStorage::new(&dirs[0]);
+    let large = SpyStorage::new(&dirs[1]);
+    let service = TieredTempStorageService::new(small.clone(), large.clone(), threshold);
+    (service, small, large, dirs)
+}
+
+#[tokio::test]
+async fn test_small_declared_size_uses_small_backend() {
+    let (service, small, large, dirs) = tiered(1024);
+
+    let tf = service.create_temp_file_sized(Some(1024)).await.unwrap();
+
+    assert_eq!((small.created(), large.created()), (1, 0));
+    assert!(tf.path().starts_with(dirs[0].path()));
+}
+
+#[tokio::test]
+async fn test_large_declared_size_uses_large_backend() {
+    let (service, small, large, dirs) = tiered(1024);
+
+    let tf = service.create_temp_file_sized(Some(1025)).await.unwrap();
+
+    assert_eq!((small.created(), large.created()), (0, 1));
+    assert!(tf.path().starts_with(dirs[1].path()));
+}
+
+#[tokio::test]
+async fn test_unknown_size_uses_large_backend() {
+    let (service, small, large, _dirs) = tiered(1024);
+
+    service.create_temp_file_sized(None).await.unwrap();
+    service.create_temp_file().await.unwrap();
+
+    assert_eq!((small.created(), large.created()), (0, 2));
+}
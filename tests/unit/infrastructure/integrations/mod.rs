@@ -0,0 +1,2 @@
+pub mod result_signature_tests;
+pub mod webhook_result_sink_tests;
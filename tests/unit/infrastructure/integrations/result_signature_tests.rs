@@ -0,0 +1,21 @@
+use magicer::infrastructure::integrations::result_signature::{canonical_json, sign_payload};
+use magicer::infrastructure::integrations::webhook_result_sink::sign;
+use serde_json::json;
+
+#[test]
+fn test_canonical_json_sorts_keys_at_every_level() {
+    let value = json!({"b": 1, "a": {"z": [true, null], "y": "é\n"}});
+
+    assert_eq!(canonical_json(&value), r#"{"a":{"y":"é\n","z":[true,null]},"b":1}"#);
+}
+
+#[test]
+fn test_sign_payload_ignores_existing_signature() {
+    let unsigned = json!({"request_id": "r1", "result": {"mime_type": "application/pdf"}});
+    let mut signed = unsigned.clone();
+    signed["signature"] = json!("sha256=stale");
+
+    let expected = sign("k", canonical_json(&unsigned).as_bytes());
+    assert_eq!(sign_payload("k", &unsigned), expected);
+    assert_eq!(sign_payload("k", &signed), expected);
+}
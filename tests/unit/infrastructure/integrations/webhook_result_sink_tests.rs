@@ -0,0 +1,56 @@
+use magicer::domain::entities::magic_result::MagicResult;
+use magicer::domain::services::result_sink::ResultSink;
+use magicer::domain::value_objects::filename::WindowsCompatibleFilename;
+use magicer::domain::value_objects::mime_type::MimeType;
+use magicer::domain::value_objects::request_id::RequestId;
+use magicer::infrastructure::integrations::webhook_result_sink::{WebhookResultSink, retry_delay, sign};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[test]
+fn test_sign_matches_rfc4231_vector() {
+    // RFC 4231 test case 2.
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn test_retry_delay_doubles_up_to_cap() {
+    assert_eq!(retry_delay(1), Duration::from_millis(500));
+    assert_eq!(retry_delay(3), Duration::from_secs(2));
+    assert_eq!(retry_delay(40), Duration::from_secs(60));
+    assert_eq!(retry_delay(u32::MAX), Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn test_results_beyond_max_pending_are_dropped() {
+    // Accepts connections and never answers, like a hung webhook.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&accepted);
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            held.push(stream);
+        }
+    });
+
+    let sink = WebhookResultSink::new(&url, None, 0).unwrap().with_max_pending(1);
+    let result = MagicResult::new(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new("a.pdf").unwrap(),
+        MimeType::try_from("application/pdf").unwrap(),
+        "PDF document".to_string(),
+    );
+    for _ in 0..3 {
+        sink.publish(&result);
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,90 @@
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+use magicer::infrastructure::magic::archive_peek::{PEEK_PREFIX_BYTES, peek_tar, peek_zip};
+use std::io::Write;
+
+/// A zip holding one member whose data is `body`. The CRC and uncompressed
+/// size are those of a stored member.
+pub fn build_zip(name: &str, method: u16, body: &[u8]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    zip.extend_from_slice(b"PK\x03\x04");
+    zip.extend_from_slice(&20u16.to_le_bytes()); // version needed
+    zip.extend_from_slice(&0u16.to_le_bytes()); // flags
+    zip.extend_from_slice(&method.to_le_bytes());
+    zip.extend_from_slice(&[0; 4]); // mod time/date
+    zip.extend_from_slice(&crc32fast::hash(body).to_le_bytes());
+    zip.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip.extend_from_slice(name.as_bytes());
+    zip.extend_from_slice(body);
+    zip
+}
+
+pub fn build_tar(name: &str, body: &[u8]) -> Vec<u8> {
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", body.len()).as_bytes());
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    let mut tar = header.to_vec();
+    tar.extend_from_slice(body);
+    tar.resize(tar.len().div_ceil(512) * 512 + 1024, 0);
+    tar
+}
+
+#[test]
+fn test_peek_zip_stored_entry() {
+    let zip = build_zip("report.pdf", 0, b"%PDF-1.4\n");
+
+    let peek = peek_zip(&zip).unwrap();
+    assert_eq!(peek.name, "report.pdf");
+    assert_eq!(peek.prefix, b"%PDF-1.4\n");
+}
+
+#[test]
+fn test_peek_zip_deflated_entry_is_bounded() {
+    let content = vec![b'A'; PEEK_PREFIX_BYTES * 4];
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&content).unwrap();
+    let zip = build_zip("big.txt", 8, &encoder.finish().unwrap());
+
+    let peek = peek_zip(&zip).unwrap();
+    assert_eq!(peek.name, "big.txt");
+    assert_eq!(peek.prefix.len(), PEEK_PREFIX_BYTES);
+}
+
+#[test]
+fn test_peek_zip_rejects_unsupported_method() {
+    let zip = build_zip("data.bin", 14, b"lzma");
+    assert!(peek_zip(&zip).is_none());
+}
+
+#[test]
+fn test_peek_tar_first_entry() {
+    let tar = build_tar("docs/report.pdf", b"%PDF-1.4\n");
+
+    let peek = peek_tar(&tar).unwrap();
+    assert_eq!(peek.name, "docs/report.pdf");
+    assert_eq!(peek.prefix, b"%PDF-1.4\n");
+}
+
+#[test]
+fn test_peek_tar_rejects_bad_checksum() {
+    let mut tar = build_tar("report.pdf", b"%PDF-1.4\n");
+    tar[0] = b'X';
+    assert!(peek_tar(&tar).is_none());
+}
+
+#[test]
+fn test_peek_rejects_truncated_input() {
+    assert!(peek_zip(b"PK\x03\x04").is_none());
+    assert!(peek_tar(&[0u8; 100]).is_none());
+}
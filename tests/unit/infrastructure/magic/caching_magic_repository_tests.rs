@@ -0,0 +1,84 @@
+use futures_util::future::BoxFuture;
+use magicer::domain::errors::MagicError;
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::domain::value_objects::mime_type::MimeType;
+use magicer::infrastructure::magic::caching_magic_repository::CachingMagicRepository;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+struct CountingRepo {
+    calls: AtomicUsize,
+}
+
+impl MagicRepository for CountingRepo {
+    fn analyze_buffer<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok((MimeType::try_from("application/pdf").unwrap(), "PDF document".to_string()))
+        })
+    }
+
+    fn analyze_with_rules<'a>(
+        &'a self,
+        _rule_path: &'a std::path::Path,
+        _data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async { Ok((MimeType::try_from("text/x-custom").unwrap(), "custom rule".to_string())) })
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_identical_analyses_share_one_call() {
+    let inner = Arc::new(CountingRepo { calls: AtomicUsize::new(0) });
+    let repo = Arc::new(CachingMagicRepository::new(inner.clone()));
+
+    let mut handles = vec![];
+    for _ in 0..10 {
+        let r = repo.clone();
+        handles.push(tokio::spawn(async move {
+            r.analyze_buffer(b"%PDF-1.4 identical", "test.pdf").await.unwrap()
+        }));
+    }
+
+    for h in handles {
+        let (mime, _) = h.await.unwrap();
+        assert_eq!(mime.as_str(), "application/pdf");
+    }
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_distinct_or_sequential_analyses_are_not_shared() {
+    let inner = Arc::new(CountingRepo { calls: AtomicUsize::new(0) });
+    let repo = CachingMagicRepository::new(inner.clone());
+
+    let (a, b) = tokio::join!(
+        repo.analyze_buffer(b"first", "a.bin"),
+        repo.analyze_buffer(b"second", "b.bin"),
+    );
+    assert!(a.is_ok() && b.is_ok());
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+    // Completed flights are not retained.
+    repo.analyze_buffer(b"first", "a.bin").await.unwrap();
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_custom_rules_are_forwarded() {
+    let inner = Arc::new(CountingRepo { calls: AtomicUsize::new(0) });
+    let repo = CachingMagicRepository::new(inner);
+
+    let (mime, description) = repo
+        .analyze_with_rules(std::path::Path::new("/tmp/rules.magic"), b"custom")
+        .await
+        .unwrap();
+    assert_eq!(mime.as_str(), "text/x-custom");
+    assert_eq!(description, "custom rule");
+}
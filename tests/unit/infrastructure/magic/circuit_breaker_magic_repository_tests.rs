@@ -0,0 +1,221 @@
+use futures_util::future::BoxFuture;
+use magicer::domain::errors::MagicError;
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::domain::value_objects::mime_type::MimeType;
+use magicer::infrastructure::magic::circuit_breaker_magic_repository::{
+    BreakerState, CircuitBreakerMagicRepository,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Fails slowly while `failing` is set, like libmagic with a broken database.
+struct FlakyRepo {
+    calls: AtomicUsize,
+    failing: AtomicBool,
+}
+
+impl FlakyRepo {
+    fn new(failing: bool) -> Arc<Self> {
+        Arc::new(Self {
+            calls: AtomicUsize::new(0),
+            failing: AtomicBool::new(failing),
+        })
+    }
+}
+
+impl MagicRepository for FlakyRepo {
+    fn analyze_buffer<'a>(
+        &'a self,
+        _data: &'a [u8],
+        filename: &'a str,
+    ) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if filename == "missing.bin" {
+                Err(MagicError::FileNotFound(filename.to_string()))
+            } else if self.failing.load(Ordering::SeqCst) {
+                Err(MagicError::AnalysisFailed("corrupt database".to_string()))
+            } else {
+                Ok((
+                    MimeType::try_from("application/pdf").unwrap(),
+                    "PDF document".to_string(),
+                ))
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_breaker_opens_after_consecutive_failures_and_fails_fast() {
+    let inner = FlakyRepo::new(true);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        3,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    );
+
+    for _ in 0..3 {
+        let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+        assert!(matches!(err, MagicError::AnalysisFailed(_)));
+    }
+    assert_eq!(repo.state(), BreakerState::Open);
+
+    let start = Instant::now();
+    let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    assert!(matches!(err, MagicError::Unavailable(_)));
+    assert!(start.elapsed() < Duration::from_millis(50));
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_success_and_missing_files_do_not_trip_breaker() {
+    let inner = FlakyRepo::new(true);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        2,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    );
+
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    inner.failing.store(false, Ordering::SeqCst);
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap();
+    inner.failing.store(true, Ordering::SeqCst);
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    repo.analyze_buffer(b"data", "missing.bin")
+        .await
+        .unwrap_err();
+    assert_eq!(repo.state(), BreakerState::Closed);
+}
+
+#[tokio::test]
+async fn test_probe_closes_or_reopens_breaker() {
+    let inner = FlakyRepo::new(true);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        1,
+        Duration::from_secs(30),
+        Duration::from_millis(100),
+    );
+
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    assert_eq!(repo.state(), BreakerState::Open);
+
+    // Failed probe keeps the breaker open.
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    assert!(matches!(err, MagicError::AnalysisFailed(_)));
+    assert_eq!(repo.state(), BreakerState::Open);
+
+    // Successful probe closes it.
+    inner.failing.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap();
+    assert_eq!(repo.state(), BreakerState::Closed);
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_only_one_probe_while_half_open() {
+    let inner = FlakyRepo::new(true);
+    let repo = Arc::new(CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        1,
+        Duration::from_secs(30),
+        Duration::from_millis(100),
+    ));
+
+    repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    inner.failing.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(120)).await;
+
+    let probe = {
+        let repo = repo.clone();
+        tokio::spawn(async move { repo.analyze_buffer(b"data", "test.bin").await })
+    };
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(repo.state(), BreakerState::HalfOpen);
+    let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    assert!(matches!(err, MagicError::Unavailable(_)));
+
+    probe.await.unwrap().unwrap();
+    assert_eq!(repo.state(), BreakerState::Closed);
+}
+
+#[tokio::test]
+async fn test_calls_dropped_by_caller_timeout_trip_breaker() {
+    // Succeeds, but only after 50ms, past the caller's 10ms deadline.
+    let inner = FlakyRepo::new(false);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        2,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    )
+    // The caller's deadline fires first, as it does when both are the
+    // analysis timeout.
+    .with_call_timeout(Duration::from_millis(20));
+
+    for _ in 0..2 {
+        let timed_out =
+            tokio::time::timeout(Duration::from_millis(10), repo.analyze_buffer(b"data", "test.bin")).await;
+        assert!(timed_out.is_err());
+    }
+    assert_eq!(repo.state(), BreakerState::Open);
+    let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+    assert!(matches!(err, MagicError::Unavailable(_)));
+    assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_call_timeout_is_enforced_and_trips_breaker() {
+    let inner = FlakyRepo::new(false);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        2,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    )
+    .with_call_timeout(Duration::from_millis(10));
+
+    for _ in 0..2 {
+        let err = repo.analyze_buffer(b"data", "test.bin").await.unwrap_err();
+        assert!(matches!(err, MagicError::AnalysisFailed(_)));
+    }
+    assert_eq!(repo.state(), BreakerState::Open);
+}
+
+#[tokio::test]
+async fn test_calls_abandoned_before_their_timeout_do_not_trip_breaker() {
+    // Like a client disconnecting, or a cancelled singleflight driver.
+    let inner = FlakyRepo::new(false);
+    let repo = CircuitBreakerMagicRepository::new(
+        inner.clone(),
+        1,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    )
+    .with_call_timeout(Duration::from_secs(5));
+
+    for _ in 0..3 {
+        let abandoned =
+            tokio::time::timeout(Duration::from_millis(10), repo.analyze_buffer(b"data", "test.bin")).await;
+        assert!(abandoned.is_err());
+    }
+    assert_eq!(repo.state(), BreakerState::Closed);
+
+    // Without a call timeout no drop is taken as one.
+    let repo = CircuitBreakerMagicRepository::new(
+        inner,
+        1,
+        Duration::from_secs(30),
+        Duration::from_secs(60),
+    );
+    let abandoned =
+        tokio::time::timeout(Duration::from_millis(10), repo.analyze_buffer(b"data", "test.bin")).await;
+    assert!(abandoned.is_err());
+    assert_eq!(repo.state(), BreakerState::Closed);
+}
@@ -0,0 +1,152 @@
+use futures_util::future::BoxFuture;
+use magicer::domain::errors::MagicError;
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::domain::value_objects::mime_type::MimeType;
+use magicer::infrastructure::magic::experiment_magic_repository::{
+    ExperimentArm, ExperimentMagicRepository,
+};
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use std::path::Path;
+use std::sync::Arc;
+
+fn experiment(fraction: f64) -> ExperimentMagicRepository {
+    let database = |mime_type: &str| {
+        Arc::new(
+            FakeMagicRepository::builder()
+                .with_mapping(b"", mime_type, mime_type)
+                .build(),
+        ) as Arc<dyn MagicRepository>
+    };
+    ExperimentMagicRepository::new(
+        database("application/x-control"),
+        database("application/x-candidate"),
+        fraction,
+    )
+}
+
+#[test]
+fn test_split_follows_fraction() {
+    let repo = experiment(0.3);
+    let candidates = (0..10_000u32)
+        .filter(|i| repo.assign(&i.to_le_bytes()) == ExperimentArm::Candidate)
+        .count();
+    assert!((2_700..=3_300).contains(&candidates), "{} of 10000", candidates);
+}
+
+#[test]
+fn test_edge_fractions_route_everything_to_one_database() {
+    assert_eq!(experiment(0.0).assign(b"content"), ExperimentArm::Control);
+    assert_eq!(experiment(1.0).assign(b"content"), ExperimentArm::Candidate);
+}
+
+#[tokio::test]
+async fn test_same_content_always_hits_same_database() {
+    let repo = experiment(0.5);
+    for i in 0..50u32 {
+        let data = format!("content {}", i).into_bytes();
+        let arm = repo.assign(&data);
+        let expected = format!("application/x-{}", arm.name());
+        for _ in 0..5 {
+            let (mime_type, _) = repo.analyze_buffer(&data, "a.bin").await.unwrap();
+            assert_eq!(mime_type.as_str(), expected);
+            assert_eq!(repo.database_variant(&data), Some(arm.name()));
+        }
+    }
+}
+
+/// Answers every trait method with `<database>:<method>`, so a method the
+/// experiment does not override shows up as the default's delegate.
+struct MarkerRepo(&'static str);
+
+impl MarkerRepo {
+    fn detection(&self, method: &str) -> Result<(MimeType, String), MagicError> {
+        Ok((MimeType::try_from("application/pdf").unwrap(), format!("{}:{}", self.0, method)))
+    }
+}
+
+impl MagicRepository for MarkerRepo {
+    fn analyze_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move { self.detection("analyze_buffer") })
+    }
+
+    fn analyze_buffer_decompressed<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move { self.detection("analyze_buffer_decompressed") })
+    }
+
+    fn analyze_file<'a>(&'a self, _file: &'a std::fs::File, _filename: &'a str) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move { self.detection("analyze_file") })
+    }
+
+    fn describe_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<String, MagicError>> {
+        Box::pin(async move { Ok(format!("{}:describe_buffer", self.0)) })
+    }
+
+    fn detect_encoding<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<Option<String>, MagicError>> {
+        Box::pin(async move { Ok(Some(format!("{}:detect_encoding", self.0))) })
+    }
+
+    fn trace_buffer<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<String, MagicError>> {
+        Box::pin(async move { Ok(format!("{}:trace_buffer", self.0)) })
+    }
+
+    fn analyze_all_types<'a>(&'a self, _data: &'a [u8], _filename: &'a str) -> BoxFuture<'a, Result<Vec<MimeType>, MagicError>> {
+        Box::pin(async move { Ok(vec![MimeType::try_from(format!("application/x-{}-all", self.0).as_str()).unwrap()]) })
+    }
+
+    fn database_variant(&self, _data: &[u8]) -> Option<&'static str> {
+        Some("marker")
+    }
+
+    fn database_variant_for_file(&self, _file: &std::fs::File) -> Option<&'static str> {
+        Some("marker")
+    }
+
+    fn analyze_with_rules<'a>(&'a self, _rule_path: &'a Path, _data: &'a [u8]) -> BoxFuture<'a, Result<(MimeType, String), MagicError>> {
+        Box::pin(async move { self.detection("analyze_with_rules") })
+    }
+}
+
+#[tokio::test]
+async fn test_every_repository_method_is_routed() {
+    let repo = ExperimentMagicRepository::new(
+        Arc::new(MarkerRepo("control")),
+        Arc::new(MarkerRepo("candidate")),
+        1.0,
+    );
+    let file = tempfile::tempfile().unwrap();
+    let data = b"content";
+
+    assert_eq!(repo.analyze_buffer(data, "a").await.unwrap().1, "candidate:analyze_buffer");
+    assert_eq!(
+        repo.analyze_buffer_decompressed(data, "a").await.unwrap().1,
+        "candidate:analyze_buffer_decompressed"
+    );
+    assert_eq!(repo.analyze_file(&file, "a").await.unwrap().1, "candidate:analyze_file");
+    assert_eq!(repo.describe_buffer(data, "a").await.unwrap(), "candidate:describe_buffer");
+    assert_eq!(
+        repo.detect_encoding(data, "a").await.unwrap().as_deref(),
+        Some("candidate:detect_encoding")
+    );
+    assert_eq!(repo.trace_buffer(data, "a").await.unwrap(), "candidate:trace_buffer");
+    assert_eq!(
+        repo.analyze_all_types(data, "a").await.unwrap()[0].as_str(),
+        "application/x-candidate-all"
+    );
+    assert_eq!(repo.database_variant(data), Some("candidate"));
+    assert_eq!(repo.database_variant_for_file(&file), Some("candidate"));
+    assert_eq!(
+        repo.analyze_with_rules(Path::new("/tmp/rules.magic"), data).await.unwrap().1,
+        "control:analyze_with_rules"
+    );
+}
+
+#[test]
+fn test_same_file_always_hits_same_database() {
+    let repo = experiment(0.5);
+    let file = tempfile::tempfile().unwrap();
+    let arm = repo.assign_file(&file);
+    for _ in 0..5 {
+        assert_eq!(repo.assign_file(&file), arm);
+    }
+    assert_eq!(repo.database_variant_for_file(&file), Some(arm.name()));
+}
@@ -0,0 +1,47 @@
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn test_custom_mapping_is_detected() {
+    let repo = FakeMagicRepository::builder()
+        .with_mapping(b"MGCR", "application/x-magicer", "Magicer test format")
+        .build();
+
+    let (mime, desc) = repo.analyze_buffer(b"MGCR\x01\x02", "a.bin").await.unwrap();
+    assert_eq!(mime.as_str(), "application/x-magicer");
+    assert_eq!(desc, "Magicer test format");
+}
+
+#[tokio::test]
+async fn test_builder_without_defaults_ignores_builtin_types() {
+    let repo = FakeMagicRepository::builder().build();
+
+    let (mime, _) = repo.analyze_buffer(b"%PDF-1.4", "a.pdf").await.unwrap();
+    assert_eq!(mime.as_str(), "application/octet-stream");
+}
+
+#[tokio::test]
+async fn test_custom_mapping_takes_precedence_over_defaults() {
+    let repo = FakeMagicRepository::builder()
+        .with_defaults()
+        .with_mapping(b"%PDF-2", "application/x-pdf2", "PDF 2 document")
+        .build();
+
+    let (mime, _) = repo.analyze_buffer(b"%PDF-2.0", "a.pdf").await.unwrap();
+    assert_eq!(mime.as_str(), "application/x-pdf2");
+    let (mime, _) = repo.analyze_buffer(b"%PDF-1.4", "a.pdf").await.unwrap();
+    assert_eq!(mime.as_str(), "application/pdf");
+}
+
+#[tokio::test]
+async fn test_delay_applies_to_each_call() {
+    let repo = FakeMagicRepository::builder()
+        .with_defaults()
+        .with_delay(Duration::from_millis(50))
+        .build();
+
+    let start = Instant::now();
+    repo.analyze_buffer(b"%PDF-1.4", "a.pdf").await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
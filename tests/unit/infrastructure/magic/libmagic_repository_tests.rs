@@ -1,5 +1,9 @@
 use magicer::domain::repositories::magic_repository::MagicRepository;
 use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::infrastructure::config::server_config::MagicParams;
+use magicer::infrastructure::magic::libmagic_repository::LibmagicRepository;
+use magicer::infrastructure::magic::wrapper::library_version;
+use crate::infrastructure::magic::archive_peek_tests::build_tar;
 
 #[tokio::test]
 async fn test_analyze_buffer_pdf() {
@@ -30,3 +34,143 @@ async fn test_analyze_buffer_concurrent() {
         assert_eq!(mime.as_str(), "application/pdf");
     }
 }
+
+#[tokio::test]
+async fn test_bytes_max_hides_deep_magic() {
+    let tar = build_tar("hello.txt", b"hello world\n");
+
+    let repo = LibmagicRepository::new(false).unwrap();
+    let (mime, _) = repo.analyze_buffer(&tar, "a.tar").await.unwrap();
+    assert_eq!(mime.as_str(), "application/x-tar");
+
+    // The ustar signature sits at offset 257; a 256-byte window misses it.
+    let params = MagicParams {
+        bytes_max: Some(256),
+        ..MagicParams::default()
+    };
+    let repo = LibmagicRepository::with_params(false, &params).unwrap();
+    let (mime, _) = repo.analyze_buffer(&tar, "a.tar").await.unwrap();
+    assert_ne!(mime.as_str(), "application/x-tar");
+}
+
+#[tokio::test]
+async fn test_describe_buffer_returns_text_description() {
+    let repo = LibmagicRepository::new(false).unwrap();
+
+    let (mime, _) = repo.analyze_buffer(b"%PDF-1.4\n", "test.pdf").await.unwrap();
+    let description = repo.describe_buffer(b"%PDF-1.4\n", "test.pdf").await.unwrap();
+
+    assert_eq!(mime.as_str(), "application/pdf");
+    assert!(description.starts_with("PDF document"), "{}", description);
+}
+
+#[tokio::test]
+async fn test_detect_encoding_reports_charset() {
+    let repo = LibmagicRepository::new(false).unwrap();
+
+    let utf8 = repo.detect_encoding("héllo wörld\n".as_bytes(), "a.txt").await.unwrap();
+    let utf16 = repo
+        .detect_encoding(b"\xFF\xFEh\x00e\x00l\x00l\x00o\x00\n\x00", "a.txt")
+        .await
+        .unwrap();
+
+    assert_eq!(utf8.as_deref(), Some("utf-8"));
+    assert_eq!(utf16.as_deref(), Some("utf-16le"));
+}
+
+#[test]
+fn test_database_version_mismatch_is_explained() {
+    // A compiled database header (magic number, then format version) for a
+    // format version no libmagic uses.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("future.mgc");
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&0xF11E041Cu32.to_le_bytes());
+    bytes.extend_from_slice(&99u32.to_le_bytes());
+    bytes.resize(4096, 0);
+    std::fs::write(&path, bytes).unwrap();
+
+    let err = LibmagicRepository::with_database(true, &MagicParams::default(), path.to_str())
+        .err()
+        .expect("a version 99 database must not load");
+    let message = err.to_string();
+    assert!(message.contains("has format version 99"), "{}", message);
+    assert!(message.contains(&format!("libmagic {} expects version", library_version())), "{}", message);
+}
+
+#[tokio::test]
+async fn test_analyze_all_types_reports_each_match_once() {
+    let repo = LibmagicRepository::new(false).unwrap();
+
+    let types = repo.analyze_all_types(b"%PDF-1.4\n", "test.pdf").await.unwrap();
+
+    assert_eq!(types.first().map(|t| t.as_str()).as_deref(), Some("application/pdf"));
+    assert!(types.iter().all(|t| t.as_str() != "application/octet-stream"));
+    let mut unique = types.clone();
+    unique.dedup();
+    assert_eq!(unique, types);
+}
+
+#[tokio::test]
+async fn test_analyze_file_reads_whole_file_and_keeps_offset() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let repo = LibmagicRepository::new(false).unwrap();
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n").unwrap();
+    file.seek(SeekFrom::Start(4)).unwrap();
+
+    let (mime, _) = repo.analyze_file(&file, "doc.pdf").await.unwrap();
+
+    assert_eq!(mime.as_str(), "application/pdf");
+    assert_eq!(file.stream_position().unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_trace_buffer_captures_debug_output() {
+    let repo = LibmagicRepository::new(false).unwrap();
+    let pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n";
+
+    let trace = repo.trace_buffer(pdf, "doc.pdf").await.unwrap();
+
+    // Builds that print no debug output yield an empty trace rather than an
+    // error.
+    if !trace.is_empty() {
+        assert!(trace.contains("PDF"), "unexpected trace: {}", trace);
+    }
+    // MAGIC_DEBUG is cleared again once the trace is taken.
+    let (mime, description) = repo.analyze_buffer(pdf, "doc.pdf").await.unwrap();
+    assert_eq!(mime.as_str(), "application/pdf");
+    assert_eq!(description, "application/pdf");
+}
+
+#[test]
+fn test_resolve_database_picks_first_existing_search_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let (second, third) = (dir.path().join("second.mgc"), dir.path().join("third.mgc"));
+    std::fs::write(&second, b"").unwrap();
+    std::fs::write(&third, b"").unwrap();
+    let candidates: Vec<String> = [dir.path().join("missing.mgc"), second.clone(), third]
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    let chosen = LibmagicRepository::resolve_database(None, &candidates).unwrap();
+    assert_eq!(chosen.as_deref(), Some(second.to_str().unwrap()));
+
+    // An explicit database_path is used as given.
+    let chosen = LibmagicRepository::resolve_database(Some("/opt/magic.mgc"), &candidates).unwrap();
+    assert_eq!(chosen.as_deref(), Some("/opt/magic.mgc"));
+    assert_eq!(LibmagicRepository::resolve_database(None, &[]).unwrap(), None);
+}
+
+#[test]
+fn test_resolve_database_fails_when_no_search_path_exists() {
+    let candidates = vec!["/nonexistent/a.mgc".to_string(), "/nonexistent/b.mgc".to_string()];
+
+    let error = LibmagicRepository::resolve_database(None, &candidates).unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.contains("magic.database_search_paths"), "{}", message);
+    assert!(message.contains("/nonexistent/a.mgc, /nonexistent/b.mgc"), "{}", message);
+}
@@ -1,2 +1,8 @@
+pub mod archive_peek_tests;
+pub mod caching_magic_repository_tests;
+pub mod circuit_breaker_magic_repository_tests;
+pub mod experiment_magic_repository_tests;
+pub mod fake_magic_repository_tests;
 pub mod libmagic_repository_tests;
+pub mod reloadable_magic_repository_tests;
 pub mod wrapper_tests;
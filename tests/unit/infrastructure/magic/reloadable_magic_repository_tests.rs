@@ -0,0 +1,37 @@
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::domain::services::magic_reloader::MagicReloader;
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::infrastructure::magic::reloadable_magic_repository::ReloadableMagicRepository;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_in_flight_analysis_finishes_on_old_repository() {
+    let slow_old = FakeMagicRepository::builder()
+        .with_mapping(b"DATA", "application/x-old", "old")
+        .with_delay(Duration::from_millis(100))
+        .build();
+    let repo = Arc::new(ReloadableMagicRepository::new(
+        Arc::new(slow_old),
+        Box::new(|| {
+            Ok(Arc::new(
+                FakeMagicRepository::builder()
+                    .with_mapping(b"DATA", "application/x-new", "new")
+                    .build(),
+            ) as Arc<dyn MagicRepository>)
+        }),
+    ));
+
+    let in_flight = {
+        let repo = repo.clone();
+        tokio::spawn(async move { repo.analyze_buffer(b"DATA", "a.bin").await })
+    };
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(repo.reload().unwrap(), 2);
+
+    let (old, _) = in_flight.await.unwrap().unwrap();
+    assert_eq!(old.as_str(), "application/x-old");
+    let (new, _) = repo.analyze_buffer(b"DATA", "a.bin").await.unwrap();
+    assert_eq!(new.as_str(), "application/x-new");
+    assert_eq!(repo.version(), 2);
+}
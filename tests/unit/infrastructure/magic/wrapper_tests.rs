@@ -1,5 +1,6 @@
 use magicer::infrastructure::magic::wrapper::MagicCookie;
-use magicer::infrastructure::magic::ffi::MAGIC_MIME_TYPE;
+use magicer::domain::errors::MagicError;
+use magicer::infrastructure::magic::ffi::{MAGIC_CHECK, MAGIC_ERROR, MAGIC_MIME_TYPE, MAGIC_PARAM_BYTES_MAX};
 
 fn find_magic_db() -> Option<String> {
     // Check for compiled magic file in target directory
@@ -10,10 +11,10 @@ fn find_magic_db() -> Option<String> {
     ];
     
     for pattern in patterns {
-        if let Ok(paths) = glob::glob(pattern) {
-            if let Some(Ok(path)) = paths.into_iter().next() {
-                return Some(path.to_string_lossy().to_string());
-            }
+        if let Ok(paths) = glob::glob(pattern)
+            && let Some(Ok(path)) = paths.into_iter().next()
+        {
+            return Some(path.to_string_lossy().to_string());
         }
     }
     None
@@ -56,3 +57,65 @@ fn test_magic_cookie_error_handling() {
     let result = cookie.load(Some("non_existent.mgc"));
     assert!(result.is_err());
 }
+
+#[test]
+fn test_magic_cookie_compile_rule() {
+    let dir = tempfile::tempdir().unwrap();
+    let rule_path = dir.path().join("trivial.magic");
+    std::fs::write(
+        &rule_path,
+        "0\tstring\tMAGICERTEST\tMagicer test format\n!:mime\tapplication/x-magicer-test\n",
+    )
+    .unwrap();
+
+    let cookie = MagicCookie::compile_rule(MAGIC_MIME_TYPE, rule_path.to_str().unwrap())
+        .expect("Failed to compile rule");
+    assert_eq!(cookie.buffer(b"MAGICERTEST payload").unwrap(), "application/x-magicer-test");
+    // Only the custom rule is loaded, so ordinary formats are not recognised.
+    assert_ne!(cookie.buffer(b"%PDF-1.4\n").unwrap(), "application/pdf");
+    // Nothing is written next to the rule or into the working directory.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    assert!(!std::path::Path::new("trivial.magic.mgc").exists());
+}
+
+#[test]
+fn test_magic_cookie_set_param() {
+    let cookie = MagicCookie::open(MAGIC_MIME_TYPE).unwrap();
+    cookie.load(find_magic_db().as_deref()).unwrap();
+    cookie.set_param(MAGIC_PARAM_BYTES_MAX, 4096).unwrap();
+    assert!(cookie.set_param(9999, 1).is_err());
+}
+
+#[test]
+fn test_magic_cookie_check_valid_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let rule_path = dir.path().join("hello.magic");
+    std::fs::write(&rule_path, "0\tstring\tHELLO\tHello file\n!:mime\ttext/x-hello\n").unwrap();
+
+    let cookie = MagicCookie::open(MAGIC_CHECK).unwrap();
+    cookie.check(Some(rule_path.to_str().unwrap())).expect("valid database");
+}
+
+#[test]
+fn test_magic_cookie_check_broken_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let rule_path = dir.path().join("broken.magic");
+    std::fs::write(&rule_path, "0\tbogustype\tHELLO\tbroken\n").unwrap();
+
+    let cookie = MagicCookie::open(MAGIC_CHECK).unwrap();
+    let err = cookie.check(Some(rule_path.to_str().unwrap())).unwrap_err();
+    assert!(matches!(err, magicer::domain::errors::MagicError::DatabaseLoadFailed(_)));
+}
+
+#[test]
+fn test_magic_cookie_error_keeps_libmagic_detail() {
+    let cookie = MagicCookie::open(MAGIC_MIME_TYPE | MAGIC_ERROR).expect("Failed to open magic cookie");
+    cookie.load(find_magic_db().as_deref()).expect("Failed to load magic database");
+
+    let err = cookie.file("/nonexistent/magicer-missing.bin").unwrap_err();
+    let MagicError::AnalysisFailed(detail) = err else {
+        panic!("expected AnalysisFailed, got {:?}", err);
+    };
+    assert!(detail.contains("magicer-missing.bin"), "{detail}");
+    assert!(detail.contains("No such file or directory"), "{detail}");
+}
@@ -1,5 +1,8 @@
 pub mod auth;
 pub mod magic;
 pub mod filesystem;
+pub mod integrations;
+pub mod results;
 pub mod config;
 pub mod telemetry;
+pub mod uploads;
@@ -0,0 +1,90 @@
+use magicer::domain::entities::magic_result::MagicResult;
+use magicer::domain::services::result_store::ResultStore;
+use magicer::domain::value_objects::filename::WindowsCompatibleFilename;
+use magicer::domain::value_objects::mime_type::MimeType;
+use magicer::domain::value_objects::request_id::RequestId;
+use magicer::infrastructure::results::in_memory_result_store::InMemoryResultStore;
+use std::time::Duration;
+
+fn result(filename: &str) -> MagicResult {
+    typed_result(filename, "application/pdf")
+}
+
+fn typed_result(filename: &str, mime_type: &str) -> MagicResult {
+    MagicResult::new(
+        RequestId::generate(),
+        WindowsCompatibleFilename::new(filename).unwrap(),
+        MimeType::try_from(mime_type).unwrap(),
+        "document".to_string(),
+    )
+}
+
+#[test]
+fn test_stored_result_is_retrieved() {
+    let store = InMemoryResultStore::new(10, Duration::from_secs(60));
+    store.put("abc", result("a.pdf"));
+    assert_eq!(store.get("abc").unwrap().filename().as_str(), "a.pdf");
+    assert!(store.get("missing").is_none());
+}
+
+#[test]
+fn test_oldest_result_evicted_when_full() {
+    let store = InMemoryResultStore::new(2, Duration::from_secs(60));
+    store.put("a", result("a.pdf"));
+    store.put("b", result("b.pdf"));
+    store.put("c", result("c.pdf"));
+    assert!(store.get("a").is_none());
+    assert!(store.get("b").is_some());
+    assert!(store.get("c").is_some());
+}
+
+#[test]
+fn test_result_expires_after_ttl() {
+    let store = InMemoryResultStore::new(10, Duration::from_millis(50));
+    store.put("abc", result("a.pdf"));
+    assert!(store.get("abc").is_some());
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(store.get("abc").is_none());
+}
+
+#[test]
+fn test_path_history_keeps_analyses_oldest_first() {
+    let store = InMemoryResultStore::new(10, Duration::from_secs(60));
+    store.record_path("docs/a.pdf", typed_result("a.pdf", "application/pdf"));
+    store.record_path("docs/a.pdf", typed_result("a.pdf", "application/x-executable"));
+
+    let types: Vec<String> = store
+        .path_history("docs/a.pdf")
+        .iter()
+        .map(|entry| entry.result.mime_type().as_str().to_string())
+        .collect();
+    assert_eq!(types, ["application/pdf", "application/x-executable"]);
+    assert!(store.path_history("docs/other.pdf").is_empty());
+}
+
+#[test]
+fn test_path_history_bounded_per_path() {
+    let store = InMemoryResultStore::new(10, Duration::from_secs(60)).with_max_history_per_path(2);
+    store.record_path("a.bin", typed_result("a.bin", "application/pdf"));
+    store.record_path("a.bin", typed_result("a.bin", "image/png"));
+    store.record_path("a.bin", typed_result("a.bin", "text/plain"));
+
+    let types: Vec<String> = store
+        .path_history("a.bin")
+        .iter()
+        .map(|entry| entry.result.mime_type().as_str().to_string())
+        .collect();
+    assert_eq!(types, ["image/png", "text/plain"]);
+}
+
+#[test]
+fn test_least_recent_path_evicted_when_full() {
+    let store = InMemoryResultStore::new(2, Duration::from_secs(60));
+    store.record_path("a.pdf", result("a.pdf"));
+    store.record_path("b.pdf", result("b.pdf"));
+    store.record_path("a.pdf", result("a.pdf"));
+    store.record_path("c.pdf", result("c.pdf"));
+    assert_eq!(store.path_history("a.pdf").len(), 2);
+    assert!(store.path_history("b.pdf").is_empty());
+    assert_eq!(store.path_history("c.pdf").len(), 1);
+}
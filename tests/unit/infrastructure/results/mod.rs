@@ -0,0 +1 @@
+pub mod in_memory_result_store_tests;
@@ -0,0 +1,51 @@
+use magicer::domain::services::temp_storage::TempStorageService;
+use magicer::domain::services::upload_session_store::{UploadSession, UploadSessionStore};
+use magicer::domain::value_objects::filename::WindowsCompatibleFilename;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use magicer::infrastructure::uploads::in_memory_session_store::InMemoryUploadSessionStore;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+async fn session(dir: &Path) -> (UploadSession, PathBuf) {
+    let file = FsTempStorageService::new(dir.to_path_buf())
+        .create_temp_file()
+        .await
+        .unwrap();
+    let path = file.path().to_path_buf();
+    let session = UploadSession {
+        filename: WindowsCompatibleFilename::new("a.pdf").unwrap(),
+        file,
+        size: 0,
+    };
+    (session, path)
+}
+
+#[tokio::test]
+async fn test_full_store_refuses_new_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InMemoryUploadSessionStore::new(1, Duration::from_secs(60));
+    let id = store.create(session(dir.path()).await.0).unwrap();
+    assert!(store.create(session(dir.path()).await.0).is_none());
+
+    store.remove(&id);
+    assert!(store.get(&id).is_none());
+    assert!(store.create(session(dir.path()).await.0).is_some());
+}
+
+#[tokio::test]
+async fn test_use_restarts_session_expiry() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InMemoryUploadSessionStore::new(10, Duration::from_millis(100));
+    let (session, path) = session(dir.path()).await;
+    let id = store.create(session).unwrap();
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(store.get(&id).is_some());
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(store.purge_expired(), 0);
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert_eq!(store.purge_expired(), 1);
+    assert!(store.get(&id).is_none());
+    assert!(!path.exists());
+}
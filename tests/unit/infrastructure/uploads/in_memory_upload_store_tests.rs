@@ -0,0 +1,64 @@
+use magicer::domain::services::temp_storage::TempStorageService;
+use magicer::domain::services::upload_store::{Upload, UploadStore};
+use magicer::domain::value_objects::filename::WindowsCompatibleFilename;
+use magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService;
+use magicer::infrastructure::uploads::in_memory_upload_store::InMemoryUploadStore;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+async fn upload(dir: &Path, content: &[u8]) -> (Upload, PathBuf) {
+    let mut file = FsTempStorageService::new(dir.to_path_buf())
+        .create_temp_file()
+        .await
+        .unwrap();
+    file.write(content).await.unwrap();
+    file.sync().await.unwrap();
+    let handle = file.read_handle().await.unwrap();
+    let path = file.path().to_path_buf();
+    let upload = Upload {
+        filename: WindowsCompatibleFilename::new("a.pdf").unwrap(),
+        file,
+        handle,
+    };
+    (upload, path)
+}
+
+#[tokio::test]
+async fn test_stored_upload_is_readable_by_token() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InMemoryUploadStore::new(10, Duration::from_secs(60));
+    let (upload, _) = upload(dir.path(), b"%PDF-1.4").await;
+    let token = store.put(upload);
+
+    let (filename, mut handle) = store.get(&token).unwrap();
+    let mut content = Vec::new();
+    handle.read_to_end(&mut content).unwrap();
+    assert_eq!(filename.as_str(), "a.pdf");
+    assert_eq!(content, b"%PDF-1.4");
+    assert!(store.get("missing").is_none());
+}
+
+#[tokio::test]
+async fn test_expired_upload_is_purged_and_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InMemoryUploadStore::new(10, Duration::from_millis(50));
+    let (upload, path) = upload(dir.path(), b"%PDF-1.4").await;
+    let token = store.put(upload);
+    assert_eq!(store.purge_expired(), 0);
+
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    assert!(store.get(&token).is_none());
+    assert_eq!(store.purge_expired(), 1);
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn test_oldest_upload_evicted_when_full() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InMemoryUploadStore::new(1, Duration::from_secs(60));
+    let first = store.put(upload(dir.path(), b"a").await.0);
+    let second = store.put(upload(dir.path(), b"b").await.0);
+    assert!(store.get(&first).is_none());
+    assert!(store.get(&second).is_some());
+}
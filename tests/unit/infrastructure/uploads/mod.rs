@@ -0,0 +1,2 @@
+pub mod in_memory_session_store_tests;
+pub mod in_memory_upload_store_tests;
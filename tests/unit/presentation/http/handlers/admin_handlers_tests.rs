@@ -0,0 +1,264 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use magicer::domain::repositories::magic_repository::MagicRepository;
+use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::telemetry::metrics::AppMetrics;
+use magicer::presentation::http::router::create_router;
+use magicer::presentation::state::app_state::AppState;
+use magicer::presentation::http::middleware::{error_handler, request_id};
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::infrastructure::magic::reloadable_magic_repository::ReloadableMagicRepository;
+use magicer::infrastructure::filesystem::sandbox::PathSandbox;
+use crate::fake_auth::FakeAuth;
+use crate::fake_temp_storage::FakeTempStorageService;
+use tower::ServiceExt;
+use std::sync::Arc;
+use std::path::PathBuf;
+use axum::middleware;
+
+fn router(reloadable: Option<Arc<ReloadableMagicRepository>>) -> axum::Router {
+    router_with_config(reloadable, ServerConfig::default())
+}
+
+fn router_with_config(
+    reloadable: Option<Arc<ReloadableMagicRepository>>,
+    config: ServerConfig,
+) -> axum::Router {
+    let magic_repo: Arc<dyn MagicRepository> = match &reloadable {
+        Some(repo) => repo.clone(),
+        None => Arc::new(FakeMagicRepository::new().unwrap()),
+    };
+    let mut state = AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        Arc::new(AppMetrics::new(&opentelemetry::global::meter("test"))),
+    );
+    if let Some(repo) = reloadable {
+        state = state.with_magic_reloader(repo);
+    }
+    create_router(Arc::new(state))
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+async fn send(router: &axum::Router, method: &str, uri: &str, body: &'static str) -> (StatusCode, serde_json::Value) {
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_reload_magic_swaps_database() {
+    // The "new database" knows a vendor format the startup one does not.
+    let reloadable = Arc::new(ReloadableMagicRepository::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Box::new(|| {
+            Ok(Arc::new(
+                FakeMagicRepository::builder()
+                    .with_mapping(b"ACME", "application/x-acme", "ACME archive")
+                    .with_defaults()
+                    .build(),
+            ) as Arc<dyn MagicRepository>)
+        }),
+    ));
+    let router = router(Some(reloadable));
+
+    let (status, json) = send(&router, "POST", "/v1/magic/content?filename=a.bin", "ACME\x01").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/octet-stream");
+
+    let (status, json) = send(&router, "POST", "/admin/reload-magic", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["database_version"], 2);
+
+    let (status, json) = send(&router, "POST", "/v1/magic/content?filename=a.bin", "ACME\x01").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/x-acme");
+}
+
+#[tokio::test]
+async fn test_reload_magic_discards_cached_detections() {
+    let reloadable = Arc::new(ReloadableMagicRepository::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Box::new(|| {
+            Ok(Arc::new(
+                FakeMagicRepository::builder()
+                    .with_mapping(b"ACME", "application/x-acme", "ACME archive")
+                    .with_defaults()
+                    .build(),
+            ) as Arc<dyn MagicRepository>)
+        }),
+    ));
+    let mut config = ServerConfig::default();
+    config.analysis.content_hash_cache_entries = 16;
+    let router = router_with_config(Some(reloadable), config);
+    let hash = magicer::application::use_cases::analyze_content::sha256_hex(b"ACME\x01");
+    let upload = || {
+        let router = router.clone();
+        let hash = hash.clone();
+        async move {
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/v1/magic/content?filename=a.bin")
+                        .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                        .header("If-Content-Hash", hash)
+                        .body(Body::from("ACME\x01"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        }
+    };
+
+    upload().await;
+    let cached = upload().await;
+    assert_eq!(cached["result"]["cached"], true);
+    assert_eq!(cached["result"]["mime_type"], "application/octet-stream");
+
+    let (status, _) = send(&router, "POST", "/admin/reload-magic", "").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let json = upload().await;
+    assert!(json["result"].get("cached").is_none());
+    assert_eq!(json["result"]["mime_type"], "application/x-acme");
+}
+
+#[tokio::test]
+async fn test_reload_magic_failure_keeps_database() {
+    let reloadable = Arc::new(ReloadableMagicRepository::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Box::new(|| {
+            Err(magicer::domain::errors::MagicError::DatabaseLoadFailed(
+                "corrupt".to_string(),
+            ))
+        }),
+    ));
+    let router = router(Some(reloadable));
+
+    let (status, json) = send(&router, "POST", "/admin/reload-magic", "").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(json["code"], "reload_failed");
+
+    let (status, json) = send(&router, "POST", "/v1/magic/content?filename=a.pdf", "%PDF-1.4").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_reload_magic_unsupported_without_reloader() {
+    let (status, json) = send(&router(None), "POST", "/admin/reload-magic", "").await;
+    assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+    assert_eq!(json["code"], "reload_unsupported");
+}
+
+fn router_with_database(database_path: &std::path::Path) -> axum::Router {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.magic.database_path = Some(database_path.to_string_lossy().into_owned());
+    let state = AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        Arc::new(AppMetrics::new(&opentelemetry::global::meter("test"))),
+    );
+    create_router(Arc::new(state))
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+#[tokio::test]
+async fn test_check_magic_reports_valid_and_broken_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let valid = dir.path().join("hello.magic");
+    std::fs::write(&valid, "0\tstring\tHELLO\tHello file\n!:mime\ttext/x-hello\n").unwrap();
+    let broken = dir.path().join("broken.magic");
+    std::fs::write(&broken, "0\tbogustype\tHELLO\tbroken\n").unwrap();
+
+    let (status, json) = send(&router_with_database(&valid), "POST", "/admin/check-magic", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["valid"], true);
+    assert!(json.get("error").is_none());
+
+    let (status, json) = send(&router_with_database(&broken), "POST", "/admin/check-magic", "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["valid"], false);
+    assert!(json["error"].as_str().unwrap().contains("valid magic"), "{}", json);
+}
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_debug_capture_stops_after_armed_requests() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let app = router(None);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/debug-capture?requests=2")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for _ in 0..3 {
+        let request = Request::builder()
+            .uri("/v1/ping")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+    }
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let captured: Vec<_> = output.lines().filter(|line| line.contains("Debug capture")).collect();
+    // The arming line itself says "Debug capture armed".
+    assert_eq!(captured.iter().filter(|line| !line.contains("armed")).count(), 2);
+    assert!(!output.contains("YWRtaW46c2VjcmV0"));
+    assert!(captured.iter().any(|line| line.contains("authorization: [redacted]")));
+}
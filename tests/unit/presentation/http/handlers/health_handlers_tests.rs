@@ -45,3 +45,101 @@ async fn test_ping_handler() {
     assert_eq!(json["message"], "pong");
     assert!(json.get("request_id").is_some());
 }
+
+#[tokio::test]
+async fn test_ready_handler_reports_unwritable_temp_dir() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(
+        magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService::new(
+            PathBuf::from("/proc/magicer_readiness_probe"),
+        ),
+    );
+    let auth_service = Arc::new(FakeAuth);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let metrics = Arc::new(AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, config, metrics));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/v1/ready")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["status"], "not_ready");
+    assert_eq!(json["reason"], "temp_dir_not_writable");
+}
+
+#[tokio::test]
+async fn test_ready_handler_reports_free_space_below_minimum() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let auth_service = Arc::new(FakeAuth);
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.temp_dir = "/tmp".to_string();
+    config.analysis.min_free_space_mb = u64::MAX;
+    let metrics = Arc::new(AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, Arc::new(config), metrics));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/v1/ready")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["reason"], "insufficient_disk_space");
+    assert!(json["free_space_mb"].as_u64().is_some_and(|mb| mb > 0));
+}
+
+#[tokio::test]
+async fn test_capabilities_reflect_effective_config() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.limits.max_body_size_mb = 7;
+    config.auth.introspection_url = Some("http://127.0.0.1:1/introspect".to_string());
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let metrics = Arc::new(AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, Arc::new(FakeAuth), Arc::new(config), metrics));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/v1/capabilities")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["limits"]["max_body_size_mb"], 7);
+    assert_eq!(json["auth"]["schemes"], serde_json::json!(["basic", "bearer"]));
+    assert_eq!(json["features"]["hash_algorithms"], serde_json::json!(["sha256", "md5", "crc32"]));
+}
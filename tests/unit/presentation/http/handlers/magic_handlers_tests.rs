@@ -7,6 +7,9 @@ use magicer::presentation::http::router::create_router;
 use magicer::presentation::state::app_state::AppState;
 use magicer::presentation::http::middleware::{error_handler, request_id};
 use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::domain::entities::magic_result::MagicResult;
+use magicer::domain::services::result_sink::ResultSink;
+use magicer::infrastructure::magic::unavailable_magic_repository::UnavailableMagicRepository;
 use magicer::infrastructure::filesystem::sandbox::PathSandbox;
 use crate::fake_auth::FakeAuth;
 use crate::fake_temp_storage::FakeTempStorageService;
@@ -47,6 +50,7 @@ async fn test_analyze_content_handler_success() {
     let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
     assert!(json.get("result").is_some());
+    assert!(json.get("path").is_none());
 }
 
 #[tokio::test]
@@ -86,6 +90,41 @@ async fn test_analyze_path_handler_success() {
     assert_eq!(json["result"]["mime_type"], "application/pdf");
 }
 
+#[tokio::test]
+async fn test_analyze_path_denied_subpath_forbidden() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let temp_dir = "/tmp/magicer_unit_handlers_denied";
+    std::fs::create_dir_all(PathBuf::from(temp_dir).join("private")).unwrap();
+    std::fs::write(PathBuf::from(temp_dir).join("private/test.pdf"), b"%PDF-1.4").unwrap();
+    let sandbox = Arc::new(
+        PathSandbox::new(PathBuf::from(temp_dir)).with_denied_subpaths(&["private"]),
+    );
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from(temp_dir)));
+    let auth_service = Arc::new(FakeAuth);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, config, noop_metrics()));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=test.pdf&path=private/test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert!(json["error"].as_str().unwrap().contains("Permission denied"));
+}
+
 #[tokio::test]
 async fn test_analyze_content_handler_large_file_streaming() {
     let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
@@ -139,7 +178,7 @@ async fn test_analyze_content_handler_chunked_streaming() {
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/magic/content?filename=test.txt")
+                .uri("/v1/magic/content?filename=test.txt&full=true")
                 .header("Authorization", "Basic YWRtaW46c2VjcmV0")
                 .header("Transfer-Encoding", "chunked")
                 .body(Body::from("small content"))
@@ -192,7 +231,7 @@ async fn test_analyze_content_handler_threshold_streaming() {
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/magic/content?filename=large.txt")
+                .uri("/v1/magic/content?filename=large.txt&full=true")
                 .header("Authorization", "Basic YWRtaW46c2VjcmV0")
                 .header("Content-Length", "2000000") // ~2MB
                 .body(Body::from(vec![0u8; 2000000]))
@@ -203,3 +242,2266 @@ async fn test_analyze_content_handler_threshold_streaming() {
     assert_eq!(response.status(), StatusCode::OK);
     assert!(temp_storage.counter() > 0);
 }
+
+#[tokio::test]
+async fn test_analyze_content_spills_to_temp_file_over_memory_budget() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.max_total_memory_bytes = 150;
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage.clone(), Arc::new(FakeAuth), Arc::new(config), noop_metrics()));
+    let router = create_router(state.clone())
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+    let request = || {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/magic/content?filename=small.txt&full=true")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .header("Content-Length", "100")
+            .body(Body::from(vec![0u8; 100]))
+            .unwrap()
+    };
+
+    // Within budget: analyzed in memory, and the budget is released after.
+    let response = router.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(temp_storage.counter(), 0);
+    assert_eq!(state.memory_budget.used(), 0);
+
+    // Another analysis holds most of the budget: the overflow goes to disk.
+    let _held = state.memory_budget.try_reserve(100).unwrap();
+    let response = router.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(temp_storage.counter(), 1);
+}
+
+#[tokio::test]
+async fn test_analyze_content_rejected_over_memory_budget_when_configured() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.max_total_memory_bytes = 50;
+    config.analysis.reject_over_memory_budget = true;
+    let response = router_with_config(config)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=small.txt")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("Content-Length", "100")
+                .body(Body::from(vec![0u8; 100]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "analysis_unavailable");
+}
+
+fn default_router() -> axum::Router {
+    router_with_config(magicer::infrastructure::config::server_config::ServerConfig::default())
+}
+
+fn router_with_config(config: magicer::infrastructure::config::server_config::ServerConfig) -> axum::Router {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let auth_service = Arc::new(FakeAuth);
+    let config = Arc::new(config);
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, config, noop_metrics()));
+    create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+#[tokio::test]
+async fn test_analyze_content_overlong_filename_code() {
+    let long_name = "a".repeat(311);
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/v1/magic/content?filename={}", long_name))
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "filename_too_long");
+}
+
+#[tokio::test]
+async fn test_analyze_path_slash_filename_code() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=dir%2Ftest.pdf&path=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "filename_invalid_char");
+}
+
+#[tokio::test]
+async fn test_analyze_content_echo_content_type() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&echo_content_type=true")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/pdf");
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_analyze_content_fields_selects_subset() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&fields=mime_type,bogus")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"], serde_json::json!({ "mime_type": "application/pdf" }));
+    assert!(json.get("request_id").is_some());
+    assert_eq!(json["filename"], "test.pdf");
+}
+
+#[tokio::test]
+async fn test_analyze_content_default_fields_full_result() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["result"]["description"], "PDF document");
+}
+
+#[tokio::test]
+async fn test_analyze_content_unknown_field_rejected_when_strict() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let auth_service = Arc::new(FakeAuth);
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.response.reject_unknown_fields = true;
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, Arc::new(config), noop_metrics()));
+    let app = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&fields=mime_type,bogus")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "unknown_field");
+}
+
+#[tokio::test]
+async fn test_rule_endpoint_disabled_by_default() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/test")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"rule":"0\tstring\tX\tx","content_base64":"WA=="}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "custom_rules_disabled");
+}
+
+#[tokio::test]
+async fn test_unavailable_magic_returns_503_but_ping_succeeds() {
+    let magic_repo = Arc::new(UnavailableMagicRepository::new("database missing"));
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let auth_service = Arc::new(FakeAuth);
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, auth_service, config, noop_metrics()));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "analysis_unavailable");
+
+    let response = router
+        .oneshot(Request::builder().uri("/v1/ping").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_detected_type_header_matches_body() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.emit_detected_type_header = true;
+    let router = router_with_config(config);
+
+    let file = tempfile::Builder::new().suffix(".pdf").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    let requests = [
+        Request::builder()
+            .method("POST")
+            .uri("/v1/magic/content?filename=test.pdf")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .body(Body::from("%PDF-1.4"))
+            .unwrap(),
+        Request::builder()
+            .method("POST")
+            .uri(format!("/v1/magic/path?filename=test.pdf&path={}", name))
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .body(Body::empty())
+            .unwrap(),
+    ];
+    for request in requests {
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let header = response.headers()["x-detected-type"].to_str().unwrap().to_string();
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(json["result"]["mime_type"], header.as_str());
+    }
+}
+
+#[tokio::test]
+async fn test_detected_type_header_off_by_default() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("x-detected-type").is_none());
+}
+
+fn paths_request(body: serde_json::Value) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/magic/paths")
+        .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_analyze_paths_reports_each_entry() {
+    let file = tempfile::Builder::new().suffix(".pdf").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    let response = default_router()
+        .oneshot(paths_request(serde_json::json!({
+            "paths": [name, "magicer-missing-file.pdf", "../etc/passwd"],
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+
+    assert_eq!(results[0]["path"], name);
+    assert_eq!(results[0]["filename"], name);
+    assert_eq!(results[0]["result"]["mime_type"], "application/pdf");
+
+    assert_eq!(results[1]["status"], 404);
+    assert!(results[1].get("result").is_none());
+
+    assert_eq!(results[2]["path"], "../etc/passwd");
+    assert_eq!(results[2]["status"], 400);
+    assert!(results[2].get("result").is_none());
+}
+
+#[tokio::test]
+async fn test_analyze_paths_keeps_input_order() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    let dir_name = dir.path().file_name().unwrap().to_str().unwrap().to_string();
+    std::fs::write(dir.path().join("slow.pdf"), b"%PDF-1.4").unwrap();
+    std::fs::write(dir.path().join("medium.sh"), b"#!/bin/sh\n").unwrap();
+    std::fs::write(dir.path().join("fast.png"), b"\x89PNG\r\n").unwrap();
+
+    // Earlier entries take longer, so completion order is the reverse of
+    // the input order.
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_defaults()
+            .with_delay_for(b"%PDF", std::time::Duration::from_millis(300))
+            .with_delay_for(b"#!/bin/sh", std::time::Duration::from_millis(150))
+            .build(),
+    );
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default()),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let paths: Vec<String> = ["slow.pdf", "medium.sh", "fast.png"]
+        .iter()
+        .map(|name| format!("{}/{}", dir_name, name))
+        .collect();
+    let started = std::time::Instant::now();
+    let response = router
+        .oneshot(paths_request(serde_json::json!({ "paths": paths })))
+        .await
+        .unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_millis(450), "entries ran sequentially");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    let results = json["results"].as_array().unwrap();
+    let reported: Vec<&str> = results.iter().map(|r| r["path"].as_str().unwrap()).collect();
+    assert_eq!(reported, paths);
+    assert_eq!(results[0]["result"]["mime_type"], "application/pdf");
+    assert_eq!(results[1]["result"]["mime_type"], "text/x-shellscript");
+    assert_eq!(results[2]["result"]["mime_type"], "image/png");
+}
+
+#[tokio::test]
+async fn test_analyze_paths_rejects_oversized_batch() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.limits.max_batch_items = 2;
+
+    let response = router_with_config(config)
+        .oneshot(paths_request(serde_json::json!({
+            "filename_default": "x.bin",
+            "paths": ["a", "b", "c"],
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "batch_too_large");
+}
+
+#[tokio::test]
+async fn test_analyze_path_last_modified_and_if_modified_since() {
+    let file = tempfile::Builder::new().suffix(".pdf").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+    // Sub-second part must be truncated when compared with HTTP dates.
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_704_067_200_500);
+    file.as_file().set_modified(mtime).unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+    let uri = format!("/v1/magic/path?filename=test.pdf&path={}", name);
+    let router = default_router();
+
+    let request = |since: Option<&str>| {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(&uri)
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0");
+        if let Some(since) = since {
+            builder = builder.header("If-Modified-Since", since);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = router.clone().oneshot(request(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let last_modified = response.headers()["last-modified"].to_str().unwrap().to_string();
+    assert_eq!(last_modified, "Mon, 01 Jan 2024 00:00:00 GMT");
+
+    let response = router.clone().oneshot(request(Some(&last_modified))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["last-modified"], last_modified.as_str());
+
+    let response = router
+        .oneshot(request(Some("Sun, 31 Dec 2023 23:59:59 GMT")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+struct RecordingSink(std::sync::Mutex<Vec<String>>);
+
+impl ResultSink for RecordingSink {
+    fn publish(&self, result: &MagicResult) {
+        self.0.lock().unwrap().push(result.mime_type().as_str());
+    }
+}
+
+#[tokio::test]
+async fn test_successful_analysis_is_published_to_result_sink() {
+    let sink = Arc::new(RecordingSink(std::sync::Mutex::new(Vec::new())));
+    let state = AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default()),
+        noop_metrics(),
+    )
+    .with_result_sink(sink.clone());
+    let router = create_router(Arc::new(state))
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(*sink.0.lock().unwrap(), vec!["application/pdf".to_string()]);
+}
+
+#[tokio::test]
+async fn test_analysis_response_has_server_timing() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let timing = response.headers()["server-timing"].to_str().unwrap();
+    assert!(timing.contains("read;dur="), "{}", timing);
+    assert!(timing.contains("detect;dur="), "{}", timing);
+    assert!(timing.contains("total;dur="), "{}", timing);
+}
+
+#[tokio::test]
+async fn test_allow_empty_classifies_empty_content_and_files() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.allow_empty = true;
+    let router = router_with_config(config);
+
+    let file = tempfile::Builder::new().suffix(".txt").tempfile_in("/tmp").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    let requests = [
+        Request::builder()
+            .method("POST")
+            .uri("/v1/magic/content?filename=empty.txt")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .body(Body::empty())
+            .unwrap(),
+        Request::builder()
+            .method("POST")
+            .uri(format!("/v1/magic/path?filename=empty.txt&path={}", name))
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .body(Body::empty())
+            .unwrap(),
+    ];
+    for request in requests {
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(json["result"]["mime_type"], "application/x-empty");
+    }
+}
+
+#[tokio::test]
+async fn test_empty_content_rejected_by_default() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=empty.txt")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_route_prefix_mounts_all_routes() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.route_prefix = Some("/magicer".to_string());
+    let router = router_with_config(config);
+
+    let ping = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = router.clone().oneshot(ping("/magicer/v1/ping")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = router.clone().oneshot(ping("/v1/ping")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/magicer/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn analyze_with_output(output: &str) -> (StatusCode, serde_json::Value) {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/v1/magic/content?filename=test.pdf&output={}", output))
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_output_mime_omits_description() {
+    let (status, json) = analyze_with_output("mime").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert!(json["result"].get("description").is_none());
+}
+
+#[tokio::test]
+async fn test_output_description_omits_mime_type() {
+    let (status, json) = analyze_with_output("description").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["description"], "PDF document");
+    assert!(json["result"].get("mime_type").is_none());
+}
+
+#[tokio::test]
+async fn test_output_both_includes_both_fields() {
+    let (status, json) = analyze_with_output("both").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["result"]["description"], "PDF document");
+}
+
+#[tokio::test]
+async fn test_unknown_output_is_rejected() {
+    let (status, json) = analyze_with_output("everything").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "unsupported_output");
+}
+
+#[tokio::test]
+async fn test_max_description_length_truncates_response() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.max_description_length = Some(3);
+    let response = router_with_config(config)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["description"], "PDF…");
+    assert_eq!(json["result"]["description_truncated"], true);
+}
+
+async fn analyze_named(uri: &str) -> (StatusCode, serde_json::Value) {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_named_content_uses_path_filename() {
+    let (status, json) = analyze_named("/v1/magic/content/report.pdf?hash=sha256").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "report.pdf");
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert!(json["result"]["hash"].is_string());
+}
+
+#[tokio::test]
+async fn test_named_content_decodes_unicode_filename() {
+    let (status, json) = analyze_named("/v1/magic/content/r%C3%A9sum%C3%A9.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "résumé.pdf");
+}
+
+#[tokio::test]
+async fn test_named_content_rejects_encoded_slash() {
+    let (status, json) = analyze_named("/v1/magic/content/..%2Fetc%2Fpasswd").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "filename_invalid_char");
+}
+
+#[tokio::test]
+async fn test_analyze_path_echoes_relative_path() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("doc.pdf"), b"%PDF-1.4").unwrap();
+    let relative = format!("{}/doc.pdf", dir.path().file_name().unwrap().to_str().unwrap());
+
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/v1/magic/path?filename=doc.pdf&path={}", relative))
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["path"], relative);
+    assert_eq!(json["filename"], "doc.pdf");
+}
+
+#[tokio::test]
+async fn test_raw_magic_returns_repository_output() {
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf?raw_magic=true").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["raw"], "PDF document");
+
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["result"].get("raw").is_none());
+}
+
+#[tokio::test]
+async fn test_content_queries_share_flag_parsing() {
+    let router = default_router();
+    for uri in [
+        "/v1/magic/content?filename=test.pdf&raw_magic=true&store=true",
+        "/v1/magic/content/test.pdf?raw_magic=true&store=true",
+    ] {
+        let (status, json) = post_json(&router, uri, Body::from("%PDF-1.4")).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(json["result"]["raw"], "PDF document", "{}", uri);
+        assert!(json["result_url"].is_string(), "{}", uri);
+    }
+    for uri in [
+        "/v1/magic/content?filename=test.pdf&raw_magic=yes",
+        "/v1/magic/content/test.pdf?raw_magic=yes",
+    ] {
+        let (status, _) = post_json(&router, uri, Body::from("%PDF-1.4")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_trace_returns_repository_trace() {
+    // The fake repository cannot trace, so the trace is present but empty.
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf?trace=true").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["result"]["trace"], "");
+
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["result"].get("trace").is_none());
+}
+
+#[tokio::test]
+async fn test_hexdump_returns_capped_dump() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.hexdump_bytes = 4;
+    let router = router_with_config(config);
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=a.pdf&hexdump=true", Body::from("%PDF-1.4")).await;
+    let (_, plain) = post_json(&router, "/v1/magic/content?filename=a.pdf", Body::from("%PDF-1.4")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(
+        json["result"]["hexdump"],
+        "00000000  25 50 44 46                                       |%PDF|\n00000004\n"
+    );
+    assert!(plain["result"].get("hexdump").is_none());
+}
+
+async fn analyze_text_utf8_only(body: &'static [u8]) -> (StatusCode, serde_json::Value) {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.text.allowed_charsets = vec!["utf-8".to_string(), "us-ascii".to_string()];
+    let response = router_with_config(config)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=notes.txt")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_disallowed_encoding_rejected() {
+    let (status, json) = analyze_text_utf8_only(b"\xFF\xFEh\x00i\x00").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "disallowed_encoding");
+}
+
+#[tokio::test]
+async fn test_allowed_encoding_reported() {
+    let (status, json) = analyze_text_utf8_only(b"#!/bin/sh\necho hi\n").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["encoding"], "us-ascii");
+}
+
+fn encoding_router() -> axum::Router {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.text.detect_encoding = true;
+    router_with_config(config)
+}
+
+#[tokio::test]
+async fn test_utf16_text_requires_transcoding() {
+    let (status, json) = post_json(&encoding_router(), "/v1/magic/content?filename=notes.txt", Body::from(&b"\xFF\xFEh\x00i\x00"[..])).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["encoding"], "utf-16le");
+    assert_eq!(json["result"]["requires_transcoding"], true);
+}
+
+#[tokio::test]
+async fn test_utf8_text_does_not_require_transcoding() {
+    let (status, json) = post_json(&encoding_router(), "/v1/magic/content?filename=run.sh", Body::from("#!/bin/sh\necho héllo\n")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["encoding"], "utf-8");
+    assert_eq!(json["result"]["requires_transcoding"], false);
+}
+
+#[tokio::test]
+async fn test_if_content_hash_header_returns_cached_result() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.content_hash_cache_entries = 16;
+    let router = router_with_config(config);
+    let hash = magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4");
+    let request = |if_content_hash: Option<&str>| {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/v1/magic/content?filename=test.pdf")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0");
+        if let Some(value) = if_content_hash {
+            builder = builder.header("If-Content-Hash", value);
+        }
+        builder.body(Body::from("%PDF-1.4")).unwrap()
+    };
+
+    let primed = router.clone().oneshot(request(None)).await.unwrap();
+    let primed_body = axum::body::to_bytes(primed.into_body(), usize::MAX).await.unwrap();
+    let primed_json: serde_json::Value = serde_json::from_slice(&primed_body).unwrap();
+    let response = router.oneshot(request(Some(&hash))).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert!(primed_json["result"].get("cached").is_none());
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["cached"], true);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_multiple_hash_algorithms_in_one_request() {
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf?hash=sha256,md5,crc32").await;
+    assert_eq!(status, StatusCode::OK);
+    let hashes = &json["result"]["hashes"];
+    assert_eq!(hashes["sha256"], "e16fa5d9b51928755db85b917f0297babaf22c7a47e97d9212adab56e61ba04e");
+    assert_eq!(hashes["md5"], "914240125319291c7cb7e712e419b254");
+    assert_eq!(hashes["crc32"], "e959baa2");
+    assert_eq!(json["result"]["hash"], hashes["sha256"]);
+}
+
+#[tokio::test]
+async fn test_unknown_hash_algorithm_lists_supported() {
+    let (status, json) = analyze_named("/v1/magic/content/test.pdf?hash=md5,sha512").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "unsupported_hash");
+    let error = json["error"].as_str().unwrap();
+    assert!(error.contains("sha512"), "{}", error);
+    assert!(error.contains("sha256, md5, crc32"), "{}", error);
+}
+
+async fn analyze_enveloped(uri: &str) -> (StatusCode, serde_json::Value) {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.response_envelope = true;
+    let response = router_with_config(config)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_response_envelope_wraps_success() {
+    let (status, json) = analyze_enveloped("/v1/magic/content?filename=test.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["data"]["result"]["mime_type"], "application/pdf");
+    assert!(json.get("error").is_none());
+}
+
+#[tokio::test]
+async fn test_response_envelope_wraps_error() {
+    let (status, json) = analyze_enveloped("/v1/magic/content?filename=test.pdf&hash=sha512").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["ok"], false);
+    assert_eq!(json["error"]["code"], "unsupported_hash");
+    assert!(json.get("data").is_none());
+}
+
+#[tokio::test]
+async fn test_response_envelope_wraps_unmatched_route() {
+    let (status, json) = analyze_enveloped("/v1/magic/nowhere").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["ok"], false);
+    assert_eq!(json["error"]["error"], "Not Found");
+}
+
+#[tokio::test]
+async fn test_stored_result_is_retrievable() {
+    let router = default_router();
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&store=true")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let stored: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    let hash = stored["result"]["hash"].as_str().unwrap();
+    let url = stored["result_url"].as_str().unwrap();
+    assert_eq!(url, format!("/v1/results/{}", hash));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(url)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let fetched: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(fetched["request_id"], stored["request_id"]);
+    assert_eq!(fetched["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_unknown_result_is_not_found() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .uri("/v1/results/deadbeef")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "result_not_found");
+}
+
+#[tokio::test]
+async fn test_result_url_absent_without_store() {
+    let (status, json) = analyze_named("/v1/magic/content/report.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json.get("result_url").is_none());
+}
+
+#[tokio::test]
+async fn test_analysis_timeout_reports_configured_timeout() {
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_delay(std::time::Duration::from_millis(1500))
+            .build(),
+    );
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.timeouts.analysis_timeout_secs = 1;
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "analysis_timeout");
+    let error = json["error"].as_str().unwrap();
+    assert!(error.contains("1s"), "{}", error);
+    assert!(error.contains("aborted"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_analysis_timeout_answers_configured_status_code() {
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_delay(std::time::Duration::from_millis(1500))
+            .build(),
+    );
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.server.timeouts.analysis_timeout_secs = 1;
+    config.server.timeouts.timeout_status_code = 503;
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=test.pdf", Body::from("%PDF-1.4")).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json["code"], "analysis_timeout");
+}
+
+#[tokio::test]
+async fn test_result_category_reports_binary_and_text() {
+    let (status, json) = analyze_named("/v1/magic/content/report.pdf").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["category"], "binary");
+
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=run.sh")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("#!/bin/sh\necho hi\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["category"], "text");
+}
+
+/// Shares a [`ManualReader`] between the meter provider and the test.
+#[derive(Debug, Clone)]
+struct SharedReader(Arc<opentelemetry_sdk::metrics::ManualReader>);
+
+impl opentelemetry_sdk::metrics::reader::MetricReader for SharedReader {
+    fn register_pipeline(&self, pipeline: std::sync::Weak<opentelemetry_sdk::metrics::Pipeline>) {
+        self.0.register_pipeline(pipeline)
+    }
+
+    fn collect(
+        &self,
+        rm: &mut opentelemetry_sdk::metrics::data::ResourceMetrics,
+    ) -> opentelemetry_sdk::metrics::MetricResult<()> {
+        self.0.collect(rm)
+    }
+
+    fn force_flush(&self) -> opentelemetry_sdk::metrics::MetricResult<()> {
+        self.0.force_flush()
+    }
+
+    fn shutdown(&self) -> opentelemetry_sdk::metrics::MetricResult<()> {
+        self.0.shutdown()
+    }
+
+    fn temporality(
+        &self,
+        kind: opentelemetry_sdk::metrics::InstrumentKind,
+    ) -> opentelemetry_sdk::metrics::Temporality {
+        self.0.temporality(kind)
+    }
+}
+
+/// Sum of the `app.analysis.strategy` data points labelled `strategy`.
+fn strategy_count(reader: &SharedReader, strategy: &str) -> u64 {
+    use opentelemetry_sdk::metrics::data::{ResourceMetrics, Sum};
+    use opentelemetry_sdk::metrics::reader::MetricReader;
+
+    let mut rm = ResourceMetrics {
+        resource: opentelemetry_sdk::Resource::empty(),
+        scope_metrics: Vec::new(),
+    };
+    reader.collect(&mut rm).unwrap();
+    rm.scope_metrics
+        .iter()
+        .flat_map(|scope| scope.metrics.iter())
+        .filter(|metric| metric.name == "app.analysis.strategy")
+        .filter_map(|metric| metric.data.as_any().downcast_ref::<Sum<u64>>())
+        .flat_map(|sum| sum.data_points.iter())
+        .filter(|point| {
+            point
+                .attributes
+                .iter()
+                .any(|kv| kv.key.as_str() == "analysis.strategy" && kv.value.as_str() == strategy)
+        })
+        .map(|point| point.value)
+        .sum()
+}
+
+#[tokio::test]
+async fn test_low_threshold_counts_temp_file_strategy() {
+    use opentelemetry::metrics::MeterProvider;
+
+    let reader = SharedReader(Arc::new(opentelemetry_sdk::metrics::ManualReader::builder().build()));
+    let provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
+        .with_reader(reader.clone())
+        .build();
+    let metrics = Arc::new(AppMetrics::new(&provider.meter("test")));
+
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.large_file_threshold_mb = 0;
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp/magicer_metrics_test"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        metrics,
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&full=true")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("Content-Length", "8")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(strategy_count(&reader, "temp_file"), 1);
+    assert_eq!(strategy_count(&reader, "in_memory"), 0);
+}
+
+#[tokio::test]
+async fn test_polyglot_query_reports_all_types() {
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_mapping(b"GIF89a", "image/gif", "GIF image data")
+            .with_mapping(b"GIF89a/*", "application/javascript", "JavaScript source")
+            .build(),
+    );
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default()),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=image.gif&polyglot=true")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("GIF89a/*=1;alert(1)//*/"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "image/gif");
+    assert_eq!(
+        json["result"]["polyglot_types"],
+        serde_json::json!(["image/gif", "application/javascript"])
+    );
+    assert_eq!(json["result"]["is_polyglot"], true);
+
+    let (_, json) = analyze_named("/v1/magic/content/report.pdf").await;
+    assert!(json["result"].get("polyglot_types").is_none());
+    assert!(json["result"].get("is_polyglot").is_none());
+}
+
+fn ranged_path_request(path: &str, range: &str, if_range: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri(format!("/v1/magic/path?filename=embedded.bin&path={}", path))
+        .header("Authorization", "Basic YWRtaW46c2VjcmV0");
+    if !range.is_empty() {
+        builder = builder.header("Range", range);
+    }
+    if let Some(tag) = if_range {
+        builder = builder.header("If-Range", tag);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_analyze_path_if_range_partial_and_stale() {
+    let file = tempfile::Builder::new().suffix(".bin").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"HEADER\x00\x00%PDF-1.4\n").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    let response = default_router().oneshot(ranged_path_request(name, "", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "application/octet-stream");
+
+    // Matching If-Range: only the embedded document is analyzed.
+    let response = default_router()
+        .oneshot(ranged_path_request(name, "bytes=8-", Some(&etag)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["result"]["range"], serde_json::json!({"start": 8, "end": 16}));
+
+    // Stale If-Range: the whole file is analyzed again.
+    let response = default_router()
+        .oneshot(ranged_path_request(name, "bytes=8-", Some("\"0-0\"")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["result"]["mime_type"], "application/octet-stream");
+    assert!(json["result"].get("range").is_none());
+}
+
+#[tokio::test]
+async fn test_analyze_path_range_beyond_file_size() {
+    let file = tempfile::Builder::new().suffix(".bin").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"%PDF-1.4\n").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    let response = default_router()
+        .oneshot(ranged_path_request(name, "bytes=100-200", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "range_not_satisfiable");
+}
+
+fn router_with_upload_dir(
+    config: magicer::infrastructure::config::server_config::ServerConfig,
+    dir: &std::path::Path,
+) -> axum::Router {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(
+        magicer::infrastructure::filesystem::temp_storage_service::FsTempStorageService::new(dir.to_path_buf()),
+    );
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, Arc::new(FakeAuth), Arc::new(config), noop_metrics()));
+    create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+async fn post_json(router: &axum::Router, uri: &str, body: Body) -> (StatusCode, serde_json::Value) {
+    send_json(router, "POST", uri, body).await
+}
+
+async fn send_json(
+    router: &axum::Router,
+    method: &str,
+    uri: &str,
+    body: Body,
+) -> (StatusCode, serde_json::Value) {
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(body)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_uploaded_content_is_analyzed_by_token_with_different_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = router_with_upload_dir(
+        magicer::infrastructure::config::server_config::ServerConfig::default(),
+        dir.path(),
+    );
+
+    let (status, json) = post_json(&router, "/v1/upload?filename=doc.pdf", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(json["size"], 8);
+    assert_eq!(json["expires_in_secs"], 300);
+    let token = json["token"].as_str().unwrap();
+
+    let (status, json) = post_json(&router, &format!("/v1/magic/token/{}", token), Body::empty()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "doc.pdf");
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert!(json["result"].get("hash").is_none());
+
+    let (status, json) = post_json(
+        &router,
+        &format!("/v1/magic/token/{}?hash=sha256&output=mime", token),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert!(json["result"].get("description").is_none());
+    assert_eq!(json["result"]["hash"].as_str().unwrap().len(), 64);
+}
+
+#[tokio::test]
+async fn test_expired_upload_token_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.uploads.ttl_secs = 0;
+    let router = router_with_upload_dir(config, dir.path());
+
+    let (status, json) = post_json(&router, "/v1/upload?filename=doc.pdf", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = json["token"].as_str().unwrap();
+
+    let (status, json) = post_json(&router, &format!("/v1/magic/token/{}", token), Body::empty()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "upload_not_found");
+}
+
+#[tokio::test]
+async fn test_session_assembled_across_patches_is_analyzed() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = router_with_upload_dir(
+        magicer::infrastructure::config::server_config::ServerConfig::default(),
+        dir.path(),
+    );
+
+    let (status, json) = post_json(&router, "/v1/session?filename=doc.pdf", Body::empty()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(json["size"], 0);
+    let id = json["session_id"].as_str().unwrap().to_string();
+
+    let uri = format!("/v1/session/{}", id);
+    let (status, json) = send_json(&router, "PATCH", &uri, Body::from("%PDF")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["size"], 4);
+    let (status, json) = send_json(&router, "PATCH", &uri, Body::from("-1.4")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["size"], 8);
+
+    let (status, json) = post_json(&router, &format!("{}/analyze", uri), Body::empty()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "doc.pdf");
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_session_over_size_cap_is_rejected_and_discarded() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.sessions.max_size_mb = 0;
+    let router = router_with_upload_dir(config, dir.path());
+
+    let (_, json) = post_json(&router, "/v1/session?filename=doc.pdf", Body::empty()).await;
+    let uri = format!("/v1/session/{}", json["session_id"].as_str().unwrap());
+
+    let (status, json) = send_json(&router, "PATCH", &uri, Body::from("%PDF")).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(json["code"], "payload_too_large");
+    let (status, json) = send_json(&router, "PATCH", &uri, Body::from("%PDF")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "session_not_found");
+}
+
+#[tokio::test]
+async fn test_unknown_session_is_not_found() {
+    let (status, json) = post_json(&default_router(), "/v1/session/missing/analyze", Body::empty()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "session_not_found");
+}
+
+#[tokio::test]
+async fn test_analyze_path_lowercases_filename_on_case_insensitive_sandbox() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Report.PDF"), b"%PDF-1.4").unwrap();
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.sandbox.case_insensitive = true;
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(dir.path().to_path_buf()).with_case_insensitive(true));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, Arc::new(FakeAuth), Arc::new(config), noop_metrics()));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let (status, json) = post_json(&router, "/v1/magic/path?filename=Report.PDF&path=Report.PDF", Body::empty()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "report.pdf");
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+struct FailingMagicRepository;
+
+impl magicer::domain::repositories::magic_repository::MagicRepository for FailingMagicRepository {
+    fn analyze_buffer<'a>(
+        &'a self,
+        _data: &'a [u8],
+        _filename: &'a str,
+    ) -> futures_util::future::BoxFuture<
+        'a,
+        Result<(magicer::domain::value_objects::mime_type::MimeType, String), magicer::domain::errors::MagicError>,
+    > {
+        Box::pin(async {
+            Err(magicer::domain::errors::MagicError::AnalysisFailed(
+                "line 12: offset 0x40 out of range".to_string(),
+            ))
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_analysis_failure_reports_libmagic_detail() {
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let state = Arc::new(AppState::new(Arc::new(FailingMagicRepository), sandbox, temp_storage, Arc::new(FakeAuth), config, noop_metrics()));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=a.bin", Body::from("data")).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(json["detail"], "line 12: offset 0x40 out of range");
+    assert!(json["error"].as_str().unwrap().contains("line 12: offset 0x40 out of range"));
+}
+
+#[tokio::test]
+async fn test_other_failures_have_no_detail() {
+    let (status, json) = post_json(&default_router(), "/v1/magic/content?filename=a.bin", Body::empty()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json.get("detail").is_none());
+}
+
+#[tokio::test]
+async fn test_missing_path_reported_as_problem_json() {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=a.pdf&path=magicer-missing-file.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("Accept", "application/problem+json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["content-type"], "application/problem+json");
+    let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["title"], "Not Found");
+    assert_eq!(json["status"], 404);
+    assert!(json["type"].is_string());
+    assert!(json["detail"].as_str().unwrap().contains("magicer-missing-file.pdf"));
+    assert_eq!(json["instance"], request_id);
+    assert!(json.get("error").is_none());
+}
+
+#[tokio::test]
+async fn test_is_text_reports_text_upload() {
+    let (status, json) = post_json(&default_router(), "/v1/magic/is-text?filename=run.sh", Body::from("#!/bin/sh\necho hi\n")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["is_text"], true);
+    assert_eq!(json["mime_type"], "text/x-shellscript");
+}
+
+#[tokio::test]
+async fn test_is_text_reports_png_as_binary() {
+    let (status, json) = post_json(&default_router(), "/v1/magic/is-text", Body::from(&b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"[..])).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["is_text"], false);
+    assert_eq!(json["mime_type"], "image/png");
+    assert!(json.get("result").is_none());
+}
+
+#[tokio::test]
+async fn test_data_uri_reports_declared_and_detected_types() {
+    let (status, json) = post_json(
+        &default_router(),
+        "/v1/magic/data-uri?filename=scan.pdf",
+        Body::from("data:image/png;base64,JVBERi0xLjQ="),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["filename"], "scan.pdf");
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["verdict"]["expected"], "image/png");
+    assert_eq!(json["verdict"]["detected"], "application/pdf");
+    assert_eq!(json["verdict"]["matches"], false);
+}
+
+#[tokio::test]
+async fn test_malformed_data_uri_is_rejected() {
+    let (status, json) = post_json(
+        &default_router(),
+        "/v1/magic/data-uri",
+        Body::from("data:application/pdf;base64,%%%"),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "invalid_data_uri");
+}
+
+async fn compare(a: &str, b: &str) -> (StatusCode, serde_json::Value) {
+    let response = default_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/compare")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::json!({ "a": a, "b": b }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_compare_reports_same_type() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("one.pdf"), b"%PDF-1.4").unwrap();
+    std::fs::write(dir.path().join("two.pdf"), b"%PDF-1.7").unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+
+    let (status, json) = compare(&format!("{}/one.pdf", name), &format!("{}/two.pdf", name)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["a"]["mime_type"], "application/pdf");
+    assert_eq!(json["b"]["mime_type"], "application/pdf");
+    assert_eq!(json["same_type"], true);
+}
+
+#[tokio::test]
+async fn test_compare_reports_different_types() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("doc.pdf"), b"%PDF-1.4").unwrap();
+    std::fs::write(dir.path().join("image.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+
+    let (status, json) = compare(&format!("{}/doc.pdf", name), &format!("{}/image.png", name)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["a"]["mime_type"], "application/pdf");
+    assert_eq!(json["b"]["mime_type"], "image/png");
+    assert_eq!(json["same_type"], false);
+}
+
+#[tokio::test]
+async fn test_compare_fails_when_a_file_is_missing() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("doc.pdf"), b"%PDF-1.4").unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+
+    let (status, json) = compare(&format!("{}/doc.pdf", name), &format!("{}/missing.pdf", name)).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(json.get("same_type").is_none());
+}
+
+#[tokio::test]
+async fn test_analyze_path_follow_symlinks_overrides_deny_symlinks() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("real.pdf"), b"%PDF-1.4").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real.pdf"), dir.path().join("link.pdf")).unwrap();
+    let sandbox = Arc::new(PathSandbox::new(dir.path().to_path_buf()).with_deny_symlinks(true));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        sandbox,
+        temp_storage,
+        Arc::new(FakeAuth),
+        config,
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let (status, _) = post_json(&router, "/v1/magic/path?filename=link.pdf&path=link.pdf", Body::empty()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, json) = post_json(
+        &router,
+        "/v1/magic/path?filename=link.pdf&path=link.pdf&follow_symlinks=true",
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_path_history_lists_stored_analyses_of_a_path() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+    let router = default_router();
+    let uri = format!("/v1/magic/path?filename=report.pdf&path={}/report.pdf&store=true", name);
+
+    std::fs::write(dir.path().join("report.pdf"), b"%PDF-1.4").unwrap();
+    let (status, json) = post_json(&router, &uri, Body::empty()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["result_url"].as_str().unwrap().starts_with("/v1/results/"));
+    std::fs::write(dir.path().join("report.pdf"), b"\x89PNG\r\n\x1a\n").unwrap();
+    let (status, _) = post_json(&router, &uri, Body::empty()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, json) = send_json(
+        &router,
+        "GET",
+        &format!("/v1/results/by-path?path={}/report.pdf", name),
+        Body::empty(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["path"], format!("{}/report.pdf", name));
+    let history = json["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["mime_type"], "application/pdf");
+    assert_eq!(history[1]["mime_type"], "image/png");
+    assert_ne!(history[0]["content_id"], history[1]["content_id"]);
+}
+
+#[tokio::test]
+async fn test_analyze_path_if_modified_since_follows_symlinks() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("real.pdf"), b"%PDF-1.4").unwrap();
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+    std::fs::File::options()
+        .write(true)
+        .open(dir.path().join("real.pdf"))
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real.pdf"), dir.path().join("link.pdf")).unwrap();
+    let sandbox = Arc::new(PathSandbox::new(dir.path().to_path_buf()).with_deny_symlinks(true));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        sandbox,
+        temp_storage,
+        Arc::new(FakeAuth),
+        config,
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    // Answered from a stat of the symlink target, without analysis.
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/magic/path?filename=link.pdf&path=link.pdf&follow_symlinks=true")
+        .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+        .header("If-Modified-Since", "Mon, 01 Jan 2024 00:00:00 GMT")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["last-modified"], "Mon, 01 Jan 2024 00:00:00 GMT");
+}
+
+static COUNTED_OPENS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn counting_open(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    COUNTED_OPENS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    std::fs::File::open(path)
+}
+
+/// Router over a sandbox at `dir` whose path analyses open files through
+/// [`counting_open`].
+fn counting_open_router(dir: &std::path::Path) -> axum::Router {
+    let mut state = AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(dir.to_path_buf())),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default()),
+        noop_metrics(),
+    );
+    state.analyze_path_use_case = magicer::application::use_cases::analyze_path::AnalyzePathUseCase::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(dir.to_path_buf())),
+        5,
+    )
+    .with_open(counting_open);
+    create_router(Arc::new(state))
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id))
+}
+
+#[tokio::test]
+async fn test_analyze_path_if_modified_since_opens_file_once() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("doc.pdf"), b"%PDF-1.4").unwrap();
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+    std::fs::File::options()
+        .write(true)
+        .open(dir.path().join("doc.pdf"))
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+    let router = counting_open_router(dir.path());
+    let request = |since: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/magic/path?filename=doc.pdf&path=doc.pdf")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+            .header("If-Modified-Since", since)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Unchanged: answered from the descriptor's metadata alone.
+    let opens_before = COUNTED_OPENS.load(std::sync::atomic::Ordering::SeqCst);
+    let response = router
+        .clone()
+        .oneshot(request("Mon, 01 Jan 2024 00:00:00 GMT"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(COUNTED_OPENS.load(std::sync::atomic::Ordering::SeqCst) - opens_before, 1);
+
+    // Changed: the same descriptor is analyzed, not a second open.
+    let opens_before = COUNTED_OPENS.load(std::sync::atomic::Ordering::SeqCst);
+    let response = router
+        .oneshot(request("Sun, 31 Dec 2023 00:00:00 GMT"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(COUNTED_OPENS.load(std::sync::atomic::Ordering::SeqCst) - opens_before, 1);
+}
+
+#[tokio::test]
+async fn test_analyze_path_if_modified_since_rejects_directory() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::File::open(dir.path().join("sub"))
+        .unwrap()
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200))
+        .unwrap();
+    let router = counting_open_router(dir.path());
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=sub&path=sub")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("If-Modified-Since", "Mon, 01 Jan 2024 00:00:00 GMT")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error(), "{}", response.status());
+}
+
+#[tokio::test]
+async fn test_is_text_checks_sandbox_path_on_get() {
+    let dir = tempfile::tempdir_in("/tmp").unwrap();
+    std::fs::write(dir.path().join("script.sh"), "#!/bin/sh\n").unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+
+    let (status, json) = send_json(&default_router(), "GET", &format!("/v1/magic/is-text?path={}/script.sh", name), Body::empty()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["is_text"], true);
+}
+
+async fn endpoint_status(router: &axum::Router, method: &str, uri: &str, body: &'static str) -> StatusCode {
+    router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_disabled_content_endpoints_return_404() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.endpoints.content_enabled = false;
+    let router = router_with_config(config);
+    let file = tempfile::Builder::new().suffix(".pdf").tempfile_in("/tmp").unwrap();
+    std::fs::write(file.path(), b"%PDF-1.4").unwrap();
+    let name = file.path().file_name().unwrap().to_str().unwrap();
+
+    for uri in ["/v1/magic/content?filename=test.pdf", "/v1/magic/content/test.pdf", "/v1/upload?filename=test.pdf"] {
+        assert_eq!(endpoint_status(&router, "POST", uri, "%PDF-1.4").await, StatusCode::NOT_FOUND, "{}", uri);
+    }
+    let uri = format!("/v1/magic/path?filename=test.pdf&path={}", name);
+    assert_eq!(endpoint_status(&router, "POST", &uri, "").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_disabled_path_and_batch_endpoints_return_404() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.endpoints.path_enabled = false;
+    config.endpoints.batch_enabled = false;
+    let router = router_with_config(config);
+
+    let path_status = endpoint_status(&router, "POST", "/v1/magic/path?filename=test.pdf&path=test.pdf", "").await;
+    assert_eq!(path_status, StatusCode::NOT_FOUND);
+    let batch_status = endpoint_status(&router, "POST", "/v1/magic/paths", r#"{"paths":["test.pdf"]}"#).await;
+    assert_eq!(batch_status, StatusCode::NOT_FOUND);
+    let content_status = endpoint_status(&router, "POST", "/v1/magic/content?filename=test.pdf", "%PDF-1.4").await;
+    assert_eq!(content_status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_echo_content_returns_small_body() {
+    use base64::Engine as _;
+    use std::io::Read;
+
+    let router = default_router();
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=doc.pdf&echo_content=true", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    assert_eq!(json["content"]["encoding"], "identity");
+    assert_eq!(json["content"]["data"], "JVBERi0xLjQ=");
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=doc.pdf&echo_content=true&echo_gzip=true", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["content"]["encoding"], "gzip");
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(json["content"]["data"].as_str().unwrap())
+        .unwrap();
+    let mut echoed = String::new();
+    flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut echoed).unwrap();
+    assert_eq!(echoed, "%PDF-1.4");
+
+    let (_, json) = post_json(&router, "/v1/magic/content?filename=doc.pdf", Body::from("%PDF-1.4")).await;
+    assert!(json.get("content").is_none());
+}
+
+#[tokio::test]
+async fn test_echo_content_rejects_body_over_cap() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.max_echo_bytes = 4;
+    let router = router_with_config(config);
+
+    // Declared too large up front, and discovered too large while reading.
+    for content_length in [Some("8"), None] {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/v1/magic/content?filename=doc.pdf&echo_content=true")
+            .header("Authorization", "Basic YWRtaW46c2VjcmV0");
+        if let Some(length) = content_length {
+            request = request.header("Content-Length", length);
+        }
+        let response = router.clone().oneshot(request.body(Body::from("%PDF-1.4")).unwrap()).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(json["code"], "echo_too_large");
+    }
+}
+
+#[tokio::test]
+async fn test_echo_overflow_is_neither_published_nor_stored() {
+    let sink = Arc::new(RecordingSink(std::sync::Mutex::new(Vec::new())));
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.max_echo_bytes = 4;
+    let state = AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    )
+    .with_result_sink(sink.clone());
+    let router = create_router(Arc::new(state))
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=doc.pdf&echo_content=true&store=true")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(sink.0.lock().unwrap().is_empty());
+
+    let hash = magicer::application::use_cases::analyze_content::sha256_hex(b"%PDF-1.4");
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(format!("/v1/results/{}", hash))
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_result_signature_verifies_against_canonical_payload() {
+    use hmac::{Hmac, Mac};
+    use magicer::infrastructure::integrations::result_signature::canonical_json;
+
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.integrations.result_signing_key = Some("audit-key".to_string());
+    let router = router_with_config(config);
+
+    for uri in ["/v1/magic/content?filename=doc.pdf", "/v1/magic/content?filename=doc.pdf&fields=mime_type"] {
+        let (status, mut json) = post_json(&router, uri, Body::from("%PDF-1.4")).await;
+        assert_eq!(status, StatusCode::OK);
+        let signature = json.as_object_mut().unwrap().remove("signature").unwrap();
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"audit-key").unwrap();
+        mac.update(canonical_json(&json).as_bytes());
+        let expected: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(signature, format!("sha256={}", expected), "{}", uri);
+    }
+
+    let (_, json) = post_json(&default_router(), "/v1/magic/content?filename=doc.pdf", Body::from("%PDF-1.4")).await;
+    assert!(json.get("signature").is_none());
+}
+
+#[tokio::test]
+async fn test_expected_type_match_reports_verdict() {
+    let (status, json) = post_json(&default_router(), "/v1/magic/content?filename=doc.pdf&expected=Application/PDF", Body::from("%PDF-1.4")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["verdict"]["detected"], "application/pdf");
+    assert_eq!(json["verdict"]["expected"], "application/pdf");
+    assert_eq!(json["verdict"]["matches"], true);
+}
+
+#[tokio::test]
+async fn test_expected_type_mismatch_is_reported_when_lenient() {
+    let (status, json) = post_json(&default_router(), "/v1/magic/content?filename=doc.pdf&expected=image/png", Body::from("%PDF-1.4")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["verdict"]["detected"], "application/pdf");
+    assert_eq!(json["verdict"]["expected"], "image/png");
+    assert_eq!(json["verdict"]["matches"], false);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_expected_type_mismatch_is_rejected_when_strict() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.strict_expected = true;
+    let router = router_with_config(config);
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=doc.pdf&expected=image/png", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["code"], "type_mismatch");
+
+    let (status, json) = post_json(&router, "/v1/magic/content?filename=doc.pdf&expected=application/pdf", Body::from("%PDF-1.4")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["verdict"]["matches"], true);
+}
+
+#[tokio::test]
+async fn test_daily_quota_rejects_past_limit() {
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.auth.quota.daily_limit = 2;
+    let router = router_with_config(config);
+    let uri = "/v1/magic/content?filename=a.pdf";
+
+    // A failed request does not use up the quota.
+    let (rejected, _) = post_json(&router, "/v1/magic/content?filename=a/b.pdf", Body::from("%PDF-1.4")).await;
+    let (first, _) = post_json(&router, uri, Body::from("%PDF-1.4")).await;
+    let (second, _) = post_json(&router, uri, Body::from("%PDF-1.4")).await;
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(rejected, StatusCode::BAD_REQUEST);
+    assert_eq!(first, StatusCode::OK);
+    assert_eq!(second, StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=86_400).contains(&retry_after));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "quota_exceeded");
+}
+
+#[tokio::test]
+async fn test_large_upload_analyzes_head_unless_full_requested() {
+    // A directory of its own: the fake appends to files named by a counter.
+    let temp_dir = std::env::temp_dir().join(format!("magicer_head_{}", uuid::Uuid::new_v4()));
+    let temp_storage = Arc::new(FakeTempStorageService::new(temp_dir.clone()));
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.large_file_threshold_mb = 0;
+    config.analysis.head_bytes = 8;
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        temp_storage.clone(),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+    let mut content = b"%PDF-1.4".to_vec();
+    content.extend(std::iter::repeat_n(b'x', 4096));
+
+    let upload = |uri: &'static str| {
+        let router = router.clone();
+        let content = content.clone();
+        async move {
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                        .header("Transfer-Encoding", "chunked")
+                        .body(Body::from(content))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+        }
+    };
+
+    temp_storage.reset();
+    let (head_status, head) = upload("/v1/magic/content?filename=a.pdf").await;
+    let head_temp_files = temp_storage.counter();
+    let (full_status, full) = upload("/v1/magic/content?filename=a.pdf&full=true").await;
+
+    assert_eq!(head_status, StatusCode::OK);
+    assert_eq!(head["result"]["mime_type"], "application/pdf");
+    assert_eq!(head["result"]["range"], serde_json::json!({"start": 0, "end": 7}));
+    assert_eq!(head_temp_files, 0);
+    assert_eq!(full_status, StatusCode::OK);
+    assert_eq!(full["result"]["mime_type"], "application/pdf");
+    assert!(full["result"].get("range").is_none());
+    assert!(temp_storage.counter() > 0);
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
+#[tokio::test]
+async fn test_if_content_hash_reads_whole_large_upload() {
+    let temp_dir = std::env::temp_dir().join(format!("magicer_head_hash_{}", uuid::Uuid::new_v4()));
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.analysis.large_file_threshold_mb = 0;
+    config.analysis.head_bytes = 8;
+    config.analysis.content_hash_cache_entries = 16;
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(temp_dir.clone())),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+    let mut content = b"%PDF-1.4".to_vec();
+    content.extend(std::iter::repeat_n(b'x', 4096));
+    let hash = magicer::application::use_cases::analyze_content::sha256_hex(&content);
+
+    let upload = || {
+        let router = router.clone();
+        let content = content.clone();
+        let hash = hash.clone();
+        async move {
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/v1/magic/content?filename=a.pdf")
+                        .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                        .header("Transfer-Encoding", "chunked")
+                        .header("If-Content-Hash", hash)
+                        .body(Body::from(content))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+        }
+    };
+
+    let (primed_status, primed) = upload().await;
+    let (status, json) = upload().await;
+
+    assert_eq!(primed_status, StatusCode::OK);
+    assert!(primed["result"].get("range").is_none());
+    assert!(primed["result"].get("cached").is_none());
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["result"]["cached"], true);
+    assert_eq!(json["result"]["mime_type"], "application/pdf");
+    let _ = std::fs::remove_dir_all(temp_dir);
+}
+
+#[tokio::test]
+async fn test_rejected_description_returns_422() {
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_mapping(b"-----BEGIN PGP MESSAGE", "application/pgp-encrypted", "PGP Encrypted message")
+            .with_defaults()
+            .build(),
+    );
+    let mut config = magicer::infrastructure::config::server_config::ServerConfig::default();
+    config.magic.reject_descriptions = vec!["encrypted".to_string(), "corrupt".to_string()];
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        noop_metrics(),
+    ));
+    let router = create_router(state)
+        .layer(middleware::from_fn(error_handler::handle_error))
+        .layer(middleware::from_fn(request_id::add_request_id));
+
+    let (rejected_status, rejected) = post_json(&router, "/v1/magic/content?filename=a.asc", Body::from("-----BEGIN PGP MESSAGE-----")).await;
+    let (accepted_status, accepted) = post_json(&router, "/v1/magic/content?filename=a.pdf", Body::from("%PDF-1.4")).await;
+
+    assert_eq!(rejected_status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(rejected["code"], "rejected_content");
+    assert_eq!(accepted_status, StatusCode::OK);
+    assert_eq!(accepted["result"]["mime_type"], "application/pdf");
+}
@@ -1,2 +1,3 @@
+pub mod admin_handlers_tests;
 pub mod health_handlers_tests;
 pub mod magic_handlers_tests;
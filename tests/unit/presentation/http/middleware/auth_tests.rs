@@ -3,14 +3,14 @@ use axum::{
     http::{Request, StatusCode},
     middleware::from_fn_with_state,
     routing::get,
-    Router,
+    Extension, Router,
 };
 use magicer::presentation::http::middleware::auth::require_auth;
 use magicer::presentation::state::app_state::AppState;
 use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
 use magicer::infrastructure::filesystem::sandbox::PathSandbox;
 use magicer::domain::services::authentication_service::AuthenticationService;
-use magicer::domain::value_objects::auth::BasicAuthCredentials;
+use magicer::domain::value_objects::auth::{BasicAuthCredentials, Principal};
 use magicer::domain::errors::AuthenticationError;
 use tower::ServiceExt;
 use std::sync::Arc;
@@ -99,3 +99,208 @@ async fn test_auth_middleware_valid_credentials() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_auth_middleware_bearer_unsupported_by_basic_service() {
+    let auth_service = Arc::new(FakeAuthService { valid_user: "u".into(), valid_pass: "p".into() });
+    let app = build_app(auth_service).await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header("Authorization", "Bearer some-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+struct FakeTokenAuth;
+
+impl AuthenticationService for FakeTokenAuth {
+    fn verify_credentials<'a>(&'a self, _credentials: &'a BasicAuthCredentials) -> BoxFuture<'a, Result<(), AuthenticationError>> {
+        Box::pin(async { Err(AuthenticationError::InvalidCredentials) })
+    }
+
+    fn authorize<'a>(&'a self, token: &'a str) -> BoxFuture<'a, Result<Principal, AuthenticationError>> {
+        Box::pin(async move {
+            if token == "good-token" {
+                Ok(Principal::new("svc-client", vec!["analyze".to_string()]))
+            } else if token == "sandbox-token" {
+                Ok(Principal::new("svc-reader", vec!["read_sandbox".to_string()]))
+            } else if token == "backend-down" {
+                Err(AuthenticationError::InternalError("introspection endpoint unreachable".to_string()))
+            } else {
+                Err(AuthenticationError::InvalidCredentials)
+            }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_auth_middleware_bearer_dispatches_to_authorize() {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let metrics = Arc::new(magicer::infrastructure::telemetry::metrics::AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, Arc::new(FakeTokenAuth), config, metrics));
+    let app = Router::new()
+        .route("/", get(|Extension(principal): Extension<Principal>| async move { principal.username().to_string() }))
+        .layer(from_fn_with_state(state, require_auth));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header("Authorization", "Bearer good-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"svc-client");
+}
+
+#[tokio::test]
+async fn test_auth_backend_failure_returns_503_not_401() {
+    let send = |token: &'static str| async move {
+        scoped_router()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/magic/content?filename=test.pdf")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::from("%PDF-1.4"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+
+    let response = send("backend-down").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "auth_unavailable");
+    assert!(json["request_id"].is_string());
+
+    assert_eq!(send("wrong-token").await.status(), StatusCode::UNAUTHORIZED);
+}
+
+fn scoped_router() -> Router {
+    let magic_repo = Arc::new(FakeMagicRepository::new().unwrap());
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let config = Arc::new(magicer::infrastructure::config::server_config::ServerConfig::default());
+    let metrics = Arc::new(magicer::infrastructure::telemetry::metrics::AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(magic_repo, sandbox, temp_storage, Arc::new(FakeTokenAuth), config, metrics));
+    magicer::presentation::http::router::create_router(state)
+        .layer(axum::middleware::from_fn(magicer::presentation::http::middleware::request_id::add_request_id))
+}
+
+#[tokio::test]
+async fn test_missing_scope_returns_forbidden() {
+    // FakeTokenAuth grants only `analyze`; path analysis needs `read_sandbox`.
+    let response = scoped_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=test.pdf&path=test.pdf")
+                .header("Authorization", "Bearer good-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "insufficient_scope");
+}
+
+#[tokio::test]
+async fn test_granted_scope_allows_request() {
+    let response = scoped_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Bearer good-token")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_trace_without_admin_scope_returns_forbidden() {
+    // FakeTokenAuth grants `analyze`, enough for the content but not a trace.
+    let response = scoped_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&trace=true")
+                .header("Authorization", "Bearer good-token")
+                .body(Body::from("%PDF-1.4\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "insufficient_scope");
+}
+
+#[tokio::test]
+async fn test_hexdump_without_admin_scope_returns_forbidden() {
+    let response = scoped_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf&hexdump=true")
+                .header("Authorization", "Bearer good-token")
+                .body(Body::from("%PDF-1.4\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "insufficient_scope");
+}
+
+#[tokio::test]
+async fn test_follow_symlinks_without_admin_scope_returns_forbidden() {
+    let response = scoped_router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/path?filename=test.pdf&path=test.pdf&follow_symlinks=true")
+                .header("Authorization", "Bearer sandbox-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "insufficient_scope");
+}
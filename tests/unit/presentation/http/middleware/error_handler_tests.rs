@@ -43,3 +43,32 @@ async fn test_error_handler_ignores_success() {
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), 0);
 }
+
+#[tokio::test]
+async fn test_error_handler_renders_problem_json_when_accepted() {
+    let app = Router::new()
+        .route("/error", get(|| async { StatusCode::BAD_REQUEST }))
+        .layer(from_fn(handle_error))
+        .layer(from_fn(add_request_id));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/error")
+                .header("Accept", "application/problem+json, application/json;q=0.5")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["content-type"], "application/problem+json");
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["type"], "about:blank");
+    assert_eq!(json["title"], "Bad Request");
+    assert_eq!(json["status"], 400);
+    assert_eq!(json["detail"], "Bad Request");
+    assert!(json["instance"].is_string());
+}
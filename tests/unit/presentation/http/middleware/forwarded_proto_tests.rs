@@ -0,0 +1,100 @@
+use crate::fake_auth::FakeAuth;
+use crate::fake_temp_storage::FakeTempStorageService;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, StatusCode},
+    middleware::from_fn,
+};
+use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::filesystem::sandbox::PathSandbox;
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::infrastructure::telemetry::metrics::AppMetrics;
+use magicer::presentation::http::middleware::connection_limits::ConnectionInfo;
+use magicer::presentation::http::middleware::{error_handler, request_id};
+use magicer::presentation::http::router::create_router;
+use magicer::presentation::state::app_state::AppState;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn https_only_router(trusted_proxies: Vec<std::net::IpAddr>) -> axum::Router {
+    let mut config = ServerConfig::default();
+    config.server.require_forwarded_proto_https = true;
+    config.server.trusted_proxies = trusted_proxies;
+    let metrics = Arc::new(AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(
+        Arc::new(FakeMagicRepository::new().unwrap()),
+        Arc::new(PathSandbox::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp"))),
+        Arc::new(FakeAuth),
+        Arc::new(config),
+        metrics,
+    ));
+    create_router(state)
+        .layer(from_fn(error_handler::handle_error))
+        .layer(from_fn(request_id::add_request_id))
+}
+
+fn analyze_request(forwarded_proto: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri("/v1/magic/content?filename=test.pdf")
+        .header("Authorization", "Basic YWRtaW46c2VjcmV0");
+    if let Some(proto) = forwarded_proto {
+        builder = builder.header("x-forwarded-proto", proto);
+    }
+    builder.body(Body::from("%PDF-1.4")).unwrap()
+}
+
+#[tokio::test]
+async fn test_request_forwarded_over_https_is_served() {
+    let response = https_only_router(Vec::new())
+        .oneshot(analyze_request(Some("https")))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_plaintext_request_is_rejected_with_426() {
+    let router = https_only_router(Vec::new());
+    for proto in [None, Some("http")] {
+        let response = router.clone().oneshot(analyze_request(proto)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UPGRADE_REQUIRED);
+        assert!(response.headers().contains_key("upgrade"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "https_required");
+    }
+}
+
+#[tokio::test]
+async fn test_forwarded_proto_from_untrusted_peer_is_ignored() {
+    let router = https_only_router(vec!["10.0.0.1".parse().unwrap()]);
+    let from = |peer: &str| {
+        let mut request = analyze_request(Some("https"));
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(ConnectionInfo::new(peer.parse().unwrap())));
+        request
+    };
+
+    let trusted = router.clone().oneshot(from("10.0.0.1:4000")).await.unwrap();
+    let untrusted = router.oneshot(from("192.0.2.7:4000")).await.unwrap();
+
+    assert_eq!(trusted.status(), StatusCode::OK);
+    assert_eq!(untrusted.status(), StatusCode::UPGRADE_REQUIRED);
+}
+
+#[tokio::test]
+async fn test_health_probe_does_not_need_forwarding() {
+    let response = https_only_router(Vec::new())
+        .oneshot(Request::builder().uri("/v1/ping").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
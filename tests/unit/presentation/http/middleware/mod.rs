@@ -1,3 +1,5 @@
 pub mod request_id_tests;
 pub mod auth_tests;
 pub mod error_handler_tests;
+pub mod request_timeout_tests;
+pub mod forwarded_proto_tests;
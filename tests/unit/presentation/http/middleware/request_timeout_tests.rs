@@ -0,0 +1,65 @@
+use crate::fake_auth::FakeAuth;
+use crate::fake_temp_storage::FakeTempStorageService;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::{from_fn, from_fn_with_state},
+};
+use magicer::infrastructure::config::server_config::ServerConfig;
+use magicer::infrastructure::filesystem::sandbox::PathSandbox;
+use magicer::infrastructure::magic::fake_magic_repository::FakeMagicRepository;
+use magicer::infrastructure::telemetry::metrics::AppMetrics;
+use magicer::presentation::http::middleware::request_timeout::enforce_request_timeout;
+use magicer::presentation::http::middleware::{error_handler, request_id};
+use magicer::presentation::http::router::create_router;
+use magicer::presentation::state::app_state::AppState;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn test_request_timeout_bounds_slow_pipeline() {
+    let magic_repo = Arc::new(
+        FakeMagicRepository::builder()
+            .with_defaults()
+            .with_delay(Duration::from_secs(10))
+            .build(),
+    );
+    let sandbox = Arc::new(PathSandbox::new(PathBuf::from("/tmp")));
+    let temp_storage = Arc::new(FakeTempStorageService::new(PathBuf::from("/tmp")));
+    let metrics = Arc::new(AppMetrics::new(&opentelemetry::global::meter("test")));
+    let state = Arc::new(AppState::new(
+        magic_repo,
+        sandbox,
+        temp_storage,
+        Arc::new(FakeAuth),
+        Arc::new(ServerConfig::default()),
+        metrics,
+    ));
+    let app = create_router(state)
+        .layer(from_fn(error_handler::handle_error))
+        .layer(from_fn(request_id::add_request_id))
+        .layer(from_fn_with_state(Duration::from_secs(1), enforce_request_timeout));
+
+    let start = Instant::now();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/magic/content?filename=test.pdf")
+                .header("Authorization", "Basic YWRtaW46c2VjcmV0")
+                .header("x-request-id", "slow-request")
+                .body(Body::from("%PDF-1.4"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["code"], "request_timeout_exceeded");
+    assert_eq!(json["request_id"], "slow-request");
+}